@@ -0,0 +1,41 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use gamescope_x11_client::xwayland::XWayland;
+
+/// Walks the window tree with one query_tree round trip per window, like
+/// get_all_windows did before it was pipelined
+fn get_all_windows_sequential(xwayland: &XWayland, window_id: u32) -> Vec<u32> {
+    let mut windows = Vec::new();
+    for child in xwayland.get_window_children(window_id).unwrap_or_default() {
+        windows.push(child);
+        windows.append(&mut get_all_windows_sequential(xwayland, child));
+    }
+
+    windows
+}
+
+fn bench_window_tree(c: &mut Criterion) {
+    // Needs a running X server, E.g. DISPLAY=:1 cargo bench
+    let display = std::env::var("DISPLAY").unwrap_or_else(|_| ":0".into());
+    let xwayland = match XWayland::new(display.clone()).connect() {
+        Ok(xwayland) => xwayland,
+        Err(err) => {
+            eprintln!("Skipping window tree benchmark, unable to connect to {display}: {err}");
+            return;
+        }
+    };
+    let Ok(root) = xwayland.get_root_window_id() else {
+        return;
+    };
+
+    let mut group = c.benchmark_group("get_all_windows");
+    group.bench_function("sequential", |b| {
+        b.iter(|| get_all_windows_sequential(&xwayland, root))
+    });
+    group.bench_function("pipelined", |b| {
+        b.iter(|| xwayland.get_all_windows(root).unwrap_or_default())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_window_tree);
+criterion_main!(benches);
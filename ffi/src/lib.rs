@@ -0,0 +1,4 @@
+//! C library of gamescope-x11-client. Builds `libgamescope_x11.so` exporting
+//! the functions declared in `include/gamescope_x11.h`.
+
+pub use gamescope_x11_client::ffi::*;
@@ -0,0 +1,47 @@
+/*
+ * C interface of gamescope-x11-client, built as libgamescope_x11.so by the
+ * gamescope-x11-client-ffi crate in the ffi directory.
+ *
+ * Functions returning int return -1 on error. The message of the last error
+ * on the calling thread can be read with gamescope_x11_last_error().
+ */
+#ifndef GAMESCOPE_X11_H
+#define GAMESCOPE_X11_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct GamescopeX11 GamescopeX11;
+typedef struct GamescopeX11Subscription GamescopeX11Subscription;
+
+typedef void (*GamescopeX11PropertyCallback)(const char *name, void *user_data);
+
+const char *gamescope_x11_last_error(void);
+
+char **gamescope_x11_discover(size_t *count);
+void gamescope_x11_free_names(char **names, size_t count);
+
+GamescopeX11 *gamescope_x11_connect(const char *display);
+void gamescope_x11_free(GamescopeX11 *handle);
+int gamescope_x11_is_primary(const GamescopeX11 *handle);
+
+int gamescope_x11_set_fps_limit(const GamescopeX11 *handle, uint32_t fps);
+int gamescope_x11_get_fps_limit(const GamescopeX11 *handle, uint32_t *fps);
+int gamescope_x11_get_focused_app(const GamescopeX11 *handle, uint32_t *app_id);
+int gamescope_x11_get_baselayer_app_id(const GamescopeX11 *handle, uint32_t *app_id);
+int gamescope_x11_set_baselayer_app_id(const GamescopeX11 *handle, uint32_t app_id);
+
+GamescopeX11Subscription *gamescope_x11_subscribe(const GamescopeX11 *handle,
+                                                  GamescopeX11PropertyCallback callback,
+                                                  void *user_data);
+void gamescope_x11_unsubscribe(GamescopeX11Subscription *subscription);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* GAMESCOPE_X11_H */
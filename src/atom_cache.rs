@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{AtomEnum, ConnectionExt},
+};
+
+use crate::atoms::GamescopeAtom;
+
+/// [AtomCache] remembers the names and ids of atoms seen on a single
+/// connection so hot paths like event dispatch don't need a round trip to the
+/// X server for every event. Atoms never change for the lifetime of an X
+/// server, but differ between servers, so every connection has its own cache.
+#[derive(Debug, Default)]
+pub(crate) struct AtomCache {
+    names: RwLock<HashMap<u32, Arc<str>>>,
+    ids: RwLock<HashMap<Arc<str>, u32>>,
+    gamescope: RwLock<HashMap<GamescopeAtom, u32>>,
+}
+
+impl AtomCache {
+    /// Creates a cache holding every [GamescopeAtom] that already exists on
+    /// the X server of the given connection. All atoms are looked up together
+    /// like x11rb's atom_manager does, but only if they exist, so
+    /// [crate::x11::atom_exists] keeps reporting which features the running
+    /// Gamescope supports. Errors are logged and leave the cache empty.
+    pub fn preloaded<F>(conn: F) -> Self
+    where
+        F: Connection,
+    {
+        let cache = Self::default();
+        if let Err(err) = cache.preload(conn, GamescopeAtom::all()) {
+            log::warn!("Unable to intern Gamescope atoms: {}", err);
+        }
+
+        cache
+    }
+
+    /// Resolves all of the given Gamescope atoms that exist on the X server.
+    /// All requests are sent before any reply is read, so this only costs a
+    /// single round trip.
+    fn preload<F, I>(&self, conn: F, atoms: I) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: Connection,
+        I: IntoIterator<Item = GamescopeAtom>,
+    {
+        let mut cookies = Vec::new();
+        for atom in atoms {
+            let name = atom.to_string();
+            let cookie = conn.intern_atom(true, name.as_bytes())?;
+            cookies.push((atom, name, cookie));
+        }
+
+        for (atom, name, cookie) in cookies {
+            let id = cookie.reply()?.atom;
+            if id == u32::from(AtomEnum::NONE) {
+                continue;
+            }
+            self.insert(id, name.into());
+            if let Ok(mut ids) = self.gamescope.write() {
+                ids.insert(atom, id);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the name of the given atom, asking the X server only the first
+    /// time the atom is seen
+    pub fn name<F>(&self, conn: F, atom: u32) -> Result<Arc<str>, Box<dyn std::error::Error>>
+    where
+        F: Connection,
+    {
+        if let Some(name) = self
+            .names
+            .read()
+            .ok()
+            .and_then(|names| names.get(&atom).cloned())
+        {
+            return Ok(name);
+        }
+
+        let name = conn.get_atom_name(atom)?.reply()?.name;
+        let name: Arc<str> = String::from_utf8(name)?.into();
+        self.insert(atom, name.clone());
+
+        Ok(name)
+    }
+
+    /// Returns the id of the atom with the given name, interning it on the X
+    /// server only the first time it is requested
+    pub fn id<F>(&self, conn: F, name: &str) -> Result<u32, Box<dyn std::error::Error>>
+    where
+        F: Connection,
+    {
+        if let Some(atom) = self.ids.read().ok().and_then(|ids| ids.get(name).copied()) {
+            return Ok(atom);
+        }
+
+        let atom = conn.intern_atom(false, name.as_bytes())?.reply()?.atom;
+        self.insert(atom, name.into());
+
+        Ok(atom)
+    }
+
+    /// Returns the id of the given Gamescope atom, resolving it only the first
+    /// time it is requested. Unlike [AtomCache::id], this does not need to
+    /// format the name of the atom, so it is cheap enough to call for every
+    /// event.
+    pub fn resolve<F>(
+        &self,
+        conn: F,
+        atom: GamescopeAtom,
+    ) -> Result<u32, Box<dyn std::error::Error>>
+    where
+        F: Connection,
+    {
+        if let Some(id) = self
+            .gamescope
+            .read()
+            .ok()
+            .and_then(|ids| ids.get(&atom).copied())
+        {
+            return Ok(id);
+        }
+
+        let id = self.id(conn, atom.to_string().as_str())?;
+        if let Ok(mut ids) = self.gamescope.write() {
+            ids.insert(atom, id);
+        }
+
+        Ok(id)
+    }
+
+    /// Returns true if the given atom id is one of the given Gamescope atoms
+    pub fn matches<F>(
+        &self,
+        conn: F,
+        id: u32,
+        atoms: &[GamescopeAtom],
+    ) -> Result<bool, Box<dyn std::error::Error>>
+    where
+        F: Connection,
+    {
+        for atom in atoms {
+            if self.resolve(&conn, *atom)? == id {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Remembers the given atom in both directions
+    fn insert(&self, atom: u32, name: Arc<str>) {
+        if let Ok(mut ids) = self.ids.write() {
+            ids.insert(name.clone(), atom);
+        }
+        if let Ok(mut names) = self.names.write() {
+            names.insert(atom, name);
+        }
+    }
+}
@@ -1,7 +1,43 @@
-#[derive(Debug, strum_macros::Display)]
+use std::fmt;
+use std::sync::Arc;
+
+use strum::IntoEnumIterator;
+use x11rb::{connection::Connection, protocol::xproto::ConnectionExt};
+
+/// X11 type of the values stored in a Gamescope property
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AtomType {
+    /// One or more numbers
+    Cardinal,
+    /// One or more window ids
+    Window,
+    /// A string
+    String,
+}
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum_macros::Display,
+    strum_macros::EnumString,
+    strum_macros::EnumIter,
+)]
 pub enum GamescopeAtom {
     #[strum(serialize = "_NET_WM_PID")]
     NetWmPID,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE")]
+    NetWmWindowType,
+    #[strum(serialize = "_NET_FRAME_EXTENTS")]
+    NetFrameExtents,
+    #[strum(serialize = "_GTK_FRAME_EXTENTS")]
+    GtkFrameExtents,
+    #[strum(serialize = "_MOTIF_WM_HINTS")]
+    MotifWmHints,
     #[strum(serialize = "STEAM_BIGPICTURE")]
     Steam,
     #[strum(serialize = "GAMESCOPE_INPUT_COUNTER")]
@@ -16,6 +52,8 @@ pub enum GamescopeAtom {
     FocusableApps,
     #[strum(serialize = "GAMESCOPE_FOCUSABLE_WINDOWS")]
     FocusableWindows,
+    #[strum(serialize = "GAMESCOPE_FOCUS_DISPLAY")]
+    FocusDisplay,
     #[strum(serialize = "GAMESCOPE_KEYBOARD_FOCUS_DISPLAY")]
     KeyboardFocusDisplay,
     #[strum(serialize = "GAMESCOPE_CURSOR_VISIBLE_FEEDBACK")]
@@ -30,12 +68,74 @@ pub enum GamescopeAtom {
     BlurRadius,
     #[strum(serialize = "GAMESCOPE_ALLOW_TEARING")]
     AllowTearing,
+    #[strum(serialize = "GAMESCOPE_FORCE_WINDOWS_FULLSCREEN")]
+    ForceWindowsFullscreen,
+    #[strum(serialize = "GAMESCOPE_SCALING_FILTER")]
+    ScalingFilter,
+    #[strum(serialize = "GAMESCOPE_FSR_SHARPNESS")]
+    FSRSharpness,
+    #[strum(serialize = "GAMESCOPE_FSR_FEEDBACK")]
+    FSRFeedback,
+    #[strum(serialize = "GAMESCOPE_XWAYLAND_MODE_CONTROL")]
+    XWaylandModeControl,
+    #[strum(serialize = "GAMESCOPE_XWAYLAND_SERVER_ID")]
+    XWaylandServerId,
+    #[strum(serialize = "GAMESCOPE_DISPLAY_IS_EXTERNAL")]
+    DisplayIsExternal,
+    #[strum(serialize = "GAMESCOPE_DISPLAY_REFRESH_RATE_FEEDBACK")]
+    DisplayRefreshRateFeedback,
+    #[strum(serialize = "GAMESCOPE_DYNAMIC_REFRESH")]
+    DynamicRefresh,
+    #[strum(serialize = "GAMESCOPE_VRR_CAPABLE")]
+    VRRCapable,
+    #[strum(serialize = "GAMESCOPE_VRR_ENABLED")]
+    VRREnabled,
+    #[strum(serialize = "GAMESCOPE_VRR_FEEDBACK")]
+    VRRFeedback,
+    #[strum(serialize = "GAMESCOPE_DISPLAY_SUPPORTS_HDR")]
+    DisplaySupportsHDR,
+    #[strum(serialize = "GAMESCOPE_DISPLAY_HDR_ENABLED")]
+    DisplayHDREnabled,
+    #[strum(serialize = "GAMESCOPE_HDR_OUTPUT_FEEDBACK")]
+    HDROutputFeedback,
+    #[strum(serialize = "GAMESCOPE_SDR_ON_HDR_CONTENT_BRIGHTNESS")]
+    SDROnHDRContentBrightness,
+    #[strum(serialize = "GAMESCOPE_HDR_ITM_ENABLE")]
+    HDRItmEnable,
+    #[strum(serialize = "GAMESCOPE_HDR_ITM_SDR_NITS")]
+    HDRItmSDRNits,
+    #[strum(serialize = "GAMESCOPE_HDR_ITM_TARGET_NITS")]
+    HDRItmTargetNits,
+    #[strum(serialize = "GAMESCOPE_COLOR_GAIN")]
+    ColorGain,
+    #[strum(serialize = "GAMESCOPE_COLOR_LINEARGAIN")]
+    ColorLinearGain,
+    #[strum(serialize = "GAMESCOPE_COLOR_LINEARGAIN_BLEND")]
+    ColorLinearGainBlend,
+    #[strum(serialize = "GAMESCOPE_COLOR_MATRIX")]
+    ColorMatrix,
+    #[strum(serialize = "GAMESCOPE_COLOR_SDR_GAMUT_WIDENESS")]
+    ColorSDRGamutWideness,
+    #[strum(serialize = "GAMESCOPE_COLOR_NIGHTMODE")]
+    ColorNightMode,
+    #[strum(serialize = "GAMESCOPE_COLOR_3DLUT_OVERRIDE")]
+    Color3DLutOverride,
+    #[strum(serialize = "GAMESCOPE_COLOR_SHAPERLUT_OVERRIDE")]
+    ColorShaperLutOverride,
     #[strum(serialize = "GAMESCOPECTRL_BASELAYER_WINDOW")]
     BaselayerWindow,
     #[strum(serialize = "GAMESCOPECTRL_BASELAYER_APPID")]
     BaselayerAppId,
     #[strum(serialize = "GAMESCOPECTRL_REQUEST_SCREENSHOT")]
     RequestScreenshot,
+    #[strum(serialize = "GAMESCOPECTRL_DEBUG_REQUEST_SCREENSHOT")]
+    DebugRequestScreenshot,
+    #[strum(serialize = "GAMESCOPECTRL_OVERVIEW")]
+    Overview,
+    #[strum(serialize = "GAMESCOPE_REQUESTED_REFRESH_RATE")]
+    RequestedRefreshRate,
+    #[strum(serialize = "GAMESCOPE_DISPLAY_EDID_PATH")]
+    DisplayEdidPath,
     #[strum(serialize = "STEAM_GAME")]
     SteamGame,
     #[strum(serialize = "STEAM_INPUT_FOCUS")]
@@ -44,6 +144,168 @@ pub enum GamescopeAtom {
     SteamOverlay,
     #[strum(serialize = "STEAM_NOTIFICATION")]
     SteamNotification,
+    #[strum(serialize = "STEAM_STREAMING_CLIENT")]
+    SteamStreamingClient,
+    #[strum(serialize = "STEAM_STREAMING_CLIENT_VIDEO")]
+    SteamStreamingClientVideo,
+}
+
+/// An atom interned on a specific X server with
+/// [crate::xwayland::XWayland::intern]. Atom ids differ between servers, so a
+/// handle must only be used with the instance it was interned on and has to be
+/// interned again after reconnecting to a restarted server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AtomHandle {
+    id: u32,
+    name: Arc<str>,
+}
+
+impl AtomHandle {
+    /// Create a handle for the given already interned atom
+    pub(crate) fn new(id: u32, name: Arc<str>) -> Self {
+        Self { id, name }
+    }
+
+    /// Returns the id of the atom on the X server
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the name of the atom (E.g. "STEAM_BIGPICTURE")
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl fmt::Display for AtomHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl GamescopeAtom {
+    /// Returns the known atom with the given X11 name (E.g.
+    /// "GAMESCOPE_FPS_LIMIT"), so the name of a property from a PropertyNotify
+    /// event can be mapped back to its variant
+    pub fn from_property_name(name: &str) -> Option<Self> {
+        name.parse().ok()
+    }
+
+    /// Returns an iterator over all known atoms
+    pub fn all() -> impl Iterator<Item = GamescopeAtom> {
+        Self::iter()
+    }
+
+    /// Returns the id of this atom on the X server of the given connection,
+    /// interning it if needed. Ids differ between servers but never change for
+    /// the lifetime of a server, so they can be resolved once per connection
+    /// and compared against the atoms reported in events.
+    pub fn resolve<F>(&self, conn: F) -> Result<u32, Box<dyn std::error::Error>>
+    where
+        F: Connection,
+    {
+        let name = self.to_string();
+        Ok(conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    }
+
+    /// Returns the type of the values stored in this property
+    pub fn atom_type(&self) -> AtomType {
+        match self {
+            GamescopeAtom::FocusedWindow
+            | GamescopeAtom::FocusableWindows
+            | GamescopeAtom::BaselayerWindow => AtomType::Window,
+            GamescopeAtom::KeyboardFocusDisplay
+            | GamescopeAtom::DisplayEdidPath
+            | GamescopeAtom::Color3DLutOverride
+            | GamescopeAtom::ColorShaperLutOverride => AtomType::String,
+            _ => AtomType::Cardinal,
+        }
+    }
+}
+
+/// [AtomKey] names a property either by a known [GamescopeAtom] or by the
+/// name of any other atom, so properties that this crate does not know about
+/// yet (E.g. from a newer Gamescope) can still be read and written
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AtomKey {
+    /// A property known to this crate
+    Known(GamescopeAtom),
+    /// Any other property by its X11 name
+    Custom(String),
+}
+
+impl AtomKey {
+    /// Returns the X11 name of the atom (E.g. "GAMESCOPE_FPS_LIMIT")
+    pub fn name(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for AtomKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtomKey::Known(atom) => atom.fmt(f),
+            AtomKey::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+impl From<GamescopeAtom> for AtomKey {
+    fn from(atom: GamescopeAtom) -> Self {
+        AtomKey::Known(atom)
+    }
+}
+
+/// Names of known atoms become [AtomKey::Known], so a property is handled the
+/// same way regardless of how it was named
+impl From<&str> for AtomKey {
+    fn from(name: &str) -> Self {
+        match GamescopeAtom::from_property_name(name) {
+            Some(atom) => AtomKey::Known(atom),
+            None => AtomKey::Custom(name.to_string()),
+        }
+    }
+}
+
+impl From<String> for AtomKey {
+    fn from(name: String) -> Self {
+        match GamescopeAtom::from_property_name(&name) {
+            Some(atom) => AtomKey::Known(atom),
+            None => AtomKey::Custom(name),
+        }
+    }
+}
+
+/// Atoms are serialized by their X11 name (E.g. "GAMESCOPE_FPS_LIMIT"), so
+/// config files refer to them the same way as xprop does
+#[cfg(feature = "serde")]
+impl serde::Serialize for GamescopeAtom {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for GamescopeAtom {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| serde::de::Error::custom(format!("Unknown atom: {}", name)))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for AtomKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for AtomKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(String::deserialize(deserializer)?.into())
+    }
 }
 
 #[cfg(test)]
@@ -56,5 +318,49 @@ mod tests {
             "GAMESCOPE_FOCUSABLE_WINDOWS",
             GamescopeAtom::FocusableWindows.to_string()
         );
+        assert_eq!(
+            "GAMESCOPE_FOCUSABLE_WINDOWS".parse::<GamescopeAtom>(),
+            Ok(GamescopeAtom::FocusableWindows)
+        );
+    }
+
+    #[test]
+    fn test_atom_from_property_name() {
+        assert_eq!(
+            GamescopeAtom::from_property_name("STEAM_GAME"),
+            Some(GamescopeAtom::SteamGame)
+        );
+        assert_eq!(GamescopeAtom::from_property_name("WM_NAME"), None);
+        for atom in GamescopeAtom::all() {
+            assert_eq!(
+                GamescopeAtom::from_property_name(&atom.to_string()),
+                Some(atom)
+            );
+        }
+    }
+
+    #[test]
+    fn test_atom_key() {
+        assert_eq!(
+            AtomKey::from("GAMESCOPE_FPS_LIMIT"),
+            AtomKey::Known(GamescopeAtom::FPSLimit)
+        );
+        let key = AtomKey::from("GAMESCOPE_NEW_FEATURE");
+        assert_eq!(key, AtomKey::Custom("GAMESCOPE_NEW_FEATURE".into()));
+        assert_eq!(key.name(), "GAMESCOPE_NEW_FEATURE");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_atom_serde() {
+        use serde::de::{value, Deserialize, IntoDeserializer};
+
+        let name: value::StrDeserializer<value::Error> = "GAMESCOPE_FPS_LIMIT".into_deserializer();
+        assert_eq!(
+            GamescopeAtom::deserialize(name),
+            Ok(GamescopeAtom::FPSLimit)
+        );
+        let name: value::StrDeserializer<value::Error> = "FPSLimit".into_deserializer();
+        assert!(GamescopeAtom::deserialize(name).is_err());
     }
 }
@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+
+use x11rb::reexports::x11rb_protocol::xauth::Family;
+
+/// Name of the only authentication method supported by x11rb
+const MIT_MAGIC_COOKIE_1: &[u8] = b"MIT-MAGIC-COOKIE-1";
+
+/// Authentication method name and data to send to the X server
+pub(crate) type AuthInfo = (Vec<u8>, Vec<u8>);
+
+/// A single entry of an Xauthority file
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AuthEntry {
+    family: Family,
+    address: Vec<u8>,
+    number: Vec<u8>,
+    name: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// Look up the authentication information for the given display in the given
+/// Xauthority file instead of the one from the environment. Returns None if
+/// the file has no matching entry.
+pub(crate) fn get_auth_from_file(
+    path: &Path,
+    family: Family,
+    address: &[u8],
+    display: u16,
+) -> Result<Option<AuthInfo>, std::io::Error> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let display = display.to_string();
+
+    while let Some(entry) = read_entry(&mut reader)? {
+        let address_matches = family == Family::WILD
+            || entry.family == Family::WILD
+            || (entry.family == family && entry.address == address);
+        let display_matches = entry.number.is_empty() || entry.number == display.as_bytes();
+        if address_matches && display_matches && entry.name == MIT_MAGIC_COOKIE_1 {
+            return Ok(Some((entry.name, entry.data)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Read a single entry from an Xauthority file, or None at the end of the file
+fn read_entry<R: Read>(reader: &mut R) -> Result<Option<AuthEntry>, std::io::Error> {
+    let family = match read_u16(reader) {
+        Ok(family) => Family::from(family),
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    };
+
+    Ok(Some(AuthEntry {
+        family,
+        address: read_bytes(reader)?,
+        number: read_bytes(reader)?,
+        name: read_bytes(reader)?,
+        data: read_bytes(reader)?,
+    }))
+}
+
+/// Read a big endian u16
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, std::io::Error> {
+    let mut buffer = [0; 2];
+    reader.read_exact(&mut buffer)?;
+    Ok(u16::from_be_bytes(buffer))
+}
+
+/// Read a length-prefixed byte array
+fn read_bytes<R: Read>(reader: &mut R) -> Result<Vec<u8>, std::io::Error> {
+    let length = read_u16(reader)?;
+    let mut buffer = vec![0; length.into()];
+    reader.read_exact(buffer.as_mut_slice())?;
+    Ok(buffer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_entry() {
+        // Generated with: xauth -f /tmp/file add :1 MIT-MAGIC-COOKIE-1 deadbeef
+        let mut data: Vec<u8> = vec![0x01, 0x00, 0x00, 0x04];
+        data.extend_from_slice(b"deck");
+        data.extend_from_slice(&[0x00, 0x01, b'1', 0x00, 0x12]);
+        data.extend_from_slice(MIT_MAGIC_COOKIE_1);
+        data.extend_from_slice(&[0x00, 0x04, 0xde, 0xad, 0xbe, 0xef]);
+
+        let mut reader = data.as_slice();
+        let entry = read_entry(&mut reader).unwrap().unwrap();
+        assert_eq!(entry.family, Family::LOCAL);
+        assert_eq!(entry.address, b"deck");
+        assert_eq!(entry.number, b"1");
+        assert_eq!(entry.data, vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(read_entry(&mut reader).unwrap(), None);
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{
+    connection::ConnectOptions,
+    xwayland::{Disconnected, XWayland},
+};
+
+/// [XWaylandBuilder] configures how to connect to a Gamescope XWayland display
+/// instead of relying on the x11rb defaults and environment variables.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use gamescope_x11_client::xwayland::XWayland;
+///
+/// let xwayland = XWayland::builder(":1")
+///     .timeout(Duration::from_secs(2))
+///     .auth_file("/run/user/1000/xauth_deck")
+///     .screen(0)
+///     .connect()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct XWaylandBuilder {
+    name: String,
+    options: ConnectOptions,
+}
+
+impl XWaylandBuilder {
+    /// Create a new builder for the given display name (E.g. ":1")
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            options: ConnectOptions::default(),
+        }
+    }
+
+    /// Give up connecting if the connection is not established within the
+    /// given duration
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Read the authentication cookie from the given Xauthority file instead
+    /// of $XAUTHORITY
+    pub fn auth_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.auth_file = Some(path.into());
+        self
+    }
+
+    /// Use the given screen instead of the one from the display name
+    pub fn screen(mut self, screen: usize) -> Self {
+        self.options.screen = Some(screen);
+        self
+    }
+
+    /// Run the background threads of the display with the given nice value
+    pub fn thread_priority(mut self, nice: i32) -> Self {
+        self.options.thread_priority = Some(nice);
+        self
+    }
+
+    /// Open the given number of extra connections that parallel read-only
+    /// operations can borrow
+    pub fn pool_size(mut self, size: usize) -> Self {
+        self.options.pool_size = Some(size);
+        self
+    }
+
+    /// Returns the options that will be used to connect
+    pub fn options(&self) -> &ConnectOptions {
+        &self.options
+    }
+
+    /// Returns an XWayland instance that is not connected yet
+    pub fn build(self) -> XWayland<Disconnected> {
+        XWayland::with_options(self.name, self.options)
+    }
+
+    /// Connect to the display and return the connected XWayland instance
+    pub fn connect(self) -> Result<XWayland, Box<dyn std::error::Error>> {
+        self.build().connect()
+    }
+}
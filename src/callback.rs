@@ -0,0 +1,142 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::{connection::ConnectOptions, worker};
+
+/// Handle of a callback registered with an `on_*` method of
+/// [XWayland](crate::xwayland::XWayland) (E.g.
+/// [XWayland::on_focus_changed](crate::xwayland::XWayland::on_focus_changed)).
+/// Dropping the handle does not unregister the callback.
+#[derive(Debug)]
+pub struct CallbackHandle {
+    registered: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+impl CallbackHandle {
+    /// Unregisters the callback. The callback is not called again after this
+    /// returns, though a call that is already running will finish.
+    pub fn unregister(&self) {
+        self.registered.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns true if the callback is still registered and its event thread
+    /// is running
+    pub fn is_registered(&self) -> bool {
+        self.registered.load(Ordering::SeqCst) && !self.thread.is_finished()
+    }
+}
+
+/// Spawns a thread that reads a value with the given function every time a
+/// notification is received from the given channel, and calls the callback
+/// with the old and new value whenever the value changed.
+pub(crate) fn spawn_change_callback<N, T, R, C>(
+    display: &str,
+    role: &str,
+    options: &ConnectOptions,
+    notifications: Receiver<N>,
+    initial: T,
+    mut read: R,
+    mut callback: C,
+) -> CallbackHandle
+where
+    N: Send + 'static,
+    T: PartialEq + Clone + Send + 'static,
+    R: FnMut() -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+    C: FnMut(T, T) + Send + 'static,
+{
+    let registered = Arc::new(AtomicBool::new(true));
+    let flag = registered.clone();
+
+    let thread = worker::spawn(display, role, options, move || {
+        let mut last = initial;
+        for _ in notifications {
+            if !flag.load(Ordering::SeqCst) {
+                break;
+            }
+            let current = match read() {
+                Ok(current) => current,
+                Err(err) => {
+                    log::error!("Error reading changed value: {}", err);
+                    continue;
+                }
+            };
+            if current == last {
+                continue;
+            }
+            let old = std::mem::replace(&mut last, current.clone());
+            callback(old, current);
+        }
+    });
+
+    CallbackHandle { registered, thread }
+}
+
+/// Spawns a thread that calls the given function every interval until the
+/// returned handle is unregistered
+#[cfg_attr(not(feature = "metrics"), allow(dead_code))]
+pub(crate) fn spawn_periodic_callback<C>(
+    display: &str,
+    role: &str,
+    options: &ConnectOptions,
+    interval: Duration,
+    mut callback: C,
+) -> CallbackHandle
+where
+    C: FnMut() + Send + 'static,
+{
+    let registered = Arc::new(AtomicBool::new(true));
+    let flag = registered.clone();
+
+    let thread = worker::spawn(display, role, options, move || {
+        while flag.load(Ordering::SeqCst) {
+            callback();
+            thread::sleep(interval);
+        }
+    });
+
+    CallbackHandle { registered, thread }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[test]
+    fn test_change_callback_skips_unchanged_values() {
+        let (tx, rx) = mpsc::channel();
+        let values = Arc::new(Mutex::new(vec![Some(620), Some(620), None, Some(769)]));
+        let calls = Arc::new(Mutex::new(Vec::new()));
+
+        let source = values.clone();
+        let seen = calls.clone();
+        let handle = spawn_change_callback(
+            ":0",
+            "callback",
+            &ConnectOptions::default(),
+            rx,
+            Some(769),
+            move || Ok(source.lock().unwrap().remove(0)),
+            move |old, new| seen.lock().unwrap().push((old, new)),
+        );
+        assert!(handle.is_registered());
+
+        for _ in 0..4 {
+            tx.send(()).unwrap();
+        }
+        drop(tx);
+        handle.thread.join().unwrap();
+
+        let calls = calls.lock().unwrap().clone();
+        assert_eq!(
+            calls,
+            vec![(Some(769), Some(620)), (Some(620), None), (None, Some(769))]
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// What a bounded channel does with a new event when it is full
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverflowPolicy {
+    /// Drop the oldest queued event to make room for the new one
+    #[default]
+    DropOldest,
+    /// Drop the new event
+    DropNewest,
+}
+
+#[derive(Debug)]
+struct Queue<T> {
+    events: VecDeque<T>,
+    dropped: u64,
+    senders: usize,
+    receiver: bool,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    queue: Mutex<Queue<T>>,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    /// Signaled when an event was queued or a sender hung up
+    filled: Condvar,
+}
+
+impl<T> Shared<T> {
+    fn lock(&self) -> MutexGuard<'_, Queue<T>> {
+        self.queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Creates a channel that holds at most the given number of events (at least
+/// one) and handles any more according to the given [OverflowPolicy]
+pub fn bounded<T>(
+    capacity: usize,
+    overflow: OverflowPolicy,
+) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let queue = Queue {
+        events: VecDeque::new(),
+        dropped: 0,
+        senders: 1,
+        receiver: true,
+    };
+    let shared = Arc::new(Shared {
+        queue: Mutex::new(queue),
+        capacity: capacity.max(1),
+        overflow,
+        filled: Condvar::new(),
+    });
+
+    (
+        BoundedSender {
+            shared: shared.clone(),
+        },
+        BoundedReceiver { shared },
+    )
+}
+
+/// Sending half of a channel created with [bounded]
+#[derive(Debug)]
+pub struct BoundedSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BoundedSender<T> {
+    /// Queues the given event without waiting. Fails if the receiver was
+    /// dropped. If the channel is full, an event is dropped according to the
+    /// [OverflowPolicy].
+    pub fn send(&self, event: T) -> Result<(), SendError<T>> {
+        let mut queue = self.shared.lock();
+        if !queue.receiver {
+            return Err(SendError(event));
+        }
+        if queue.events.len() >= self.shared.capacity {
+            queue.dropped += 1;
+            match self.shared.overflow {
+                OverflowPolicy::DropOldest => {
+                    queue.events.pop_front();
+                }
+                OverflowPolicy::DropNewest => return Ok(()),
+            }
+        }
+        queue.events.push_back(event);
+        self.shared.filled.notify_one();
+
+        Ok(())
+    }
+}
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        self.shared.lock().senders -= 1;
+        self.shared.filled.notify_all();
+    }
+}
+
+/// Receiving half of a channel created with [bounded]. Works like a
+/// [std::sync::mpsc::Receiver] and additionally reports how many events were
+/// dropped because the consumer fell behind.
+#[derive(Debug)]
+pub struct BoundedReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BoundedReceiver<T> {
+    /// Waits for the next event. Fails once the channel is empty and all
+    /// senders hung up.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(event) = queue.events.pop_front() {
+                return Ok(event);
+            }
+            if queue.senders == 0 {
+                return Err(RecvError);
+            }
+            queue = self
+                .shared
+                .filled
+                .wait(queue)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Returns the next event without waiting
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut queue = self.shared.lock();
+        if let Some(event) = queue.events.pop_front() {
+            return Ok(event);
+        }
+        if queue.senders == 0 {
+            return Err(TryRecvError::Disconnected);
+        }
+        Err(TryRecvError::Empty)
+    }
+
+    /// Waits for the next event for up to the given timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<T, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut queue = self.shared.lock();
+        loop {
+            if let Some(event) = queue.events.pop_front() {
+                return Ok(event);
+            }
+            if queue.senders == 0 {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            queue = self
+                .shared
+                .filled
+                .wait_timeout(queue, remaining)
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+    }
+
+    /// Returns an iterator that waits for events until all senders hung up
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        std::iter::from_fn(|| self.recv().ok())
+    }
+
+    /// Returns the number of events that were dropped because the channel
+    /// was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Returns the number of queued events
+    pub fn len(&self) -> usize {
+        self.shared.lock().events.len()
+    }
+
+    /// Returns true if no events are queued
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the maximum number of queued events
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        self.shared.lock().receiver = false;
+    }
+}
+
+impl<T> IntoIterator for BoundedReceiver<T> {
+    type Item = T;
+    type IntoIter = BoundedIntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        BoundedIntoIter { rx: self }
+    }
+}
+
+/// Owning iterator over the events of a [BoundedReceiver]
+#[derive(Debug)]
+pub struct BoundedIntoIter<T> {
+    rx: BoundedReceiver<T>,
+}
+
+impl<T> Iterator for BoundedIntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.rx.recv().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_channel_overflow() {
+        let (tx, rx) = bounded(2, OverflowPolicy::DropOldest);
+        for counter in 1..=4 {
+            tx.send(counter).unwrap();
+        }
+        assert_eq!(rx.dropped(), 2);
+        assert_eq!(rx.recv(), Ok(3));
+        assert_eq!(rx.recv(), Ok(4));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+
+        let (tx, rx) = bounded(2, OverflowPolicy::DropNewest);
+        for counter in 1..=4 {
+            tx.send(counter).unwrap();
+        }
+        drop(tx);
+        assert_eq!(rx.dropped(), 2);
+        assert_eq!(rx.into_iter().collect::<Vec<_>>(), vec![1, 2]);
+
+        let (tx, rx) = bounded(1, OverflowPolicy::DropOldest);
+        drop(rx);
+        assert!(tx.send(1).is_err());
+    }
+}
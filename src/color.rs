@@ -0,0 +1,67 @@
+/// Night mode settings from the GAMESCOPE_COLOR_NIGHTMODE property
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NightMode {
+    /// Strength of the effect, from 0.0 (off) to 1.0
+    pub amount: f32,
+    /// Hue to tint the display with, from 0.0 to 1.0
+    pub hue: f32,
+    /// Saturation of the tint, from 0.0 to 1.0
+    pub saturation: f32,
+}
+
+impl NightMode {
+    /// Encodes the settings into the CARDINAL values Gamescope expects
+    pub(crate) fn encode(&self) -> Vec<u32> {
+        encode_floats(&[self.amount, self.hue, self.saturation])
+    }
+
+    /// Decodes the settings from the CARDINAL values of the property
+    pub(crate) fn decode(values: &[u32]) -> Option<Self> {
+        let [amount, hue, saturation] = decode_floats(values)?;
+        Some(Self {
+            amount,
+            hue,
+            saturation,
+        })
+    }
+}
+
+/// Gamescope stores floating point values in CARDINAL properties as the bits
+/// of a 32-bit float. Returns the CARDINAL value for the given float.
+pub fn encode_float(value: f32) -> u32 {
+    value.to_bits()
+}
+
+/// Returns the float stored in the given CARDINAL value
+pub fn decode_float(value: u32) -> f32 {
+    f32::from_bits(value)
+}
+
+/// Encodes all of the given floats into CARDINAL values
+pub(crate) fn encode_floats(values: &[f32]) -> Vec<u32> {
+    values.iter().copied().map(encode_float).collect()
+}
+
+/// Decodes exactly N floats from the given CARDINAL values. Returns None if
+/// there is a different number of values.
+pub(crate) fn decode_floats<const N: usize>(values: &[u32]) -> Option<[f32; N]> {
+    let values: [u32; N] = values.try_into().ok()?;
+    Some(values.map(decode_float))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_float_encoding() {
+        assert_eq!(encode_float(1.0), 0x3f80_0000);
+        assert_eq!(decode_float(0x3f00_0000), 0.5);
+
+        let gain = [1.0, 0.5, 0.25];
+        let values = encode_floats(&gain);
+        assert_eq!(decode_floats::<3>(&values), Some(gain));
+        assert_eq!(decode_floats::<9>(&values), None);
+    }
+}
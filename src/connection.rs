@@ -0,0 +1,289 @@
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use x11rb::connection::Connection;
+#[cfg(not(feature = "xcb"))]
+use x11rb::{
+    reexports::x11rb_protocol::{parse_display::parse_display, xauth},
+    rust_connection::{DefaultStream, RustConnection},
+};
+
+#[cfg(not(feature = "xcb"))]
+use crate::auth;
+use crate::{atom_cache::AtomCache, worker, x11};
+
+/// Connection to the X server used by [crate::xwayland::XWayland]. This is
+/// x11rb's pure Rust RustConnection by default, or its libxcb based
+/// XCBConnection with the "xcb" feature, which is faster for high-frequency
+/// property polling.
+#[cfg(not(feature = "xcb"))]
+pub type X11Connection = RustConnection;
+/// Connection to the X server used by [crate::xwayland::XWayland]. This is
+/// x11rb's pure Rust RustConnection by default, or its libxcb based
+/// XCBConnection with the "xcb" feature, which is faster for high-frequency
+/// property polling.
+#[cfg(feature = "xcb")]
+pub type X11Connection = x11rb::xcb_ffi::XCBConnection;
+
+/// Options used when connecting to an XWayland display. By default the x11rb
+/// defaults are used, which read the Xauthority file and screen from the
+/// environment and wait for the server indefinitely.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// Maximum time to wait for the connection to be established
+    pub timeout: Option<Duration>,
+    /// Path to the Xauthority file to read the authentication cookie from
+    /// instead of $XAUTHORITY
+    pub auth_file: Option<PathBuf>,
+    /// Screen to use instead of the one from the display name
+    pub screen: Option<usize>,
+    /// Nice value to run the background threads of the display with (E.g. 10
+    /// so event threads don't compete with the game). Threads keep the
+    /// priority of the process when not set.
+    pub thread_priority: Option<i32>,
+    /// Number of extra connections to open for parallel read-only operations
+    /// like [crate::xwayland::XWayland::snapshot]. No extra connections are
+    /// opened when not set.
+    pub pool_size: Option<usize>,
+}
+
+/// Policy used to reconnect to an XWayland display after the connection to it
+/// was lost (E.g. because Gamescope was restarted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReconnectPolicy {
+    /// Time to wait before each reconnect attempt
+    pub interval: Duration,
+    /// Maximum number of reconnect attempts before giving up, or None to keep
+    /// trying forever
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            max_attempts: None,
+        }
+    }
+}
+
+/// Changes to the state of the connection to an XWayland display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ConnectionEvent {
+    /// The connection to the display was lost
+    Disconnected,
+    /// The connection was re-established and all active listeners were
+    /// subscribed again
+    Reconnected,
+}
+
+#[derive(Debug)]
+struct ConnectionState {
+    conn: Arc<X11Connection>,
+    root_window_id: u32,
+    atoms: Arc<AtomCache>,
+}
+
+/// [SharedConnection] is the connection to an XWayland display that is shared
+/// between an [crate::xwayland::XWayland] handle and its background threads.
+/// The underlying connection can be replaced when reconnecting, so users
+/// should fetch it with [SharedConnection::get] each time it is needed.
+#[derive(Debug, Clone)]
+pub(crate) struct SharedConnection {
+    name: String,
+    options: ConnectOptions,
+    state: Arc<RwLock<ConnectionState>>,
+    policy: Arc<Mutex<Option<ReconnectPolicy>>>,
+}
+
+impl SharedConnection {
+    /// Connect to the given XWayland display using the given options
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(options), err(Display)))]
+    pub fn connect(
+        name: &str,
+        options: ConnectOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let state = Self::open(name, &options)?;
+        Ok(Self {
+            name: name.to_string(),
+            options,
+            state: Arc::new(RwLock::new(state)),
+            policy: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Open a new connection to the given display, giving up after the
+    /// configured timeout.
+    fn open(
+        name: &str,
+        options: &ConnectOptions,
+    ) -> Result<ConnectionState, Box<dyn std::error::Error>> {
+        let Some(timeout) = options.timeout else {
+            return Self::open_blocking(name, options)
+                .map_err(|err| err as Box<dyn std::error::Error>);
+        };
+
+        // Connect on a separate thread so a server that never answers the
+        // connection setup can't block the caller.
+        let (tx, rx) = mpsc::channel();
+        let display = name.to_string();
+        let thread_options = options.clone();
+        worker::spawn(name, "connect", options, move || {
+            let _ = tx.send(Self::open_blocking(display.as_str(), &thread_options));
+        });
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result.map_err(|err| err as Box<dyn std::error::Error>),
+            Err(_) => Err(format!("Timed out connecting to {}", name).into()),
+        }
+    }
+
+    /// Open a new connection to the given display. Errors keep their original
+    /// type (E.g. [x11rb::errors::ConnectError]) so callers can inspect them.
+    #[cfg(not(feature = "xcb"))]
+    fn open_blocking(
+        name: &str,
+        options: &ConnectOptions,
+    ) -> Result<ConnectionState, Box<dyn std::error::Error + Send + Sync>> {
+        let parsed = parse_display(Some(name))?;
+        let screen_num = options.screen.unwrap_or(parsed.screen.into());
+
+        // Try each address the display can be reached at until one works
+        let mut error: Option<Box<dyn std::error::Error + Send + Sync>> = None;
+        for address in parsed.connect_instruction() {
+            let (stream, (family, peer)) = match DefaultStream::connect(&address) {
+                Ok(stream) => stream,
+                Err(err) => {
+                    error = Some(err.into());
+                    continue;
+                }
+            };
+
+            let auth = match options.auth_file.as_ref() {
+                Some(path) => auth::get_auth_from_file(path, family, &peer, parsed.display)?,
+                // Ignore errors reading the default file like x11rb does
+                None => xauth::get_auth(family, &peer, parsed.display).unwrap_or(None),
+            };
+            let (auth_name, auth_data) = auth.unwrap_or_default();
+
+            let conn = RustConnection::connect_to_stream_with_auth_info(
+                stream, screen_num, auth_name, auth_data,
+            )?;
+            log::info!("Connected to: {}", screen_num);
+            let root_window_id = conn.setup().roots[screen_num].root;
+            let atoms = AtomCache::preloaded(&conn);
+
+            return Ok(ConnectionState {
+                conn: Arc::new(conn),
+                root_window_id,
+                atoms: Arc::new(atoms),
+            });
+        }
+
+        Err(error.unwrap_or_else(|| format!("Unable to connect to {}", name).into()))
+    }
+
+    /// Open a new connection to the given display using libxcb. libxcb reads
+    /// the Xauthority file from the environment, so an explicit
+    /// [ConnectOptions::auth_file] is not supported.
+    #[cfg(feature = "xcb")]
+    fn open_blocking(
+        name: &str,
+        options: &ConnectOptions,
+    ) -> Result<ConnectionState, Box<dyn std::error::Error + Send + Sync>> {
+        if options.auth_file.is_some() {
+            return Err("An explicit Xauthority file is not supported with libxcb".into());
+        }
+
+        let display = std::ffi::CString::new(name)?;
+        let (conn, default_screen) = X11Connection::connect(Some(&display))?;
+        let screen_num = options.screen.unwrap_or(default_screen);
+        log::info!("Connected to: {}", screen_num);
+        let root_window_id = conn
+            .setup()
+            .roots
+            .get(screen_num)
+            .ok_or(x11rb::errors::ConnectError::InvalidScreen)?
+            .root;
+        let atoms = AtomCache::preloaded(&conn);
+
+        Ok(ConnectionState {
+            conn: Arc::new(conn),
+            root_window_id,
+            atoms: Arc::new(atoms),
+        })
+    }
+
+    /// Returns the name of the display (E.g. ":0")
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Returns the options used to connect to the display
+    pub fn options(&self) -> &ConnectOptions {
+        &self.options
+    }
+
+    /// Returns the current connection
+    pub fn get(&self) -> Arc<X11Connection> {
+        match self.state.read() {
+            Ok(state) => state.conn.clone(),
+            Err(poisoned) => poisoned.into_inner().conn.clone(),
+        }
+    }
+
+    /// Returns the current connection together with the cache of atoms
+    /// interned on it
+    pub fn get_with_atoms(&self) -> (Arc<X11Connection>, Arc<AtomCache>) {
+        match self.state.read() {
+            Ok(state) => (state.conn.clone(), state.atoms.clone()),
+            Err(poisoned) => {
+                let state = poisoned.into_inner();
+                (state.conn.clone(), state.atoms.clone())
+            }
+        }
+    }
+
+    /// Returns the root window id of the current connection
+    pub fn root_window_id(&self) -> u32 {
+        match self.state.read() {
+            Ok(state) => state.root_window_id,
+            Err(poisoned) => poisoned.into_inner().root_window_id,
+        }
+    }
+
+    /// Returns the reconnect policy, if reconnecting is enabled
+    pub fn policy(&self) -> Option<ReconnectPolicy> {
+        self.policy.lock().map(|policy| *policy).unwrap_or_default()
+    }
+
+    /// Sets the reconnect policy. Reconnecting is disabled when set to None.
+    pub fn set_policy(&self, policy: Option<ReconnectPolicy>) {
+        if let Ok(mut current) = self.policy.lock() {
+            *current = policy;
+        }
+    }
+
+    /// Open an additional connection to the same display with the same
+    /// options. The shared connection is not changed.
+    pub fn open_extra(&self) -> Result<Arc<X11Connection>, Box<dyn std::error::Error>> {
+        Ok(Self::open(self.name.as_str(), &self.options)?.conn)
+    }
+
+    /// Make a single attempt to connect to the display again and replace the
+    /// current connection. The display must be a Gamescope XWayland.
+    pub fn reconnect(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let state = Self::open(self.name.as_str(), &self.options)?;
+        if !x11::is_gamescope_xwayland(state.conn.as_ref(), state.root_window_id)? {
+            return Err(format!("Display {} is not a Gamescope XWayland", self.name).into());
+        }
+
+        let mut current = self.state.write().map_err(|err| err.to_string())?;
+        *current = state;
+
+        Ok(())
+    }
+}
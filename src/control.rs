@@ -0,0 +1,329 @@
+use crate::{atoms::GamescopeAtom, xwayland::XWayland};
+
+/// [GamescopeControl] contains the read and write operations used to query and
+/// control a single Gamescope XWayland instance. It is object-safe, so code
+/// that drives Gamescope can take a `&dyn GamescopeControl` and be tested
+/// against a fake instead of a real X server. Every implementation also gets
+/// the [crate::xwayland::Primary] API for free.
+pub trait GamescopeControl {
+    /// Returns the name of the XWayland instance (E.g. ":0")
+    fn get_name(&self) -> String;
+    /// Returns the app id used to identify the overlay app
+    fn get_overlay_app_id(&self) -> u32;
+    /// Returns the root window ID of the xwayland instance
+    fn get_root_window_id(&self) -> Result<u32, Box<dyn std::error::Error>>;
+    /// Returns the window ids of the children of the given window
+    fn get_window_children(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>>;
+    /// Returns the window name of the given window
+    fn get_window_name(&self, window_id: u32)
+        -> Result<Option<String>, Box<dyn std::error::Error>>;
+    /// Returns the instance and class names from the WM_CLASS property of the
+    /// given window
+    fn get_window_class(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>>;
+    /// Returns the true if the given property exists on the given window
+    fn has_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Returns the value(s) of the given property on the given window
+    fn get_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>;
+    /// Sets the given x window property value(s) on the given window
+    fn set_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Removes the given x window property from the given window
+    fn remove_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the given string property of the given window
+    fn get_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>>;
+    /// Sets the given string property on the given window
+    fn set_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns true if the given atom is known to the running Gamescope
+    fn has_atom(&self, key: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>>;
+
+    /// Returns the window names of all of the given windows
+    fn get_window_names(
+        &self,
+        window_ids: &[u32],
+    ) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+        window_ids
+            .iter()
+            .map(|window_id| self.get_window_name(*window_id))
+            .collect()
+    }
+
+    /// Returns the first value of the given property on the given window
+    fn get_one_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let value = self.get_xprop(window_id, key)?.unwrap_or_default();
+        Ok(value.first().copied())
+    }
+
+    /// Returns true if this instance is the primary Gamescope xwayland instance
+    fn is_primary_instance(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root_id = self.get_root_window_id()?;
+        self.has_xprop(root_id, GamescopeAtom::KeyboardFocusDisplay)
+    }
+
+    /// Returns the process ID of the given window from the '_NET_WM_PID' atom
+    fn get_window_pid(&self, window_id: u32) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(window_id, GamescopeAtom::NetWmPID)
+    }
+
+    /// Returns the process IDs associated with the given window. By default
+    /// this is the '_NET_WM_PID' of the window, which may be a pid inside a
+    /// sandbox.
+    fn get_pids_for_window(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        Ok(self.get_window_pid(window_id)?.into_iter().collect())
+    }
+
+    /// Returns the currently set app ID on the given window
+    fn get_app_id(&self, window_id: u32) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(window_id, GamescopeAtom::SteamGame)
+    }
+
+    /// Sets the app ID on the given window
+    fn set_app_id(&self, window_id: u32, app_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_xprop(window_id, GamescopeAtom::SteamGame, vec![app_id])
+    }
+
+    /// Returns whether or not the given window has an app ID set
+    fn has_app_id(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
+        self.has_xprop(window_id, GamescopeAtom::SteamGame)
+    }
+}
+
+impl GamescopeControl for XWayland {
+    fn get_name(&self) -> String {
+        XWayland::get_name(self)
+    }
+
+    fn get_overlay_app_id(&self) -> u32 {
+        XWayland::get_overlay_app_id(self)
+    }
+
+    fn get_root_window_id(&self) -> Result<u32, Box<dyn std::error::Error>> {
+        XWayland::get_root_window_id(self)
+    }
+
+    fn get_window_children(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        XWayland::get_window_children(self, window_id)
+    }
+
+    fn get_window_name(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        XWayland::get_window_name(self, window_id)
+    }
+
+    fn get_window_class(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        XWayland::get_window_class(self, window_id)
+    }
+
+    fn has_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        XWayland::has_xprop(self, window_id, key)
+    }
+
+    fn get_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        XWayland::get_xprop(self, window_id, key)
+    }
+
+    fn set_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        XWayland::set_xprop(self, window_id, key, values)
+    }
+
+    fn remove_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        XWayland::remove_xprop(self, window_id, key)
+    }
+
+    fn get_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        XWayland::get_string_xprop(self, window_id, key)
+    }
+
+    fn set_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        XWayland::set_string_xprop(self, window_id, key, value)
+    }
+
+    fn has_atom(&self, key: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>> {
+        XWayland::has_atom(self, key)
+    }
+
+    fn get_window_names(
+        &self,
+        window_ids: &[u32],
+    ) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+        XWayland::get_window_names(self, window_ids)
+    }
+
+    fn get_pids_for_window(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        XWayland::get_pids_for_window(self, window_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::xwayland::Primary;
+
+    /// Minimal implementation that only stores root window properties
+    #[derive(Default)]
+    struct RootOnly {
+        properties: RefCell<HashMap<String, Vec<u32>>>,
+    }
+
+    impl GamescopeControl for RootOnly {
+        fn get_name(&self) -> String {
+            ":0".into()
+        }
+
+        fn get_overlay_app_id(&self) -> u32 {
+            crate::xwayland::OVERLAY_APP_ID
+        }
+
+        fn get_root_window_id(&self) -> Result<u32, Box<dyn std::error::Error>> {
+            Ok(1)
+        }
+
+        fn get_window_children(&self, _: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+            Ok(Vec::new())
+        }
+
+        fn get_window_name(&self, _: u32) -> Result<Option<String>, Box<dyn std::error::Error>> {
+            Ok(None)
+        }
+
+        fn get_window_class(
+            &self,
+            _: u32,
+        ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+            Ok(None)
+        }
+
+        fn has_xprop(
+            &self,
+            _: u32,
+            key: GamescopeAtom,
+        ) -> Result<bool, Box<dyn std::error::Error>> {
+            Ok(self.properties.borrow().contains_key(&key.to_string()))
+        }
+
+        fn get_xprop(
+            &self,
+            _: u32,
+            key: GamescopeAtom,
+        ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+            Ok(self.properties.borrow().get(&key.to_string()).cloned())
+        }
+
+        fn set_xprop(
+            &self,
+            _: u32,
+            key: GamescopeAtom,
+            values: Vec<u32>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            self.properties.borrow_mut().insert(key.to_string(), values);
+            Ok(())
+        }
+
+        fn remove_xprop(
+            &self,
+            _: u32,
+            key: GamescopeAtom,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            self.properties.borrow_mut().remove(&key.to_string());
+            Ok(())
+        }
+
+        fn get_string_xprop(
+            &self,
+            _: u32,
+            _: GamescopeAtom,
+        ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+            Ok(None)
+        }
+
+        fn set_string_xprop(
+            &self,
+            _: u32,
+            _: GamescopeAtom,
+            _: &str,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            Err("Not supported".into())
+        }
+
+        fn has_atom(&self, _: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>> {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_primary_through_trait_object() {
+        let fake = RootOnly::default();
+        let control: &dyn GamescopeControl = &fake;
+
+        control.set_baselayer_app_id(620).unwrap();
+        assert_eq!(control.get_baselayer_app_id().unwrap(), Some(620));
+        control.remove_baselayer_app_id().unwrap();
+        assert_eq!(control.get_baselayer_app_id().unwrap(), None);
+        assert!(!control.supports_overview().unwrap());
+    }
+}
@@ -0,0 +1,107 @@
+use x11rb::{connection::Connection, protocol::xproto::ConnectionExt};
+
+use crate::{watchdog::is_process_running, x11};
+
+/// Root window property that records the process ID of the controlling client.
+/// It is only used by clients of this crate, so it is kept out of the
+/// GAMESCOPE_ namespace that belongs to Gamescope itself.
+pub(crate) const CONTROLLER_PROPERTY: &str = "_SHADOWBLIP_GAMESCOPE_X11_CLIENT_CONTROLLER";
+
+/// Changes to the client controlling a Gamescope XWayland instance
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ControllerEvent {
+    /// The process with the given process ID took control
+    Acquired { pid: u32 },
+    /// The controlling process released control
+    Released,
+}
+
+/// Returns the process ID of the client that currently controls the display
+/// according to the [CONTROLLER_PROPERTY] on the given root window. Entries
+/// left behind by processes that are no longer running are ignored.
+pub(crate) fn get_controller<F>(
+    conn: F,
+    root_window_id: u32,
+) -> Result<Option<u32>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let value = x11::get_property(conn, root_window_id, CONTROLLER_PROPERTY)?;
+    let pid = value.and_then(|value| value.first().copied());
+    Ok(pid.filter(|pid| is_process_running(*pid)))
+}
+
+/// Records the given process as the controller of the display unless another
+/// running process already is. The server is grabbed while the property is
+/// checked and updated so two clients can't both acquire control. Returns true
+/// if the given process is now the controller.
+pub(crate) fn try_acquire<F>(
+    conn: F,
+    root_window_id: u32,
+    pid: u32,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    with_server_grabbed(&conn, || {
+        let current = get_controller(&conn, root_window_id)?;
+        if !can_acquire(current, pid) {
+            return Ok(false);
+        }
+        x11::set_property(&conn, root_window_id, CONTROLLER_PROPERTY, vec![pid])?;
+        Ok(true)
+    })
+}
+
+/// Removes the controller record if it belongs to the given process. Returns
+/// true if it was removed.
+pub(crate) fn release<F>(
+    conn: F,
+    root_window_id: u32,
+    pid: u32,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    with_server_grabbed(&conn, || {
+        let current = x11::get_property(&conn, root_window_id, CONTROLLER_PROPERTY)?;
+        if current.and_then(|value| value.first().copied()) != Some(pid) {
+            return Ok(false);
+        }
+        x11::remove_property(&conn, root_window_id, CONTROLLER_PROPERTY)?;
+        Ok(true)
+    })
+}
+
+/// Returns true if the given process may take control when the display is
+/// currently controlled by the given (running) process
+fn can_acquire(current: Option<u32>, pid: u32) -> bool {
+    current.map(|current| current == pid).unwrap_or(true)
+}
+
+/// Runs the given function while no other client can talk to the X server
+fn with_server_grabbed<F, T>(
+    conn: &F,
+    f: impl FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    conn.grab_server()?.check()?;
+    let result = f();
+    conn.ungrab_server()?.check()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_can_acquire() {
+        assert!(can_acquire(None, 100));
+        assert!(can_acquire(Some(100), 100));
+        assert!(!can_acquire(Some(200), 100));
+    }
+}
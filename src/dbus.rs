@@ -0,0 +1,313 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::channel::oneshot;
+use x11rb::protocol::{xproto::EventMask, Event};
+use zbus::blocking::connection::Builder;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+
+use crate::{
+    callback::CallbackHandle,
+    event_loop::RawEvent,
+    worker,
+    xwayland::{BlurMode, Primary, ScreenshotType, XWayland},
+};
+
+/// Well-known name the service is published under by [DBusService::session]
+pub const SERVICE_NAME: &str = "org.shadowblip.Gamescope";
+
+/// Object path the Gamescope interface is served at
+pub const OBJECT_PATH: &str = "/org/shadowblip/Gamescope";
+
+/// D-Bus interface (org.shadowblip.Gamescope) exporting the [Primary]
+/// functions of an XWayland instance. Missing values (E.g. no focused app)
+/// are reported as 0.
+#[derive(Debug)]
+pub struct GamescopeInterface {
+    xwayland: XWayland,
+}
+
+impl GamescopeInterface {
+    /// Runs the given function on a worker thread, so calls that wait for the
+    /// X server (E.g. taking a screenshot) don't block the D-Bus executor
+    /// while other calls are pending
+    async fn call<T, F>(&self, f: F) -> fdo::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&XWayland) -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let xwayland = self.xwayland.clone();
+        let options = xwayland.get_connect_options();
+        worker::spawn(&xwayland.get_name(), "dbus", &options, move || {
+            let _ = tx.send(f(&xwayland).map_err(|err| err.to_string()));
+        });
+
+        match rx.await {
+            Ok(result) => result.map_err(fdo::Error::Failed),
+            Err(_) => Err(fdo::Error::Failed(
+                "Worker thread exited without a result".into(),
+            )),
+        }
+    }
+}
+
+#[zbus::interface(name = "org.shadowblip.Gamescope")]
+impl GamescopeInterface {
+    /// Returns the name of the XWayland display (E.g. ":1")
+    fn get_name(&self) -> String {
+        self.xwayland.get_name()
+    }
+
+    /// Returns the app ids of the focusable apps
+    async fn get_focusable_apps(&self) -> fdo::Result<Vec<u32>> {
+        let apps = self.call(|xwayland| xwayland.get_focusable_apps()).await?;
+        Ok(apps.unwrap_or_default())
+    }
+
+    /// Returns the ids of the focusable windows
+    async fn get_focusable_windows(&self) -> fdo::Result<Vec<u32>> {
+        let windows = self
+            .call(|xwayland| xwayland.get_focusable_windows())
+            .await?;
+        Ok(windows.unwrap_or_default())
+    }
+
+    /// Returns the app id of the focused app
+    async fn get_focused_app(&self) -> fdo::Result<u32> {
+        let app_id = self.call(|xwayland| xwayland.get_focused_app()).await?;
+        Ok(app_id.unwrap_or_default())
+    }
+
+    /// Returns the id of the focused window
+    async fn get_focused_window(&self) -> fdo::Result<u32> {
+        let window_id = self.call(|xwayland| xwayland.get_focused_window()).await?;
+        Ok(window_id.unwrap_or_default())
+    }
+
+    /// Focuses the given window
+    async fn set_main_app(&self, window_id: u32) -> fdo::Result<()> {
+        self.call(move |xwayland| xwayland.set_main_app(window_id))
+            .await
+    }
+
+    /// Returns the FPS limit
+    async fn get_fps_limit(&self) -> fdo::Result<u32> {
+        let fps = self.call(|xwayland| xwayland.get_fps_limit()).await?;
+        Ok(fps.unwrap_or_default())
+    }
+
+    /// Sets the FPS limit (0 to disable it)
+    async fn set_fps_limit(&self, fps: u32) -> fdo::Result<()> {
+        self.call(move |xwayland| xwayland.set_fps_limit(fps)).await
+    }
+
+    /// Returns the blur mode (0 = off, 1 = conditional, 2 = always)
+    async fn get_blur_mode(&self) -> fdo::Result<u32> {
+        let mode = self.call(|xwayland| xwayland.get_blur_mode()).await?;
+        let mode = match mode {
+            None | Some(BlurMode::Off) => 0,
+            Some(BlurMode::Cond) => 1,
+            Some(BlurMode::Always) => 2,
+        };
+        Ok(mode)
+    }
+
+    /// Sets the blur mode (0 = off, 1 = conditional, 2 = always)
+    async fn set_blur_mode(&self, mode: u32) -> fdo::Result<()> {
+        let mode = match mode {
+            0 => BlurMode::Off,
+            1 => BlurMode::Cond,
+            2 => BlurMode::Always,
+            _ => {
+                return Err(fdo::Error::InvalidArgs(format!(
+                    "Invalid blur mode: {mode}"
+                )))
+            }
+        };
+        self.call(move |xwayland| xwayland.set_blur_mode(mode))
+            .await
+    }
+
+    /// Sets the blur radius in pixels
+    async fn set_blur_radius(&self, radius: u32) -> fdo::Result<()> {
+        self.call(move |xwayland| xwayland.set_blur_radius(radius))
+            .await
+    }
+
+    /// Returns the app id of the baselayer app
+    async fn get_baselayer_app_id(&self) -> fdo::Result<u32> {
+        let app_id = self
+            .call(|xwayland| xwayland.get_baselayer_app_id())
+            .await?;
+        Ok(app_id.unwrap_or_default())
+    }
+
+    /// Sets the app id of the baselayer app
+    async fn set_baselayer_app_id(&self, app_id: u32) -> fdo::Result<()> {
+        self.call(move |xwayland| xwayland.set_baselayer_app_id(app_id))
+            .await
+    }
+
+    /// Removes the baselayer app id
+    async fn remove_baselayer_app_id(&self) -> fdo::Result<()> {
+        self.call(|xwayland| xwayland.remove_baselayer_app_id())
+            .await
+    }
+
+    /// Requests a screenshot of the given type (0 = base plane only, 1 = all
+    /// real layers, 2 = full composition, 3 = screen buffer)
+    async fn request_screenshot(&self, screenshot_type: u32) -> fdo::Result<()> {
+        let screenshot_type = screenshot_type_from_u32(screenshot_type)?;
+        self.call(move |xwayland| xwayland.request_screenshot(screenshot_type))
+            .await
+    }
+
+    /// Takes a screenshot of the given type and returns the path of the
+    /// written file, waiting up to the given number of milliseconds
+    async fn take_screenshot(&self, screenshot_type: u32, timeout_ms: u32) -> fdo::Result<String> {
+        let screenshot_type = screenshot_type_from_u32(screenshot_type)?;
+        let timeout = Duration::from_millis(timeout_ms.into());
+        let path = self
+            .call(move |xwayland| xwayland.take_screenshot_with_type(screenshot_type, timeout))
+            .await?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Emitted when the focused app changes. The app id is 0 if no app is
+    /// focused.
+    #[zbus(signal)]
+    pub async fn focus_changed(emitter: &SignalEmitter<'_>, app_id: u32) -> zbus::Result<()>;
+
+    /// Emitted when a property on the root window changes (E.g.
+    /// "GAMESCOPE_FOCUSED_APP")
+    #[zbus(signal)]
+    pub async fn property_changed(emitter: &SignalEmitter<'_>, name: String) -> zbus::Result<()>;
+}
+
+/// Returns the [ScreenshotType] with the given value
+fn screenshot_type_from_u32(value: u32) -> fdo::Result<ScreenshotType> {
+    match value {
+        0 => Ok(ScreenshotType::BasePlaneOnly),
+        1 => Ok(ScreenshotType::AllRealLayers),
+        2 => Ok(ScreenshotType::FullComposition),
+        3 => Ok(ScreenshotType::ScreenBuffer),
+        _ => Err(fdo::Error::InvalidArgs(format!(
+            "Invalid screenshot type: {value}"
+        ))),
+    }
+}
+
+/// [DBusService] serves a [GamescopeInterface] for an XWayland instance and
+/// emits its signals, so processes that are not written in Rust can control
+/// Gamescope through this crate. The interface is removed from the bus when
+/// the service is dropped.
+#[derive(Debug)]
+pub struct DBusService {
+    conn: Connection,
+    xwayland: XWayland,
+    focus: CallbackHandle,
+    /// Sender the root window property changes are forwarded through
+    changes: Arc<Sender<RawEvent>>,
+}
+
+impl DBusService {
+    /// Serves the given instance on the session bus under [SERVICE_NAME]
+    pub fn session(xwayland: &XWayland) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::start(xwayland, Builder::session()?.name(SERVICE_NAME)?)
+    }
+
+    /// Serves the given instance at [OBJECT_PATH] on the connection built by
+    /// the given builder (E.g. to use the system bus or another name when
+    /// serving multiple instances)
+    pub fn start(
+        xwayland: &XWayland,
+        builder: Builder<'_>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let interface = GamescopeInterface {
+            xwayland: xwayland.clone(),
+        };
+        let conn = builder.serve_at(OBJECT_PATH, interface)?.build()?;
+        let iface = conn
+            .object_server()
+            .interface::<_, GamescopeInterface>(OBJECT_PATH)?;
+
+        let emitter = iface.signal_emitter().clone();
+        let focus = xwayland.on_focus_changed(move |_, app_id| {
+            let signal = GamescopeInterface::focus_changed(&emitter, app_id.unwrap_or_default());
+            if let Err(err) = futures::executor::block_on(signal) {
+                log::error!("Error emitting FocusChanged: {}", err);
+            }
+        })?;
+
+        let (tx, events) = mpsc::channel();
+        let tx = Arc::new(tx);
+        let root = xwayland.get_root_window_id()?;
+        xwayland.subscribe_events(root, EventMask::PROPERTY_CHANGE, tx.clone())?;
+        let emitter = iface.signal_emitter().clone();
+        let options = xwayland.get_connect_options();
+        worker::spawn(&xwayland.get_name(), "dbus", &options, move || {
+            for (conn, atoms, event) in events {
+                let Event::PropertyNotify(event) = event else {
+                    continue;
+                };
+                let name = match atoms.name(conn.as_ref(), event.atom) {
+                    Ok(name) => name.to_string(),
+                    Err(err) => {
+                        log::error!("Error looking up changed property: {}", err);
+                        continue;
+                    }
+                };
+                let signal = GamescopeInterface::property_changed(&emitter, name);
+                if let Err(err) = futures::executor::block_on(signal) {
+                    log::error!("Error emitting PropertyChanged: {}", err);
+                }
+            }
+        });
+
+        Ok(Self {
+            conn,
+            xwayland: xwayland.clone(),
+            focus,
+            changes: tx,
+        })
+    }
+
+    /// Returns the D-Bus connection the service is served on
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl Drop for DBusService {
+    fn drop(&mut self) {
+        self.focus.unregister();
+        if let Err(err) = self.xwayland.unsubscribe_events(&self.changes) {
+            log::debug!("Error ending property change subscription: {}", err);
+        }
+        let result = self
+            .conn
+            .object_server()
+            .remove::<GamescopeInterface, _>(OBJECT_PATH);
+        if let Err(err) = result {
+            log::debug!("Error removing D-Bus interface: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screenshot_type_from_u32() {
+        assert_eq!(
+            screenshot_type_from_u32(2).unwrap(),
+            ScreenshotType::FullComposition
+        );
+        assert!(screenshot_type_from_u32(4).is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{connection::ConnectOptions, worker};
+
+/// Limits how often a listener delivers events for the same key (E.g. the same
+/// property), for properties like GAMESCOPE_INPUT_COUNTER that Gamescope may
+/// update on every frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Debounce {
+    /// Deliver every event
+    #[default]
+    Off,
+    /// Deliver at most one event per key within the given interval and drop
+    /// all others
+    Throttle(Duration),
+    /// Deliver at most one event per key within the given interval. The latest
+    /// of the events received during the interval is delivered when it ends,
+    /// so the last change is never lost.
+    Coalesce(Duration),
+}
+
+/// Applies a [Debounce] setting to a stream of keyed events
+#[derive(Debug)]
+struct Debouncer<K, T> {
+    mode: Debounce,
+    /// When an event was last delivered for each key
+    last_sent: HashMap<K, Instant>,
+    /// Latest held back event of each key and when it is due
+    pending: HashMap<K, (Instant, T)>,
+}
+
+impl<K: Hash + Eq + Clone, T> Debouncer<K, T> {
+    fn new(mode: Debounce) -> Self {
+        Self {
+            mode,
+            last_sent: HashMap::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Handles the given event received at the given time. Returns the event
+    /// if it should be delivered right away.
+    fn on_event(&mut self, key: K, event: T, now: Instant) -> Option<T> {
+        let interval = match self.mode {
+            Debounce::Off => return Some(event),
+            Debounce::Throttle(interval) | Debounce::Coalesce(interval) => interval,
+        };
+        let due = self.last_sent.get(&key).map(|sent| *sent + interval);
+        match due {
+            Some(due) if due > now => {
+                if matches!(self.mode, Debounce::Coalesce(_)) {
+                    self.pending.insert(key, (due, event));
+                }
+                None
+            }
+            _ => {
+                self.pending.remove(&key);
+                self.last_sent.insert(key, now);
+                Some(event)
+            }
+        }
+    }
+
+    /// Returns when the next held back event is due
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.values().map(|(due, _)| *due).min()
+    }
+
+    /// Returns the held back events that are due at the given time
+    fn flush(&mut self, now: Instant) -> Vec<T> {
+        let keys: Vec<K> = self
+            .pending
+            .iter()
+            .filter(|(_, (due, _))| *due <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let mut events = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some((_, event)) = self.pending.remove(&key) {
+                self.last_sent.insert(key, now);
+                events.push(event);
+            }
+        }
+
+        events
+    }
+
+    /// Returns all held back events
+    fn drain(&mut self) -> Vec<T> {
+        self.pending.drain().map(|(_, (_, event))| event).collect()
+    }
+}
+
+/// Spawns a thread that forwards the events received from the given channel,
+/// limited per key according to the given [Debounce] setting. The key of each
+/// event is returned by the given function.
+pub(crate) fn spawn_debouncer<T, K, F>(
+    display: &str,
+    options: &ConnectOptions,
+    events: Receiver<T>,
+    mode: Debounce,
+    key: F,
+) -> (JoinHandle<()>, Receiver<T>)
+where
+    T: Send + 'static,
+    K: Hash + Eq + Clone + Send + 'static,
+    F: Fn(&T) -> K + Send + 'static,
+{
+    let (tx, rx): (Sender<T>, Receiver<T>) = mpsc::channel();
+
+    let child = worker::spawn(display, "debounce", options, move || {
+        let mut debouncer = Debouncer::new(mode);
+        loop {
+            let result = match debouncer.next_due() {
+                Some(due) => events.recv_timeout(due.saturating_duration_since(Instant::now())),
+                None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let ready = match result {
+                Ok(event) => {
+                    let now = Instant::now();
+                    let mut ready = debouncer.flush(now);
+                    ready.extend(debouncer.on_event(key(&event), event, now));
+                    ready
+                }
+                Err(RecvTimeoutError::Timeout) => debouncer.flush(Instant::now()),
+                Err(RecvTimeoutError::Disconnected) => {
+                    for event in debouncer.drain() {
+                        let _ = tx.send(event);
+                    }
+                    break;
+                }
+            };
+            for event in ready {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    (child, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debounce_modes() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let interval = Duration::from_millis(100);
+
+        let mut throttle = Debouncer::new(Debounce::Throttle(interval));
+        assert_eq!(throttle.on_event("counter", 1, at(0)), Some(1));
+        assert_eq!(throttle.on_event("counter", 2, at(10)), None);
+        assert_eq!(throttle.on_event("focus", 3, at(20)), Some(3));
+        assert_eq!(throttle.next_due(), None);
+        assert_eq!(throttle.on_event("counter", 4, at(100)), Some(4));
+
+        let mut coalesce = Debouncer::new(Debounce::Coalesce(interval));
+        assert_eq!(coalesce.on_event("counter", 1, at(0)), Some(1));
+        assert_eq!(coalesce.on_event("counter", 2, at(10)), None);
+        assert_eq!(coalesce.on_event("counter", 3, at(20)), None);
+        assert_eq!(coalesce.next_due(), Some(at(100)));
+        assert!(coalesce.flush(at(50)).is_empty());
+        assert_eq!(coalesce.flush(at(100)), vec![3]);
+        assert_eq!(coalesce.on_event("counter", 4, at(150)), None);
+        assert_eq!(coalesce.drain(), vec![4]);
+
+        let mut off = Debouncer::new(Debounce::Off);
+        assert_eq!(off.on_event("counter", 1, at(0)), Some(1));
+        assert_eq!(off.on_event("counter", 2, at(0)), Some(2));
+    }
+}
@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use x11rb::errors::ConnectError;
+
+use crate::{
+    atoms::GamescopeAtom,
+    connection::{ConnectOptions, SharedConnection},
+    worker, x11,
+    xwayland::{Disconnected, XWayland},
+};
+
+/// Directory where X servers create their sockets by default
+pub const X11_SOCKET_DIR: &str = "/tmp/.X11-unix";
+
+/// Kernel table of all unix domain sockets
+const PROC_NET_UNIX: &str = "/proc/net/unix";
+
+/// Socket flag set on listening sockets (__SO_ACCEPTCON)
+const SO_ACCEPTCON: &str = "00010000";
+
+/// [DiscoveryOptions] select which strategies are used to find X11 displays
+/// and how to connect to them. All strategies are enabled by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveryOptions {
+    /// List the sockets in /tmp/.X11-unix, including abstract sockets found
+    /// in /proc/net/unix
+    pub socket_dir: bool,
+    /// Scan $XDG_RUNTIME_DIR for X11 sockets, for setups that place them
+    /// outside of /tmp/.X11-unix
+    pub xdg_runtime_dir: bool,
+    /// Include the display from $DISPLAY
+    pub display_env: bool,
+    /// Maximum time to wait for a display to answer when probing whether it
+    /// is a Gamescope XWayland. All displays are probed concurrently.
+    pub probe_timeout: Duration,
+    /// Options used to connect to each found display
+    pub connect: ConnectOptions,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            socket_dir: true,
+            xdg_runtime_dir: true,
+            display_env: true,
+            probe_timeout: Duration::from_secs(5),
+            connect: ConnectOptions::default(),
+        }
+    }
+}
+
+/// Outcome of probing a single X11 display during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DisplayStatus {
+    /// The display is a Gamescope XWayland
+    Gamescope,
+    /// Connected to the display, but it is not a Gamescope XWayland
+    NotGamescope,
+    /// The socket was found but connecting to it failed
+    ConnectFailed(String),
+    /// The X server rejected the connection because of missing or wrong
+    /// authentication
+    AuthFailed(String),
+    /// Connected to the display, but querying it failed
+    QueryFailed(String),
+    /// The display did not answer within the probe timeout
+    TimedOut,
+}
+
+/// Diagnostics for a single X11 display found during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayDiagnostic {
+    /// Name of the display (E.g. ":0")
+    pub name: String,
+    /// What happened when probing the display
+    pub status: DisplayStatus,
+}
+
+/// [DiscoveryReport] contains the discovered Gamescope XWayland instances
+/// along with the diagnostics of every display that was probed, so callers
+/// can explain why a display was not found.
+#[derive(Debug, Default)]
+pub struct DiscoveryReport {
+    /// Discovered Gamescope XWayland instances that are not yet connected
+    pub xwaylands: Vec<XWayland<Disconnected>>,
+    /// Diagnostics of every probed display
+    pub diagnostics: Vec<DisplayDiagnostic>,
+}
+
+/// [DiscoveredDisplay] describes an X11 display found during discovery
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DiscoveredDisplay {
+    /// Name of the display (E.g. ":1")
+    pub name: String,
+    /// Path of the socket the display listens on. Abstract sockets are
+    /// prefixed with "@" (E.g. "@/tmp/.X11-unix/X1").
+    pub socket_path: Option<String>,
+    /// Process ID of the Xwayland server owning the socket
+    pub xwayland_pid: Option<u32>,
+    /// True if the display is a Gamescope XWayland
+    pub is_gamescope: bool,
+    /// True if the display is the primary Gamescope XWayland
+    pub is_primary: bool,
+}
+
+/// A listening X11 socket from /proc/net/unix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UnixSocket {
+    /// Name of the display (E.g. ":1")
+    pub display: String,
+    /// Path of the socket, prefixed with "@" for abstract sockets
+    pub path: String,
+    /// Inode of the socket
+    pub inode: u64,
+}
+
+/// Errors returned when a specific Gamescope XWayland could not be discovered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiscoveryError {
+    /// No display is the primary Gamescope XWayland
+    NoPrimaryFound,
+}
+
+impl fmt::Display for DiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiscoveryError::NoPrimaryFound => {
+                write!(f, "No primary Gamescope XWayland instance found")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DiscoveryError {}
+
+/// Result of probing a single display
+#[derive(Debug, Clone)]
+pub(crate) struct Probe {
+    pub status: DisplayStatus,
+    /// True if the display is the primary Gamescope XWayland
+    pub is_primary: bool,
+}
+
+/// Probes all given displays concurrently and returns the result for each
+/// display in the same order. Displays that don't answer within the probe
+/// timeout are reported as [DisplayStatus::TimedOut].
+pub(crate) fn probe_displays(displays: &[String], options: &DiscoveryOptions) -> Vec<Probe> {
+    let (tx, rx) = mpsc::channel();
+    for (index, display) in displays.iter().enumerate() {
+        let tx = tx.clone();
+        let name = display.clone();
+        let connect = options.connect.clone();
+        // Threads of hung displays are left behind and exit once the server
+        // answers or the connection fails.
+        worker::spawn(display, "probe", &options.connect, move || {
+            let _ = tx.send((index, probe_display(name.as_str(), &connect)));
+        });
+    }
+    drop(tx);
+
+    let timed_out = Probe {
+        status: DisplayStatus::TimedOut,
+        is_primary: false,
+    };
+    let mut probes = vec![timed_out; displays.len()];
+    let deadline = Instant::now() + options.probe_timeout;
+    while let Ok((index, probe)) =
+        rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+    {
+        probes[index] = probe;
+    }
+
+    probes
+}
+
+/// Connects to the given display and checks if it is a Gamescope XWayland
+fn probe_display(display: &str, options: &ConnectOptions) -> Probe {
+    // The tracing macros shadow `display`, so the name is recorded by value
+    #[cfg(feature = "tracing")]
+    let _span = {
+        let name = display.to_string();
+        tracing::debug_span!("probe_display", display = name).entered()
+    };
+    let probe = |status| Probe {
+        status,
+        is_primary: false,
+    };
+
+    // Connect to the display
+    let conn = match SharedConnection::connect(display, options.clone()) {
+        Ok(conn) => conn,
+        Err(err) => return probe(DisplayStatus::from_connect_error(err.as_ref())),
+    };
+    let root_window_id = conn.root_window_id();
+    let conn = conn.get();
+
+    // Check to see if the root window has gamescope-specific properties
+    match x11::is_gamescope_xwayland(conn.as_ref(), root_window_id) {
+        Ok(true) => (),
+        Ok(false) => return probe(DisplayStatus::NotGamescope),
+        Err(err) => return probe(DisplayStatus::QueryFailed(err.to_string())),
+    }
+
+    // Only the primary instance has the keyboard focus display property
+    let primary_atom = GamescopeAtom::KeyboardFocusDisplay.to_string();
+    let is_primary =
+        x11::has_property(conn.as_ref(), root_window_id, primary_atom.as_str()).unwrap_or(false);
+
+    Probe {
+        status: DisplayStatus::Gamescope,
+        is_primary,
+    }
+}
+
+/// Returns the display names (E.g. ":1") of all sockets in the given directory
+/// that are named like X11 sockets (E.g. "X1").
+pub(crate) fn list_socket_dir(dir: &Path) -> Result<Vec<String>, std::io::Error> {
+    let mut displays: Vec<String> = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let Some(number) = name.to_str().and_then(socket_display_number) else {
+            continue;
+        };
+        displays.push(format!(":{}", number));
+    }
+
+    Ok(displays)
+}
+
+/// Returns the display names (E.g. ":1") of all X11 sockets listening in
+/// /proc/net/unix.
+pub(crate) fn list_proc_net_unix() -> Result<Vec<String>, std::io::Error> {
+    let mut displays: Vec<String> = list_unix_sockets()?
+        .into_iter()
+        .map(|socket| socket.display)
+        .collect();
+    dedup_displays(&mut displays);
+
+    Ok(displays)
+}
+
+/// Returns all listening X11 sockets in /proc/net/unix
+pub(crate) fn list_unix_sockets() -> Result<Vec<UnixSocket>, std::io::Error> {
+    let contents = fs::read_to_string(PROC_NET_UNIX)?;
+    Ok(parse_proc_net_unix(contents.as_str()))
+}
+
+/// Parses all listening X11 sockets from the contents of /proc/net/unix. Both
+/// filesystem ("/tmp/.X11-unix/X1") and abstract ("@/tmp/.X11-unix/X1")
+/// sockets are included.
+fn parse_proc_net_unix(contents: &str) -> Vec<UnixSocket> {
+    let prefix = format!("{}/", X11_SOCKET_DIR);
+    let mut sockets: Vec<UnixSocket> = Vec::new();
+
+    // Columns: Num RefCount Protocol Flags Type St Inode Path
+    for line in contents.lines().skip(1) {
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let (Some(flags), Some(inode), Some(path)) =
+            (columns.get(3), columns.get(6), columns.get(7))
+        else {
+            continue;
+        };
+        if *flags != SO_ACCEPTCON {
+            continue;
+        }
+        let Ok(inode) = inode.parse() else {
+            continue;
+        };
+        let Some(number) = path
+            .strip_prefix('@')
+            .unwrap_or(path)
+            .strip_prefix(prefix.as_str())
+            .and_then(socket_display_number)
+        else {
+            continue;
+        };
+        sockets.push(UnixSocket {
+            display: format!(":{}", number),
+            path: path.to_string(),
+            inode,
+        });
+    }
+
+    sockets
+}
+
+/// Returns the process ID of the process holding one of the given socket
+/// inodes open. Xwayland processes are preferred over other processes (E.g.
+/// Gamescope itself, which creates the sockets and passes them to Xwayland).
+pub(crate) fn find_socket_owner(inodes: &[u64]) -> Option<u32> {
+    if inodes.is_empty() {
+        return None;
+    }
+    let targets: Vec<String> = inodes
+        .iter()
+        .map(|inode| format!("socket:[{}]", inode))
+        .collect();
+
+    let mut owner: Option<u32> = None;
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        // Processes of other users can't be inspected, so skip any errors
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        let holds_socket = fds.flatten().any(|fd| {
+            fs::read_link(fd.path()).is_ok_and(|link| {
+                targets
+                    .iter()
+                    .any(|target| link.as_os_str() == target.as_str())
+            })
+        });
+        if !holds_socket {
+            continue;
+        }
+
+        let comm = fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+        if comm.trim() == "Xwayland" {
+            return Some(pid);
+        }
+        owner.get_or_insert(pid);
+    }
+
+    owner
+}
+
+/// Removes duplicate display names while keeping their order
+pub(crate) fn dedup_displays(displays: &mut Vec<String>) {
+    let mut seen = HashSet::new();
+    displays.retain(|display| seen.insert(display.clone()));
+}
+
+/// Returns the socket paths of all X11 sockets in $XDG_RUNTIME_DIR and its
+/// ".X11-unix" subdirectory. Sockets outside of /tmp/.X11-unix can't be
+/// addressed by display number, so their full path is used as display name.
+pub(crate) fn list_xdg_runtime_dir() -> Vec<String> {
+    let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR") else {
+        return Vec::new();
+    };
+    let runtime_dir = Path::new(&runtime_dir);
+
+    let mut displays: Vec<String> = Vec::new();
+    for dir in [runtime_dir.to_path_buf(), runtime_dir.join(".X11-unix")] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let is_socket = entry.file_type().is_ok_and(|kind| kind.is_socket());
+            let name = entry.file_name();
+            if !is_socket || name.to_str().and_then(socket_display_number).is_none() {
+                continue;
+            }
+            displays.push(entry.path().to_string_lossy().to_string());
+        }
+    }
+
+    displays
+}
+
+/// Returns the path of the filesystem socket of the given display, if it
+/// exists (E.g. "/tmp/.X11-unix/X1" for ":1")
+pub(crate) fn socket_path_for_display(display: &str) -> Option<String> {
+    let path = match display.strip_prefix(':') {
+        Some(number) => format!("{}/X{}", X11_SOCKET_DIR, number.split('.').next()?),
+        None => display.to_string(),
+    };
+    Path::new(path.as_str()).exists().then_some(path)
+}
+
+/// Returns the display from $DISPLAY, if set
+pub(crate) fn display_from_env() -> Option<String> {
+    env::var("DISPLAY")
+        .ok()
+        .filter(|display| !display.is_empty())
+}
+
+/// Returns the display number of an X11 socket name (E.g. "1" for "X1")
+fn socket_display_number(name: &str) -> Option<&str> {
+    let number = name.strip_prefix('X')?;
+    number.parse::<u64>().ok().map(|_| number)
+}
+
+impl DisplayStatus {
+    /// Returns the status for a failed connection attempt
+    pub(crate) fn from_connect_error(err: &(dyn std::error::Error + 'static)) -> Self {
+        match err.downcast_ref::<ConnectError>() {
+            Some(ConnectError::SetupAuthenticate(setup)) => {
+                DisplayStatus::AuthFailed(String::from_utf8_lossy(&setup.reason).to_string())
+            }
+            Some(ConnectError::SetupFailed(setup)) => {
+                DisplayStatus::AuthFailed(String::from_utf8_lossy(&setup.reason).to_string())
+            }
+            _ => DisplayStatus::ConnectFailed(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proc_net_unix() {
+        let contents = "\
+Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 31337 @/tmp/.X11-unix/X1
+0000000000000000: 00000002 00000000 00010000 0001 01 31338 /tmp/.X11-unix/X1
+0000000000000000: 00000002 00000000 00010000 0001 01 31339 @/tmp/.X11-unix/X2
+0000000000000000: 00000003 00000000 00000000 0001 03 31340 /tmp/.X11-unix/X0
+0000000000000000: 00000002 00000000 00010000 0001 01 31341 /run/user/1000/gamescope-0
+0000000000000000: 00000003 00000000 00000000 0001 03 31342
+";
+        let displays: Vec<String> = parse_proc_net_unix(contents)
+            .into_iter()
+            .map(|socket| socket.display)
+            .collect();
+        assert_eq!(displays, vec![":1", ":1", ":2"]);
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::path::Path;
+
+/// Fixed header every EDID starts with
+const EDID_HEADER: [u8; 8] = [0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00];
+
+/// Size of the EDID base block
+const EDID_BLOCK_SIZE: usize = 128;
+
+/// Information about the display Gamescope is outputting to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DisplayInfo {
+    /// True if the display is an external display (E.g. a TV the device is
+    /// docked to) instead of the built-in panel
+    pub is_external: bool,
+    /// Path of the EDID file Gamescope wrote for the display
+    pub edid_path: Option<String>,
+    /// Identity of the display read from its EDID
+    pub edid: Option<Edid>,
+}
+
+/// Identity of a display parsed from the base block of its EDID
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Edid {
+    /// Three letter PNP id of the manufacturer (E.g. "VLV")
+    pub manufacturer: String,
+    /// Manufacturer product code
+    pub product_code: u16,
+    /// Monitor name from the display descriptors, if set
+    pub name: Option<String>,
+}
+
+impl Edid {
+    /// Parses the given EDID. Returns None if it is not a valid EDID.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < EDID_BLOCK_SIZE || data[..8] != EDID_HEADER {
+            return None;
+        }
+
+        // Manufacturer id is three 5-bit letters where 1 is 'A'
+        let id = u16::from_be_bytes([data[8], data[9]]);
+        let manufacturer: String = [10, 5, 0]
+            .iter()
+            .map(|shift| char::from(b'A' - 1 + ((id >> shift) & 0x1f) as u8))
+            .collect();
+        let product_code = u16::from_le_bytes([data[10], data[11]]);
+
+        // The monitor name is stored in a display descriptor with tag 0xfc
+        let name = data[54..126]
+            .chunks(18)
+            .find(|descriptor| descriptor[..3] == [0, 0, 0] && descriptor[3] == 0xfc)
+            .map(|descriptor| {
+                let text = &descriptor[5..];
+                let end = text.iter().position(|c| *c == b'\n').unwrap_or(text.len());
+                String::from_utf8_lossy(&text[..end]).trim().to_string()
+            });
+
+        Some(Self {
+            manufacturer,
+            product_code,
+            name,
+        })
+    }
+
+    /// Reads and parses the EDID file at the given path
+    pub fn read(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = fs::read(path)?;
+        Self::parse(&data).ok_or_else(|| format!("Invalid EDID: {}", path.display()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_edid() {
+        let mut data = [0u8; EDID_BLOCK_SIZE];
+        data[..8].copy_from_slice(&EDID_HEADER);
+        // "VLV" = 22, 12, 22
+        data[8..10].copy_from_slice(&((22u16 << 10) | (12 << 5) | 22).to_be_bytes());
+        data[10..12].copy_from_slice(&0x3003u16.to_le_bytes());
+        data[72..77].copy_from_slice(&[0, 0, 0, 0xfc, 0]);
+        data[77..90].copy_from_slice(b"ANX7530 U\n   ");
+
+        let edid = Edid::parse(&data).unwrap();
+        assert_eq!(edid.manufacturer, "VLV");
+        assert_eq!(edid.product_code, 0x3003);
+        assert_eq!(edid.name.as_deref(), Some("ANX7530 U"));
+
+        assert_eq!(Edid::parse(&data[..64]), None);
+    }
+}
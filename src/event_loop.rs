@@ -0,0 +1,540 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        xproto::{
+            ChangeWindowAttributesAux, ClientMessageEvent, ConnectionExt, CreateWindowAux,
+            EventMask, WindowClass,
+        },
+        Event,
+    },
+    COPY_FROM_PARENT,
+};
+
+use crate::{
+    atom_cache::AtomCache,
+    connection::{ConnectionEvent, SharedConnection, X11Connection},
+    worker,
+};
+
+/// An event paired with the connection it was received on and the cache of
+/// atoms interned on that connection
+pub(crate) type RawEvent = (Arc<X11Connection>, Arc<AtomCache>, Event);
+
+/// A single listener registered with the [EventLoop]
+#[derive(Debug)]
+struct Subscriber {
+    window_id: u32,
+    event_mask: EventMask,
+    tx: Arc<Sender<RawEvent>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    subscribers: Vec<Subscriber>,
+    /// Combined event mask of all subscribers of each window
+    masks: HashMap<u32, EventMask>,
+    /// Event mask this client had selected on each window before the first
+    /// subscriber was added (E.g. by code using the connection directly),
+    /// kept when selecting and restored when the last subscriber is gone
+    base_masks: HashMap<u32, EventMask>,
+    /// Listeners for connection state changes
+    status_subscribers: Vec<Sender<ConnectionEvent>>,
+    /// Unmapped window owned by this client used to wake up the event thread
+    /// on shutdown.
+    wakeup_window_id: u32,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// [EventLoop] reads events from the connection shared with the query API on a
+/// single background thread and dispatches them to the subscribers of the
+/// window each event was reported on. The thread is started when the first
+/// subscriber is added and stops when the loop is dropped. If a
+/// [crate::connection::ReconnectPolicy] is set, the loop reconnects when the
+/// connection is lost and subscribes all windows again.
+#[derive(Debug)]
+pub(crate) struct EventLoop {
+    conn: SharedConnection,
+    state: Arc<Mutex<State>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl EventLoop {
+    /// Create a new event loop for the given connection
+    pub fn new(conn: SharedConnection) -> Result<Self, Box<dyn std::error::Error>> {
+        let wakeup_window_id = create_wakeup_window(conn.get().as_ref(), conn.root_window_id())?;
+        let state = State {
+            wakeup_window_id,
+            ..Default::default()
+        };
+
+        Ok(Self {
+            conn,
+            state: Arc::new(Mutex::new(state)),
+            stopped: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Select the given event mask on the given window and return a receiver
+    /// for all events reported on that window. The event mask is combined with
+    /// the masks of any other subscribers of the same window and the mask
+    /// already selected on the window by this client. Once the receiver is
+    /// dropped, the mask is released again.
+    pub fn subscribe(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+    ) -> Result<Receiver<RawEvent>, Box<dyn std::error::Error>> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribe_with(window_id, event_mask, Arc::new(tx))?;
+
+        Ok(rx)
+    }
+
+    /// Select the given event mask on the given window like
+    /// [EventLoop::subscribe] and send its events through the given sender.
+    /// This allows a single receiver to get the events of several windows.
+    /// The subscription ends once the receiver is dropped or the window is
+    /// destroyed.
+    pub fn subscribe_with(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+        tx: Arc<Sender<RawEvent>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let conn = self.conn.get();
+
+        // Keep any events already selected on the window when it is
+        // subscribed to for the first time
+        let selected = match state.masks.get(&window_id) {
+            Some(mask) => *mask,
+            None => {
+                let base = get_event_mask(conn.as_ref(), window_id)?;
+                state.base_masks.insert(window_id, base);
+                base
+            }
+        };
+
+        // Set the event mask to start listening for events
+        let mask = selected | event_mask;
+        if mask != selected || !state.masks.contains_key(&window_id) {
+            select_events(conn.as_ref(), window_id, mask)?;
+        }
+        state.masks.insert(window_id, mask);
+
+        state.subscribers.push(Subscriber {
+            window_id,
+            event_mask,
+            tx,
+        });
+        self.ensure_running(&mut state);
+
+        Ok(())
+    }
+
+    /// Ends all subscriptions made with the given sender and releases their
+    /// event masks. Once the caller drops its own copy of the sender, the
+    /// receiver is disconnected without waiting for another event.
+    #[cfg_attr(not(any(feature = "dbus", feature = "ffi")), allow(dead_code))]
+    pub fn unsubscribe(
+        &self,
+        tx: &Arc<Sender<RawEvent>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let mut windows: Vec<u32> = Vec::new();
+        state.subscribers.retain(|subscriber| {
+            if !Arc::ptr_eq(&subscriber.tx, tx) {
+                return true;
+            }
+            windows.push(subscriber.window_id);
+            false
+        });
+
+        let conn = self.conn.get();
+        windows.sort_unstable();
+        windows.dedup();
+        for window_id in windows {
+            release_events(conn.as_ref(), &mut state, window_id);
+        }
+
+        Ok(())
+    }
+
+    /// Select the given event mask on the given window outside of any
+    /// subscription, replacing the mask selected with this method before.
+    /// The masks of the subscribers of the window are kept and the given mask
+    /// stays selected once the last of them is gone.
+    pub fn select(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let conn = self.conn.get();
+
+        if !state.masks.contains_key(&window_id) {
+            return select_events(conn.as_ref(), window_id, event_mask);
+        }
+
+        let mask = state
+            .subscribers
+            .iter()
+            .filter(|subscriber| subscriber.window_id == window_id)
+            .fold(event_mask, |mask, subscriber| mask | subscriber.event_mask);
+        select_events(conn.as_ref(), window_id, mask)?;
+        state.base_masks.insert(window_id, event_mask);
+        state.masks.insert(window_id, mask);
+
+        Ok(())
+    }
+
+    /// Returns a receiver for changes to the state of the connection
+    pub fn subscribe_status(
+        &self,
+    ) -> Result<Receiver<ConnectionEvent>, Box<dyn std::error::Error>> {
+        let mut state = self.state.lock().map_err(|err| err.to_string())?;
+        let (tx, rx) = mpsc::channel();
+        state.status_subscribers.push(tx);
+        self.ensure_running(&mut state);
+
+        Ok(rx)
+    }
+
+    /// Returns true if the thread that reads events from the connection was
+    /// started
+    pub fn is_running(&self) -> bool {
+        self.state
+            .lock()
+            .map(|state| state.thread.is_some())
+            .unwrap_or(true)
+    }
+
+    /// Spawn the thread that reads and dispatches events if it is not already
+    /// running
+    fn ensure_running(&self, state: &mut State) {
+        if state.thread.is_some() {
+            return;
+        }
+
+        let conn = self.conn.clone();
+        let shared = self.state.clone();
+        let stopped = self.stopped.clone();
+        let display = conn.name().to_string();
+        let options = conn.options().clone();
+        state.thread = Some(worker::spawn(&display, "events", &options, move || {
+            run(conn, shared, stopped);
+        }));
+    }
+}
+
+impl Drop for EventLoop {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let Ok(state) = self.state.lock() else {
+            return;
+        };
+
+        // Send an event to our own window to wake up the event thread
+        let conn = self.conn.get();
+        let window_id = state.wakeup_window_id;
+        let event = ClientMessageEvent::new(32, window_id, 0u32, [0u32; 5]);
+        let result = conn.send_event(false, window_id, EventMask::NO_EVENT, event);
+        if let Err(err) = result.map(|_| conn.flush()) {
+            log::debug!("Error waking up event thread: {}", err);
+        }
+    }
+}
+
+/// Reads and dispatches events until the loop is stopped or the connection is
+/// lost and cannot be recovered.
+fn run(conn: SharedConnection, state: Arc<Mutex<State>>, stopped: Arc<AtomicBool>) {
+    loop {
+        let (current, atoms) = conn.get_with_atoms();
+        let event = match current.wait_for_event() {
+            Ok(event) => event,
+            Err(err) => {
+                log::error!("Error waiting for X event: {}", err);
+                if stopped.load(Ordering::SeqCst) || !reconnect(&conn, &state, &stopped) {
+                    break;
+                }
+                continue;
+            }
+        };
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let Some(window_id) = event_window(&event) else {
+            log::debug!("Ignoring X event without a window: {:?}", event);
+            continue;
+        };
+        let destroyed = match &event {
+            Event::DestroyNotify(event) => Some(event.window),
+            _ => None,
+        };
+        let Ok(mut state) = state.lock() else {
+            break;
+        };
+
+        // Dispatch the event and forget any subscribers that hung up. The
+        // last subscriber gets the event itself instead of a copy.
+        let mut remaining = state
+            .subscribers
+            .iter()
+            .filter(|subscriber| subscriber.window_id == window_id)
+            .count();
+        let subscribed = remaining;
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("dispatch", window_id, subscribed).entered();
+        let mut event = Some(event);
+        state.subscribers.retain(|subscriber| {
+            if subscriber.window_id != window_id {
+                return true;
+            }
+            remaining -= 1;
+            let event = if remaining == 0 {
+                event.take()
+            } else {
+                event.clone()
+            };
+            let Some(event) = event else {
+                return true;
+            };
+            subscriber
+                .tx
+                .send((current.clone(), atoms.clone(), event))
+                .is_ok()
+        });
+        if remaining_subscribers(&state, window_id) != subscribed {
+            release_events(current.as_ref(), &mut state, window_id);
+        }
+
+        // A destroyed window will not report any more events, so its
+        // subscribers can be dropped
+        if let Some(destroyed) = destroyed {
+            forget_window(&mut state, destroyed);
+        }
+    }
+
+    // Drop all subscribers so their listener threads can exit
+    if let Ok(mut state) = state.lock() {
+        state.subscribers.clear();
+        state.status_subscribers.clear();
+    }
+}
+
+/// Returns the number of subscribers of the given window
+fn remaining_subscribers(state: &State, window_id: u32) -> usize {
+    state
+        .subscribers
+        .iter()
+        .filter(|subscriber| subscriber.window_id == window_id)
+        .count()
+}
+
+/// Forgets the subscribers and event masks of the given destroyed window
+fn forget_window(state: &mut State, window_id: u32) {
+    state
+        .subscribers
+        .retain(|subscriber| subscriber.window_id != window_id);
+    state.masks.remove(&window_id);
+    state.base_masks.remove(&window_id);
+}
+
+/// Narrows the event mask selected on the given window to the masks of its
+/// remaining subscribers after some of them hung up. Once no subscriber is
+/// left, the mask the window had before it was first subscribed to is
+/// restored.
+fn release_events(conn: &X11Connection, state: &mut State, window_id: u32) {
+    let base = state
+        .base_masks
+        .get(&window_id)
+        .copied()
+        .unwrap_or(EventMask::NO_EVENT);
+    let mask = state
+        .subscribers
+        .iter()
+        .filter(|subscriber| subscriber.window_id == window_id)
+        .fold(base, |mask, subscriber| mask | subscriber.event_mask);
+
+    if state.masks.get(&window_id) != Some(&mask) {
+        // The window may already be destroyed
+        if let Err(err) = select_events(conn, window_id, mask) {
+            log::debug!("Unable to release events of window {}: {}", window_id, err);
+        }
+    }
+    if remaining_subscribers(state, window_id) == 0 {
+        state.masks.remove(&window_id);
+        state.base_masks.remove(&window_id);
+    } else {
+        state.masks.insert(window_id, mask);
+    }
+}
+
+/// Tries to re-establish a lost connection according to the reconnect policy
+/// and subscribes all windows again. Returns false if reconnecting is disabled
+/// or failed.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(display = conn.name()))
+)]
+fn reconnect(conn: &SharedConnection, state: &Mutex<State>, stopped: &AtomicBool) -> bool {
+    let Some(policy) = conn.policy() else {
+        return false;
+    };
+    notify_status(state, ConnectionEvent::Disconnected);
+
+    let old_root_window_id = conn.root_window_id();
+    let mut attempts = 0;
+    loop {
+        if stopped.load(Ordering::SeqCst) {
+            return false;
+        }
+        if policy.max_attempts.is_some_and(|max| attempts >= max) {
+            log::error!("Giving up reconnecting to {}", conn.name());
+            return false;
+        }
+        attempts += 1;
+        std::thread::sleep(policy.interval);
+
+        match conn.reconnect() {
+            Ok(_) => break,
+            Err(err) => log::debug!("Failed to reconnect to {}: {}", conn.name(), err),
+        }
+    }
+    log::info!("Reconnected to {}", conn.name());
+
+    let current = conn.get();
+    let root_window_id = conn.root_window_id();
+    let Ok(mut guard) = state.lock() else {
+        return false;
+    };
+    match create_wakeup_window(current.as_ref(), root_window_id) {
+        Ok(window_id) => guard.wakeup_window_id = window_id,
+        Err(err) => log::warn!("Unable to create wakeup window: {}", err),
+    }
+
+    // Select the event masks on the new connection. Only the root window is
+    // expected to survive a restart of the server. Nothing else is selected
+    // on a new connection, so there are no previous masks to keep.
+    guard.base_masks.clear();
+    let masks = std::mem::take(&mut guard.masks);
+    for (window_id, mask) in masks {
+        let window_id = if window_id == old_root_window_id {
+            root_window_id
+        } else {
+            window_id
+        };
+        if let Err(err) = select_events(current.as_ref(), window_id, mask) {
+            log::warn!("Unable to subscribe to window {} again: {}", window_id, err);
+            continue;
+        }
+        guard.masks.insert(window_id, mask);
+    }
+    for subscriber in guard.subscribers.iter_mut() {
+        if subscriber.window_id == old_root_window_id {
+            subscriber.window_id = root_window_id;
+        }
+    }
+    drop(guard);
+
+    notify_status(state, ConnectionEvent::Reconnected);
+    true
+}
+
+/// Sends the given connection event to all status subscribers
+fn notify_status(state: &Mutex<State>, event: ConnectionEvent) {
+    if let Ok(mut state) = state.lock() {
+        state
+            .status_subscribers
+            .retain(|subscriber| subscriber.send(event).is_ok());
+    }
+}
+
+/// Select the given event mask on the given window
+fn select_events(
+    conn: &X11Connection,
+    window_id: u32,
+    event_mask: EventMask,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut attrs = ChangeWindowAttributesAux::new();
+    attrs.event_mask = Some(event_mask);
+    conn.change_window_attributes(window_id, &attrs)?.check()?;
+
+    Ok(())
+}
+
+/// Returns the event mask this client has selected on the given window
+fn get_event_mask(
+    conn: &X11Connection,
+    window_id: u32,
+) -> Result<EventMask, Box<dyn std::error::Error>> {
+    let attrs = conn.get_window_attributes(window_id)?.reply()?;
+    Ok(attrs.your_event_mask)
+}
+
+/// Create an unmapped window that can be used to send events to ourselves
+fn create_wakeup_window(
+    conn: &X11Connection,
+    root_window_id: u32,
+) -> Result<u32, Box<dyn std::error::Error>> {
+    let window_id = conn.generate_id()?;
+    conn.create_window(
+        COPY_FROM_PARENT as u8,
+        window_id,
+        root_window_id,
+        0,
+        0,
+        1,
+        1,
+        0,
+        WindowClass::INPUT_ONLY,
+        COPY_FROM_PARENT,
+        &CreateWindowAux::new(),
+    )?
+    .check()?;
+
+    Ok(window_id)
+}
+
+/// Returns the window the given event was reported on. This is the window
+/// whose event mask caused the event to be delivered to this client.
+fn event_window(event: &Event) -> Option<u32> {
+    let window_id = match event {
+        Event::PropertyNotify(event) => event.window,
+        Event::CreateNotify(event) => event.parent,
+        Event::DestroyNotify(event) => event.event,
+        Event::MapNotify(event) => event.event,
+        Event::UnmapNotify(event) => event.event,
+        Event::ReparentNotify(event) => event.event,
+        Event::ConfigureNotify(event) => event.event,
+        Event::GravityNotify(event) => event.event,
+        Event::CirculateNotify(event) => event.event,
+        Event::MapRequest(event) => event.parent,
+        Event::ConfigureRequest(event) => event.parent,
+        Event::CirculateRequest(event) => event.event,
+        Event::ResizeRequest(event) => event.window,
+        Event::FocusIn(event) => event.event,
+        Event::FocusOut(event) => event.event,
+        Event::EnterNotify(event) => event.event,
+        Event::LeaveNotify(event) => event.event,
+        Event::KeyPress(event) => event.event,
+        Event::KeyRelease(event) => event.event,
+        Event::ButtonPress(event) => event.event,
+        Event::ButtonRelease(event) => event.event,
+        Event::MotionNotify(event) => event.event,
+        Event::Expose(event) => event.window,
+        Event::VisibilityNotify(event) => event.window,
+        Event::ColormapNotify(event) => event.window,
+        Event::ClientMessage(event) => event.window,
+        _ => return None,
+    };
+
+    Some(window_id)
+}
@@ -0,0 +1,163 @@
+use std::fmt;
+
+use x11rb::connection::RequestConnection;
+
+/// X11 extensions used by this crate. Extensions are only queried the first
+/// time they are needed, and features that depend on an extension the X
+/// server does not provide fail with [ExtensionMissing].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Extension {
+    /// X-Resource, used to look up the process ID of a window's client
+    XRes,
+    /// XTEST, used to send fake input events
+    XTest,
+}
+
+impl Extension {
+    /// All extensions used by this crate
+    pub const ALL: [Extension; 2] = [Extension::XRes, Extension::XTest];
+
+    /// Returns the name the X server knows the extension as
+    pub fn name(&self) -> &'static str {
+        match self {
+            Extension::XRes => "X-Resource",
+            Extension::XTest => "XTEST",
+        }
+    }
+}
+
+impl fmt::Display for Extension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Error returned when a feature needs an X11 extension that the X server does
+/// not provide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtensionMissing(pub Extension);
+
+impl fmt::Display for ExtensionMissing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The X server does not support the {} extension", self.0)
+    }
+}
+
+impl std::error::Error for ExtensionMissing {}
+
+/// How the process ID of a window's client is looked up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PidLookup {
+    /// Ask the X server for the process ID of the client using XRes
+    XRes,
+    /// Read the _NET_WM_PID property the client set on the window. This is
+    /// used when XRes is missing and only works for clients that set it.
+    NetWmPid,
+}
+
+/// [Capabilities] describes which X11 extensions the X server provides and
+/// which degraded fallback paths are used because of missing ones.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Capabilities {
+    /// Extensions the X server provides
+    pub extensions: Vec<Extension>,
+}
+
+impl Capabilities {
+    /// Returns true if the X server provides the given extension
+    pub fn has_extension(&self, extension: Extension) -> bool {
+        self.extensions.contains(&extension)
+    }
+
+    /// Returns how process IDs of windows are looked up
+    pub fn pid_lookup(&self) -> PidLookup {
+        if self.has_extension(Extension::XRes) {
+            PidLookup::XRes
+        } else {
+            PidLookup::NetWmPid
+        }
+    }
+
+    /// Returns the extensions used by this crate that the X server does not
+    /// provide
+    pub fn missing_extensions(&self) -> Vec<Extension> {
+        Extension::ALL
+            .into_iter()
+            .filter(|extension| !self.has_extension(*extension))
+            .collect()
+    }
+
+    /// Returns true if any fallback path is used because of a missing
+    /// extension
+    pub fn is_degraded(&self) -> bool {
+        self.pid_lookup() != PidLookup::XRes
+    }
+}
+
+/// Returns the capabilities of the X server
+pub(crate) fn get_capabilities<F>(conn: F) -> Result<Capabilities, Box<dyn std::error::Error>>
+where
+    F: RequestConnection,
+{
+    let mut extensions: Vec<Extension> = Vec::new();
+    for extension in Extension::ALL {
+        if has_extension(&conn, extension)? {
+            extensions.push(extension);
+        }
+    }
+
+    Ok(Capabilities { extensions })
+}
+
+/// Returns true if the X server provides the given extension. The result is
+/// cached by the connection, so only the first call makes a request.
+pub(crate) fn has_extension<F>(
+    conn: F,
+    extension: Extension,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: RequestConnection,
+{
+    Ok(conn.extension_information(extension.name())?.is_some())
+}
+
+/// Returns an [ExtensionMissing] error if the X server does not provide the
+/// given extension
+pub(crate) fn require_extension<F>(
+    conn: F,
+    extension: Extension,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: RequestConnection,
+{
+    if !has_extension(conn, extension)? {
+        return Err(ExtensionMissing(extension).into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities_fallbacks() {
+        let full = Capabilities {
+            extensions: Extension::ALL.to_vec(),
+        };
+        assert_eq!(full.pid_lookup(), PidLookup::XRes);
+        assert!(!full.is_degraded());
+        assert!(full.missing_extensions().is_empty());
+
+        let stripped = Capabilities {
+            extensions: vec![Extension::XTest],
+        };
+        assert_eq!(stripped.pid_lookup(), PidLookup::NetWmPid);
+        assert!(stripped.is_degraded());
+        assert!(stripped.missing_extensions().contains(&Extension::XRes));
+    }
+}
@@ -0,0 +1,917 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+use crate::{
+    atoms::{AtomType, GamescopeAtom},
+    control::GamescopeControl,
+    property::{PropertyChange, PropertyValue},
+    state::FocusableWindow,
+    xwayland::OVERLAY_APP_ID,
+};
+
+/// Window id of the root window of every [FakeXWayland]
+pub const FAKE_ROOT_WINDOW_ID: u32 = 1;
+
+/// Root window properties Gamescope updates when the focus changes
+const FOCUS_ATOMS: [GamescopeAtom; 3] = [
+    GamescopeAtom::FocusedWindow,
+    GamescopeAtom::FocusedApp,
+    GamescopeAtom::FocusedAppGFX,
+];
+
+/// Errors returned by a [FakeXWayland], either for invalid requests or when
+/// injected with [FakeXWayland::fail_nth_request] and friends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FakeError {
+    /// The window with the given id does not exist
+    BadWindow(u32),
+    /// The connection to the fake server was dropped
+    ConnectionClosed,
+    /// The request did not complete in time
+    Timeout,
+}
+
+impl fmt::Display for FakeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FakeError::BadWindow(window_id) => write!(f, "BadWindow: window {}", window_id),
+            FakeError::ConnectionClosed => write!(f, "Connection closed"),
+            FakeError::Timeout => write!(f, "Request timed out"),
+        }
+    }
+}
+
+impl std::error::Error for FakeError {}
+
+/// Faults to inject into requests made through [GamescopeControl]
+#[derive(Debug, Default)]
+struct Faults {
+    /// Number of requests made so far
+    requests: usize,
+    /// Errors to return for the request with the given number
+    failing_requests: HashMap<usize, FakeError>,
+    /// Number of property change events until the connection drops
+    events_until_drop: Option<usize>,
+    /// How long property reads take before they time out
+    read_timeout: Option<Duration>,
+    disconnected: bool,
+}
+
+/// A simulated window
+#[derive(Debug, Default)]
+struct FakeWindow {
+    children: Vec<u32>,
+    name: Option<String>,
+    class: Option<(String, String)>,
+    properties: HashMap<String, Vec<u32>>,
+    strings: HashMap<String, String>,
+}
+
+/// State shared between all clones of a [FakeXWayland]
+#[derive(Debug)]
+struct State {
+    windows: HashMap<u32, FakeWindow>,
+    next_window_id: u32,
+    atoms: HashSet<String>,
+    overlay_app_id: u32,
+    listeners: Vec<Sender<PropertyChange>>,
+    faults: Faults,
+}
+
+/// [FakeXWayland] is an in-memory [GamescopeControl] backend that stores
+/// window properties in a map instead of talking to an X server. Windows can
+/// be added and removed to simulate apps, and every property change is sent
+/// to listeners like the PropertyNotify events of a real server. All clones
+/// share the same state, so a clone can be handed to the code under test while
+/// the test drives and inspects the other.
+#[derive(Debug, Clone)]
+pub struct FakeXWayland {
+    name: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl FakeXWayland {
+    /// Create a new fake XWayland instance with the given display name (E.g.
+    /// ":0") that only has a root window
+    pub fn new(name: &str) -> Self {
+        let mut windows = HashMap::new();
+        windows.insert(FAKE_ROOT_WINDOW_ID, FakeWindow::default());
+        let state = State {
+            windows,
+            next_window_id: FAKE_ROOT_WINDOW_ID + 1,
+            atoms: HashSet::new(),
+            overlay_app_id: OVERLAY_APP_ID,
+            listeners: Vec::new(),
+            faults: Faults::default(),
+        };
+        Self {
+            name: name.to_string(),
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Create a new fake instance that reports itself as the primary Gamescope
+    /// XWayland instance
+    pub fn new_primary(name: &str) -> Self {
+        let xwayland = Self::new(name);
+        xwayland.set_primary(true);
+        xwayland
+    }
+
+    /// Sets whether the instance reports itself as the primary Gamescope
+    /// XWayland instance
+    pub fn set_primary(&self, primary: bool) {
+        let key = GamescopeAtom::KeyboardFocusDisplay;
+        let mut state = self.lock();
+        let result = if primary {
+            state.set_property(FAKE_ROOT_WINDOW_ID, key, vec![0])
+        } else {
+            state.remove_property(FAKE_ROOT_WINDOW_ID, key)
+        };
+        result.expect("the root window always exists");
+    }
+
+    /// Sets the app id used to identify the overlay app
+    pub fn set_overlay_app_id(&self, app_id: u32) {
+        self.lock().overlay_app_id = app_id;
+    }
+
+    /// Marks the given atom as known, as if the running Gamescope supported
+    /// the feature it belongs to. Atoms are also known once any property using
+    /// them was set.
+    pub fn add_atom(&self, key: GamescopeAtom) {
+        self.lock().atoms.insert(key.to_string());
+    }
+
+    /// Creates a new window as a child of the given window and returns its id
+    pub fn add_window(&self, parent: u32) -> Result<u32, Box<dyn std::error::Error>> {
+        let mut state = self.lock();
+        let window_id = state.next_window_id;
+        state.window_mut(parent)?.children.push(window_id);
+        state.windows.insert(window_id, FakeWindow::default());
+        state.next_window_id += 1;
+
+        Ok(window_id)
+    }
+
+    /// Destroys the given window and all of its children
+    pub fn remove_window(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if window_id == FAKE_ROOT_WINDOW_ID {
+            return Err("The root window can't be removed".into());
+        }
+        let mut state = self.lock();
+        let window = state
+            .windows
+            .remove(&window_id)
+            .ok_or(FakeError::BadWindow(window_id))?;
+        for parent in state.windows.values_mut() {
+            parent.children.retain(|child| *child != window_id);
+        }
+        for child in window.children {
+            state.remove_tree(child);
+        }
+
+        Ok(())
+    }
+
+    /// Sets the name (WM_NAME) of the given window
+    pub fn set_window_name(
+        &self,
+        window_id: u32,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().window_mut(window_id)?.name = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Sets the instance and class names (WM_CLASS) of the given window
+    pub fn set_window_class(
+        &self,
+        window_id: u32,
+        instance: &str,
+        class: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let class = (instance.to_string(), class.to_string());
+        self.lock().window_mut(window_id)?.class = Some(class);
+        Ok(())
+    }
+
+    /// Sets the given property as if it was set by Gamescope or another
+    /// client. Unlike [GamescopeControl::set_xprop], this is not counted as a
+    /// request and is not affected by injected faults.
+    pub fn simulate_property(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().set_property(window_id, key, values)
+    }
+
+    /// Removes the given property as if it was removed by Gamescope or another
+    /// client
+    pub fn simulate_property_removed(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().remove_property(window_id, key)
+    }
+
+    /// Creates a top-level window for an app with the given app id and name
+    /// and adds it to the focusable apps and windows like Gamescope does.
+    /// Returns the id of the new window.
+    pub fn spawn_app(&self, app_id: u32, name: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        let window_id = self.add_window(FAKE_ROOT_WINDOW_ID)?;
+        self.set_window_name(window_id, name)?;
+
+        let mut state = self.lock();
+        state.set_property(window_id, GamescopeAtom::SteamGame, vec![app_id])?;
+        state.update_focusable()?;
+
+        Ok(window_id)
+    }
+
+    /// Destroys the given app window and removes it from the focusable apps
+    /// and windows. If it was focused, the most recently spawned remaining app
+    /// gets focus.
+    pub fn close_app(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.remove_window(window_id)?;
+
+        let mut state = self.lock();
+        state.update_focusable()?;
+        let root = state.window(FAKE_ROOT_WINDOW_ID)?;
+        let focused = root
+            .properties
+            .get(&GamescopeAtom::FocusedWindow.to_string());
+        if focused.and_then(|value| value.first()) != Some(&window_id) {
+            return Ok(());
+        }
+        let next = root
+            .properties
+            .get(&GamescopeAtom::FocusableWindows.to_string())
+            .and_then(|values| FocusableWindow::from_values(values).last().copied())
+            .map(|focusable| focusable.window_id);
+        match next {
+            Some(next) => state.focus(next),
+            None => {
+                for key in FOCUS_ATOMS {
+                    state.remove_property(FAKE_ROOT_WINDOW_ID, key)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Focuses the given window like Gamescope does, by updating the focused
+    /// window, app and graphics app on the root window
+    pub fn simulate_focus(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.lock().focus(window_id)
+    }
+
+    /// Returns a receiver channel that receives a [PropertyChange] for every
+    /// property that is set or removed on any window
+    pub fn listen_for_property_values(&self) -> Receiver<PropertyChange> {
+        let (tx, rx) = mpsc::channel();
+        self.lock().listeners.push(tx);
+        rx
+    }
+
+    /// Makes the nth request from now through [GamescopeControl] fail with the
+    /// given error, where 1 is the next request
+    pub fn fail_nth_request(&self, n: usize, error: FakeError) {
+        let mut state = self.lock();
+        let request = state.faults.requests + n.max(1);
+        state.faults.failing_requests.insert(request, error);
+    }
+
+    /// Drops the connection after the given number of property change events
+    /// were sent, or never if None
+    pub fn drop_connection_after_events(&self, events: Option<usize>) {
+        self.lock().faults.events_until_drop = events;
+    }
+
+    /// Drops the connection. All listeners are disconnected and every request
+    /// fails with [FakeError::ConnectionClosed] until [FakeXWayland::reconnect]
+    /// is called.
+    pub fn drop_connection(&self) {
+        self.lock().drop_connection();
+    }
+
+    /// Restores a dropped connection. Listeners have to subscribe again.
+    pub fn reconnect(&self) {
+        self.lock().faults.disconnected = false;
+    }
+
+    /// Returns false if the connection was dropped
+    pub fn is_connected(&self) -> bool {
+        !self.lock().faults.disconnected
+    }
+
+    /// Makes property reads block for the given duration and then fail with
+    /// [FakeError::Timeout], or disables timeouts if None
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) {
+        self.lock().faults.read_timeout = timeout;
+    }
+
+    /// Returns the number of requests made through [GamescopeControl]
+    pub fn request_count(&self) -> usize {
+        self.lock().faults.requests
+    }
+
+    /// Lock the shared state for a request, applying any injected faults
+    fn request(&self) -> Result<MutexGuard<'_, State>, Box<dyn std::error::Error>> {
+        let mut state = self.lock();
+        state.faults.requests += 1;
+        if state.faults.disconnected {
+            return Err(FakeError::ConnectionClosed.into());
+        }
+        let request = state.faults.requests;
+        if let Some(error) = state.faults.failing_requests.remove(&request) {
+            return Err(error.into());
+        }
+
+        Ok(state)
+    }
+
+    /// Lock the shared state for a property read, applying any injected faults
+    fn read_request(&self) -> Result<MutexGuard<'_, State>, Box<dyn std::error::Error>> {
+        let timeout = self.lock().faults.read_timeout;
+        let state = self.request()?;
+        if let Some(timeout) = timeout {
+            drop(state);
+            thread::sleep(timeout);
+            return Err(FakeError::Timeout.into());
+        }
+
+        Ok(state)
+    }
+
+    /// Lock the shared state
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl State {
+    /// Borrow the given window or error if it does not exist
+    fn window(&self, window_id: u32) -> Result<&FakeWindow, Box<dyn std::error::Error>> {
+        self.windows
+            .get(&window_id)
+            .ok_or_else(|| FakeError::BadWindow(window_id).into())
+    }
+
+    /// Mutably borrow the given window or error if it does not exist
+    fn window_mut(
+        &mut self,
+        window_id: u32,
+    ) -> Result<&mut FakeWindow, Box<dyn std::error::Error>> {
+        self.windows
+            .get_mut(&window_id)
+            .ok_or_else(|| FakeError::BadWindow(window_id).into())
+    }
+
+    /// Removes the given window and all of its children
+    fn remove_tree(&mut self, window_id: u32) {
+        if let Some(window) = self.windows.remove(&window_id) {
+            for child in window.children {
+                self.remove_tree(child);
+            }
+        }
+    }
+
+    /// Sets the given property on the given window and notifies listeners
+    fn set_property(
+        &mut self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.window_mut(window_id)?
+            .properties
+            .insert(key.to_string(), values.clone());
+        self.atoms.insert(key.to_string());
+        let value = match key.atom_type() {
+            AtomType::Window => PropertyValue::Window(values),
+            _ => PropertyValue::Cardinal(values),
+        };
+        self.notify(window_id, key, Some(value));
+
+        Ok(())
+    }
+
+    /// Sets the given string property on the given window and notifies
+    /// listeners
+    fn set_string_property(
+        &mut self,
+        window_id: u32,
+        key: GamescopeAtom,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.window_mut(window_id)?
+            .strings
+            .insert(key.to_string(), value.to_string());
+        self.atoms.insert(key.to_string());
+        self.notify(
+            window_id,
+            key,
+            Some(PropertyValue::String(value.to_string())),
+        );
+
+        Ok(())
+    }
+
+    /// Removes the given property from the given window and notifies
+    /// listeners if it was set
+    fn remove_property(
+        &mut self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let window = self.window_mut(window_id)?;
+        let removed = window.properties.remove(&key.to_string()).is_some();
+        let removed = window.strings.remove(&key.to_string()).is_some() || removed;
+        if removed {
+            self.notify(window_id, key, None);
+        }
+
+        Ok(())
+    }
+
+    /// Focuses the given window and its app
+    fn focus(&mut self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let app_id = self
+            .window(window_id)?
+            .properties
+            .get(&GamescopeAtom::SteamGame.to_string())
+            .and_then(|value| value.first().copied())
+            .unwrap_or_default();
+        let root = FAKE_ROOT_WINDOW_ID;
+        self.set_property(root, GamescopeAtom::FocusedWindow, vec![window_id])?;
+        self.set_property(root, GamescopeAtom::FocusedApp, vec![app_id])?;
+        self.set_property(root, GamescopeAtom::FocusedAppGFX, vec![app_id])
+    }
+
+    /// Updates the focusable apps and windows from all top-level windows that
+    /// have an app id. Focusable windows are published as (window, app id,
+    /// pid) triplets like Gamescope does, with a pid of 0 if the window has no
+    /// _NET_WM_PID.
+    fn update_focusable(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let app_key = GamescopeAtom::SteamGame.to_string();
+        let pid_key = GamescopeAtom::NetWmPID.to_string();
+        let mut apps: Vec<u32> = Vec::new();
+        let mut windows: Vec<u32> = Vec::new();
+        for window_id in self.window(FAKE_ROOT_WINDOW_ID)?.children.iter() {
+            let properties = &self.windows[window_id].properties;
+            let app_id = properties
+                .get(&app_key)
+                .and_then(|value| value.first().copied());
+            if let Some(app_id) = app_id {
+                let pid = properties
+                    .get(&pid_key)
+                    .and_then(|value| value.first().copied())
+                    .unwrap_or_default();
+                windows.extend([*window_id, app_id, pid]);
+                if !apps.contains(&app_id) {
+                    apps.push(app_id);
+                }
+            }
+        }
+
+        let root = FAKE_ROOT_WINDOW_ID;
+        self.set_property(root, GamescopeAtom::FocusableApps, apps)?;
+        self.set_property(root, GamescopeAtom::FocusableWindows, windows)
+    }
+
+    /// Disconnects all listeners and fails all further requests
+    fn drop_connection(&mut self) {
+        self.faults.disconnected = true;
+        self.faults.events_until_drop = None;
+        self.listeners.clear();
+    }
+
+    /// Sends the change to all listeners that are still listening
+    fn notify(&mut self, window_id: u32, key: GamescopeAtom, value: Option<PropertyValue>) {
+        let change = PropertyChange {
+            window_id,
+            property: key.to_string(),
+            value,
+        };
+        self.listeners
+            .retain(|listener| listener.send(change.clone()).is_ok());
+
+        if let Some(events) = self.faults.events_until_drop.as_mut() {
+            *events = events.saturating_sub(1);
+            if *events == 0 {
+                self.drop_connection();
+            }
+        }
+    }
+}
+
+impl GamescopeControl for FakeXWayland {
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn get_overlay_app_id(&self) -> u32 {
+        self.lock().overlay_app_id
+    }
+
+    fn get_root_window_id(&self) -> Result<u32, Box<dyn std::error::Error>> {
+        drop(self.request()?);
+        Ok(FAKE_ROOT_WINDOW_ID)
+    }
+
+    fn get_window_children(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        Ok(self.request()?.window(window_id)?.children.clone())
+    }
+
+    fn get_window_name(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        Ok(self.request()?.window(window_id)?.name.clone())
+    }
+
+    fn get_window_class(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        Ok(self.request()?.window(window_id)?.class.clone())
+    }
+
+    fn has_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        let state = self.read_request()?;
+        let window = state.window(window_id)?;
+        let key = key.to_string();
+        Ok(window.properties.contains_key(&key) || window.strings.contains_key(&key))
+    }
+
+    fn get_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        let state = self.read_request()?;
+        Ok(state
+            .window(window_id)?
+            .properties
+            .get(&key.to_string())
+            .cloned())
+    }
+
+    fn set_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.request()?.set_property(window_id, key, values)
+    }
+
+    fn remove_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.request()?.remove_property(window_id, key)
+    }
+
+    fn get_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let state = self.read_request()?;
+        Ok(state
+            .window(window_id)?
+            .strings
+            .get(&key.to_string())
+            .cloned())
+    }
+
+    fn set_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.request()?.set_string_property(window_id, key, value)
+    }
+
+    fn has_atom(&self, key: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(self.request()?.atoms.contains(&key.to_string()))
+    }
+}
+
+/// A single step of a [Scenario]. Apps are referred to by their app id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// Spawn a window for the app with the given app id and name
+    SpawnApp { app_id: u32, name: String },
+    /// Destroy the window of the given app
+    CloseApp { app_id: u32 },
+    /// Focus the window of the given app
+    FocusApp { app_id: u32 },
+    /// Give the overlay input focus on top of the focused app, spawning the
+    /// overlay window if needed
+    OpenOverlay,
+    /// Take input focus away from the overlay
+    CloseOverlay,
+    /// Set a property on the window of the given app, or on the root window if
+    /// None
+    SetProperty {
+        app_id: Option<u32>,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    },
+}
+
+impl Step {
+    /// Applies the step to the given fake instance. The windows spawned for
+    /// each app id are tracked in the given map.
+    pub fn apply(
+        &self,
+        xwayland: &FakeXWayland,
+        windows: &mut HashMap<u32, u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let window_for = |app_id: &u32| {
+            windows
+                .get(app_id)
+                .copied()
+                .ok_or_else(|| format!("App {} was not spawned", app_id))
+        };
+
+        match self {
+            Step::SpawnApp { app_id, name } => {
+                let window_id = xwayland.spawn_app(*app_id, name)?;
+                windows.insert(*app_id, window_id);
+            }
+            Step::CloseApp { app_id } => {
+                xwayland.close_app(window_for(app_id)?)?;
+                windows.remove(app_id);
+            }
+            Step::FocusApp { app_id } => xwayland.simulate_focus(window_for(app_id)?)?,
+            Step::OpenOverlay => {
+                let overlay_app_id = xwayland.get_overlay_app_id();
+                let window_id = match windows.get(&overlay_app_id) {
+                    Some(window_id) => *window_id,
+                    None => {
+                        let window_id = xwayland.spawn_app(overlay_app_id, "Steam")?;
+                        windows.insert(overlay_app_id, window_id);
+                        window_id
+                    }
+                };
+                xwayland.simulate_property(window_id, GamescopeAtom::SteamOverlay, vec![1])?;
+                xwayland.simulate_property(window_id, GamescopeAtom::SteamInputFocus, vec![1])?;
+                xwayland.simulate_property(
+                    FAKE_ROOT_WINDOW_ID,
+                    GamescopeAtom::FocusedApp,
+                    vec![overlay_app_id],
+                )?;
+            }
+            Step::CloseOverlay => {
+                let overlay_app_id = xwayland.get_overlay_app_id();
+                let window_id = window_for(&overlay_app_id)?;
+                xwayland.simulate_property_removed(window_id, GamescopeAtom::SteamInputFocus)?;
+                // The focused app goes back to the app that is still drawn
+                let root = FAKE_ROOT_WINDOW_ID;
+                let app_id = xwayland
+                    .lock()
+                    .window(root)?
+                    .properties
+                    .get(&GamescopeAtom::FocusedAppGFX.to_string())
+                    .cloned();
+                match app_id {
+                    Some(app_id) => {
+                        xwayland.simulate_property(root, GamescopeAtom::FocusedApp, app_id)?
+                    }
+                    None => xwayland.simulate_property_removed(root, GamescopeAtom::FocusedApp)?,
+                }
+            }
+            Step::SetProperty {
+                app_id,
+                key,
+                values,
+            } => {
+                let window_id = match app_id {
+                    Some(app_id) => window_for(app_id)?,
+                    None => FAKE_ROOT_WINDOW_ID,
+                };
+                xwayland.simulate_property(window_id, *key, values.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// [Scenario] is a scripted sequence of [Step]s like spawning apps and moving
+/// focus between them, so complex flows such as "the overlay opens while a
+/// game launches" can be replayed against a [FakeXWayland] in tests.
+///
+/// ```
+/// use gamescope_x11_client::fake::{FakeXWayland, Scenario};
+/// use gamescope_x11_client::xwayland::Primary;
+///
+/// let xwayland = FakeXWayland::new_primary(":0");
+/// let windows = Scenario::new()
+///     .spawn_app(620, "Portal 2")
+///     .focus_app(620)
+///     .open_overlay()
+///     .run(&xwayland)
+///     .unwrap();
+/// assert!(xwayland.is_overlay_focused().unwrap());
+/// assert!(windows.contains_key(&620));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Create a new empty scenario
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the given step to the scenario
+    pub fn step(mut self, step: Step) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Spawn a window for the app with the given app id and name
+    pub fn spawn_app(self, app_id: u32, name: &str) -> Self {
+        self.step(Step::SpawnApp {
+            app_id,
+            name: name.to_string(),
+        })
+    }
+
+    /// Destroy the window of the given app
+    pub fn close_app(self, app_id: u32) -> Self {
+        self.step(Step::CloseApp { app_id })
+    }
+
+    /// Focus the window of the given app
+    pub fn focus_app(self, app_id: u32) -> Self {
+        self.step(Step::FocusApp { app_id })
+    }
+
+    /// Give the overlay input focus
+    pub fn open_overlay(self) -> Self {
+        self.step(Step::OpenOverlay)
+    }
+
+    /// Take input focus away from the overlay
+    pub fn close_overlay(self) -> Self {
+        self.step(Step::CloseOverlay)
+    }
+
+    /// Set a property on the window of the given app, or on the root window if
+    /// None
+    pub fn set_property(self, app_id: Option<u32>, key: GamescopeAtom, values: Vec<u32>) -> Self {
+        self.step(Step::SetProperty {
+            app_id,
+            key,
+            values,
+        })
+    }
+
+    /// Returns the steps of the scenario
+    pub fn steps(&self) -> &[Step] {
+        self.steps.as_slice()
+    }
+
+    /// Applies all steps in order to the given fake instance. Returns the
+    /// window id spawned for each app id that is still open.
+    pub fn run(
+        &self,
+        xwayland: &FakeXWayland,
+    ) -> Result<HashMap<u32, u32>, Box<dyn std::error::Error>> {
+        let mut windows: HashMap<u32, u32> = HashMap::new();
+        for step in self.steps.iter() {
+            step.apply(xwayland, &mut windows)?;
+        }
+
+        Ok(windows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xwayland::Primary;
+
+    #[test]
+    fn test_fake_xwayland() {
+        let xwayland = FakeXWayland::new_primary(":0");
+        assert!(xwayland.is_primary_instance().unwrap());
+
+        let window_id = xwayland.add_window(FAKE_ROOT_WINDOW_ID).unwrap();
+        xwayland.set_window_name(window_id, "Steam").unwrap();
+        assert_eq!(
+            xwayland.get_window_children(FAKE_ROOT_WINDOW_ID).unwrap(),
+            vec![window_id]
+        );
+
+        let rx = xwayland.listen_for_property_values();
+        xwayland.set_main_app(window_id).unwrap();
+        xwayland.set_baselayer_window(window_id).unwrap();
+        assert_eq!(
+            xwayland.get_app_id(window_id).unwrap(),
+            Some(OVERLAY_APP_ID)
+        );
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            PropertyChange {
+                window_id,
+                property: "STEAM_GAME".into(),
+                value: Some(PropertyValue::Cardinal(vec![OVERLAY_APP_ID])),
+            }
+        );
+        assert_eq!(
+            rx.try_recv().unwrap().value,
+            Some(PropertyValue::Window(vec![window_id]))
+        );
+
+        xwayland.remove_window(window_id).unwrap();
+        assert!(xwayland.get_app_id(window_id).is_err());
+        assert!(!xwayland.supports_overview().unwrap());
+    }
+
+    #[test]
+    fn test_scenario_overlay_during_launch() {
+        let xwayland = FakeXWayland::new_primary(":0");
+        let windows = Scenario::new()
+            .spawn_app(620, "Portal 2")
+            .focus_app(620)
+            .open_overlay()
+            .spawn_app(730, "Counter-Strike")
+            .close_overlay()
+            .focus_app(730)
+            .close_app(730)
+            .run(&xwayland)
+            .unwrap();
+
+        assert_eq!(xwayland.get_focused_app().unwrap(), Some(OVERLAY_APP_ID));
+        assert_eq!(
+            xwayland.get_focused_window().unwrap(),
+            windows.get(&OVERLAY_APP_ID).copied()
+        );
+        assert_eq!(
+            xwayland.get_focusable_apps().unwrap(),
+            Some(vec![620, OVERLAY_APP_ID])
+        );
+        assert_eq!(
+            xwayland.get_focusable_windows().unwrap(),
+            Some(vec![
+                windows[&620],
+                620,
+                0,
+                windows[&OVERLAY_APP_ID],
+                OVERLAY_APP_ID,
+                0
+            ])
+        );
+        assert!(!windows.contains_key(&730));
+    }
+
+    #[test]
+    fn test_fake_xwayland_faults() {
+        let xwayland = FakeXWayland::new(":1");
+        let root = FAKE_ROOT_WINDOW_ID;
+
+        let key = GamescopeAtom::FPSLimit;
+        xwayland.fail_nth_request(2, FakeError::BadWindow(root));
+        assert!(xwayland.get_xprop(root, key).is_ok());
+        let err = xwayland.get_xprop(root, key).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&FakeError::BadWindow(root)));
+        assert!(xwayland.get_xprop(root, key).is_ok());
+
+        let rx = xwayland.listen_for_property_values();
+        xwayland.drop_connection_after_events(Some(2));
+        xwayland.set_fps_limit(30).unwrap();
+        xwayland.set_fps_limit(60).unwrap();
+        assert_eq!(rx.iter().count(), 2);
+        let err = xwayland.set_fps_limit(90).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&FakeError::ConnectionClosed));
+
+        xwayland.reconnect();
+        xwayland.set_read_timeout(Some(Duration::ZERO));
+        let err = xwayland.get_xprop(root, key).unwrap_err();
+        assert_eq!(err.downcast_ref(), Some(&FakeError::Timeout));
+    }
+}
@@ -0,0 +1,374 @@
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Thread};
+
+use x11rb::protocol::{xproto::EventMask, Event};
+
+use crate::{
+    event_loop::RawEvent,
+    xwayland::{Primary, XWayland},
+};
+
+thread_local! {
+    /// Message of the last error on the calling thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the given error as the last error of the calling thread
+fn set_last_error(err: impl ToString) {
+    let message = err.to_string().replace('\0', "");
+    let message = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Returns the result code of an optional value, writing the value to the
+/// given output pointer. Returns 1 if the value is set, 0 if it is not and -1
+/// on error.
+unsafe fn write_optional(
+    result: Result<Option<u32>, Box<dyn std::error::Error>>,
+    out: *mut u32,
+) -> c_int {
+    match result {
+        Ok(Some(value)) => {
+            if !out.is_null() {
+                *out = value;
+            }
+            1
+        }
+        Ok(None) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Returns the result code of an operation without a value: 0 on success and
+/// -1 on error
+fn write_status(result: Result<(), Box<dyn std::error::Error>>) -> c_int {
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Opaque handle to a connected XWayland instance
+pub struct GamescopeX11 {
+    xwayland: XWayland,
+}
+
+/// Opaque handle to a property change subscription
+pub struct GamescopeX11Subscription {
+    xwayland: XWayland,
+    tx: Arc<Sender<RawEvent>>,
+    stopped: Arc<AtomicBool>,
+    /// Held by the listener thread while it checks `stopped` and calls the
+    /// callback
+    calling: Arc<Mutex<()>>,
+    thread: Thread,
+}
+
+/// Called with the name of each changed root window property and the user
+/// data passed to [gamescope_x11_subscribe]. The name is only valid for the
+/// duration of the call.
+pub type GamescopeX11PropertyCallback = extern "C" fn(name: *const c_char, user_data: *mut c_void);
+
+/// User data pointer that is handed to the callback on the listener thread
+struct UserData(*mut c_void);
+
+// The caller of gamescope_x11_subscribe promises that the user data can be
+// used from another thread
+unsafe impl Send for UserData {}
+
+/// Returns the message of the last error on the calling thread, or NULL if
+/// there was none. The message is valid until the next failing call on the
+/// same thread.
+#[no_mangle]
+pub extern "C" fn gamescope_x11_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match last.borrow().as_ref() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Returns the names of all Gamescope XWayland displays (E.g. ":0") and writes
+/// their number to `count`. The names must be freed with
+/// [gamescope_x11_free_names]. Returns NULL on error.
+///
+/// # Safety
+///
+/// `count` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_discover(count: *mut usize) -> *mut *mut c_char {
+    let displays = match crate::discover_gamescope_displays() {
+        Ok(displays) => displays,
+        Err(err) => {
+            set_last_error(err);
+            return ptr::null_mut();
+        }
+    };
+    let names: Vec<*mut c_char> = displays
+        .into_iter()
+        .filter_map(|name| CString::new(name).ok())
+        .map(CString::into_raw)
+        .collect();
+    *count = names.len();
+
+    Box::into_raw(names.into_boxed_slice()) as *mut *mut c_char
+}
+
+/// Frees the names returned by [gamescope_x11_discover]
+///
+/// # Safety
+///
+/// `names` and `count` must have been returned by [gamescope_x11_discover]
+/// and the names must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_free_names(names: *mut *mut c_char, count: usize) {
+    if names.is_null() {
+        return;
+    }
+    let names = Box::from_raw(ptr::slice_from_raw_parts_mut(names, count));
+    for name in names.iter() {
+        drop(CString::from_raw(*name));
+    }
+}
+
+/// Connects to the given XWayland display (E.g. ":1"). Returns NULL on error.
+/// The handle must be freed with [gamescope_x11_free].
+///
+/// # Safety
+///
+/// `display` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_connect(display: *const c_char) -> *mut GamescopeX11 {
+    let display = match CStr::from_ptr(display).to_str() {
+        Ok(display) => display.to_string(),
+        Err(err) => {
+            set_last_error(err);
+            return ptr::null_mut();
+        }
+    };
+    match XWayland::new(display).connect() {
+        Ok(xwayland) => Box::into_raw(Box::new(GamescopeX11 { xwayland })),
+        Err(err) => {
+            set_last_error(err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Disconnects and frees the given handle
+///
+/// # Safety
+///
+/// `handle` must have been returned by [gamescope_x11_connect] and must not
+/// be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_free(handle: *mut GamescopeX11) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Returns 1 if the instance is the primary Gamescope XWayland, 0 if it is
+/// not and -1 on error
+///
+/// # Safety
+///
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_is_primary(handle: *const GamescopeX11) -> c_int {
+    match (*handle).xwayland.is_primary_instance() {
+        Ok(primary) => primary as c_int,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Sets the FPS limit (0 to disable it). Returns 0 on success and -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_set_fps_limit(
+    handle: *const GamescopeX11,
+    fps: u32,
+) -> c_int {
+    write_status((*handle).xwayland.set_fps_limit(fps))
+}
+
+/// Writes the FPS limit to `fps`. Returns 1 if it is set, 0 if it is not and
+/// -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle and `fps` NULL or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_get_fps_limit(
+    handle: *const GamescopeX11,
+    fps: *mut u32,
+) -> c_int {
+    write_optional((*handle).xwayland.get_fps_limit(), fps)
+}
+
+/// Writes the app id of the focused app to `app_id`. Returns 1 if an app is
+/// focused, 0 if none is and -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle and `app_id` NULL or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_get_focused_app(
+    handle: *const GamescopeX11,
+    app_id: *mut u32,
+) -> c_int {
+    write_optional((*handle).xwayland.get_focused_app(), app_id)
+}
+
+/// Writes the app id of the baselayer app to `app_id`. Returns 1 if it is
+/// set, 0 if it is not and -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle and `app_id` NULL or a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_get_baselayer_app_id(
+    handle: *const GamescopeX11,
+    app_id: *mut u32,
+) -> c_int {
+    write_optional((*handle).xwayland.get_baselayer_app_id(), app_id)
+}
+
+/// Sets the app id of the baselayer app. Returns 0 on success and -1 on error.
+///
+/// # Safety
+///
+/// `handle` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_set_baselayer_app_id(
+    handle: *const GamescopeX11,
+    app_id: u32,
+) -> c_int {
+    write_status((*handle).xwayland.set_baselayer_app_id(app_id))
+}
+
+/// Calls the given callback on a background thread whenever a property on the
+/// root window changes. Returns NULL on error. The subscription must be ended
+/// with [gamescope_x11_unsubscribe].
+///
+/// # Safety
+///
+/// `handle` must be a valid handle and `user_data` must be usable from
+/// another thread until the subscription ends.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_subscribe(
+    handle: *const GamescopeX11,
+    callback: GamescopeX11PropertyCallback,
+    user_data: *mut c_void,
+) -> *mut GamescopeX11Subscription {
+    let xwayland = &(*handle).xwayland;
+    let (tx, events) = mpsc::channel();
+    let tx = Arc::new(tx);
+    let subscribed = xwayland
+        .get_root_window_id()
+        .and_then(|root| xwayland.subscribe_events(root, EventMask::PROPERTY_CHANGE, tx.clone()));
+    if let Err(err) = subscribed {
+        set_last_error(err);
+        return ptr::null_mut();
+    }
+
+    // The thread exits once unsubscribing drops the sender, but events that
+    // were already queued must not reach the callback anymore. The stopped
+    // flag is checked under the lock unsubscribing waits for, so the callback
+    // can't start after unsubscribing returned.
+    let stopped = Arc::new(AtomicBool::new(false));
+    let calling = Arc::new(Mutex::new(()));
+    let thread_stopped = stopped.clone();
+    let thread_calling = calling.clone();
+    let user_data = UserData(user_data);
+    let options = xwayland.get_connect_options();
+    let handle = crate::worker::spawn(&xwayland.get_name(), "ffi", &options, move || {
+        let user_data = user_data;
+        for (conn, atoms, event) in events {
+            let Event::PropertyNotify(event) = event else {
+                continue;
+            };
+            let name = match atoms.name(conn.as_ref(), event.atom) {
+                Ok(name) => name,
+                Err(err) => {
+                    log::error!("Error looking up changed property: {}", err);
+                    continue;
+                }
+            };
+            let Ok(name) = CString::new(name.as_ref()) else {
+                continue;
+            };
+            let _calling = thread_calling
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if thread_stopped.load(Ordering::SeqCst) {
+                break;
+            }
+            callback(name.as_ptr(), user_data.0);
+        }
+    });
+
+    let subscription = GamescopeX11Subscription {
+        xwayland: xwayland.clone(),
+        tx,
+        stopped,
+        calling,
+        thread: handle.thread().clone(),
+    };
+    Box::into_raw(Box::new(subscription))
+}
+
+/// Ends the given subscription. Waits for a call of the callback that is
+/// already in progress, so the callback is not running anymore once this
+/// returns. May also be called from within the callback.
+///
+/// # Safety
+///
+/// `subscription` must have been returned by [gamescope_x11_subscribe] and
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn gamescope_x11_unsubscribe(subscription: *mut GamescopeX11Subscription) {
+    if subscription.is_null() {
+        return;
+    }
+    let subscription = Box::from_raw(subscription);
+    subscription.stopped.store(true, Ordering::SeqCst);
+
+    // Wait for a running callback, unless called from that callback
+    if thread::current().id() != subscription.thread.id() {
+        drop(subscription.calling.lock());
+    }
+    if let Err(err) = subscription.xwayland.unsubscribe_events(&subscription.tx) {
+        log::debug!("Error ending subscription: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ffi_connect_error() {
+        let display = CString::new(":9999").unwrap();
+        let handle = unsafe { gamescope_x11_connect(display.as_ptr()) };
+        assert!(handle.is_null());
+        assert!(!gamescope_x11_last_error().is_null());
+        unsafe { gamescope_x11_free(handle) };
+    }
+}
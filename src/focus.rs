@@ -0,0 +1,213 @@
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{
+    atoms::GamescopeAtom,
+    worker,
+    xwayland::{Primary, XWayland},
+};
+
+/// Default number of focus changes remembered by a [FocusTracker]
+pub const DEFAULT_FOCUS_HISTORY_LIMIT: usize = 256;
+
+/// A period in which an app and window had focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusEntry {
+    /// The focused app id (GAMESCOPE_FOCUSED_APP)
+    pub app_id: Option<u32>,
+    /// The focused window (GAMESCOPE_FOCUSED_WINDOW)
+    pub window_id: Option<u32>,
+    /// When the app and window gained focus
+    pub focused_at: Instant,
+    /// When the app and window lost focus, or None if they are still focused
+    pub unfocused_at: Option<Instant>,
+}
+
+impl FocusEntry {
+    /// Returns how long the app and window were focused until the given time
+    fn duration_until(&self, now: Instant) -> Duration {
+        self.unfocused_at
+            .unwrap_or(now)
+            .saturating_duration_since(self.focused_at)
+    }
+}
+
+#[derive(Debug)]
+struct History {
+    entries: Vec<FocusEntry>,
+    limit: usize,
+}
+
+/// [FocusTracker] keeps a timestamped history of the focused app and window
+/// of the primary Gamescope instance, for play time tracking or alt-tab style
+/// app switchers. Clones share the same history, so a clone can be passed to
+/// [FocusTracker::start] while the original is queried.
+#[derive(Debug, Clone)]
+pub struct FocusTracker {
+    history: Arc<Mutex<History>>,
+}
+
+impl Default for FocusTracker {
+    fn default() -> Self {
+        Self::with_limit(DEFAULT_FOCUS_HISTORY_LIMIT)
+    }
+}
+
+impl FocusTracker {
+    /// Create a tracker that remembers up to [DEFAULT_FOCUS_HISTORY_LIMIT]
+    /// focus changes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a tracker that remembers up to the given number of focus changes
+    pub fn with_limit(limit: usize) -> Self {
+        let history = History {
+            entries: Vec::new(),
+            limit: limit.max(1),
+        };
+        Self {
+            history: Arc::new(Mutex::new(history)),
+        }
+    }
+
+    /// Records the current focus of the given primary instance and starts
+    /// recording every change to its focused app or window. Returns a join
+    /// handle of the tracking thread.
+    pub fn start(&self, primary: &XWayland) -> Result<JoinHandle<()>, Box<dyn std::error::Error>> {
+        let keys = [GamescopeAtom::FocusedApp, GamescopeAtom::FocusedWindow];
+        let (_, rx) = primary.listen_for_atom_changes(&keys)?;
+        self.record(primary.get_focused_app()?, primary.get_focused_window()?);
+
+        let tracker = self.clone();
+        let xwayland = primary.clone();
+        let options = primary.get_connect_options();
+        let handle = worker::spawn(&primary.get_name(), "focus", &options, move || {
+            for _ in rx {
+                let focus = xwayland
+                    .get_focused_app()
+                    .and_then(|app_id| Ok((app_id, xwayland.get_focused_window()?)));
+                match focus {
+                    Ok((app_id, window_id)) => tracker.record(app_id, window_id),
+                    Err(err) => log::error!("Error reading focus: {}", err),
+                }
+            }
+        });
+
+        Ok(handle)
+    }
+
+    /// Records that the given app and window gained focus now. Nothing is
+    /// recorded if they are already focused.
+    pub fn record(&self, app_id: Option<u32>, window_id: Option<u32>) {
+        self.record_at(app_id, window_id, Instant::now());
+    }
+
+    /// Records that the given app and window gained focus at the given time
+    fn record_at(&self, app_id: Option<u32>, window_id: Option<u32>, now: Instant) {
+        let Ok(mut history) = self.history.lock() else {
+            return;
+        };
+        if let Some(current) = history.entries.last_mut() {
+            if current.app_id == app_id && current.window_id == window_id {
+                return;
+            }
+            current.unfocused_at = Some(now);
+        }
+
+        history.entries.push(FocusEntry {
+            app_id,
+            window_id,
+            focused_at: now,
+            unfocused_at: None,
+        });
+        if history.entries.len() > history.limit {
+            history.entries.remove(0);
+        }
+    }
+
+    /// Returns all remembered focus changes, oldest first
+    pub fn history(&self) -> Vec<FocusEntry> {
+        self.history
+            .lock()
+            .map(|history| history.entries.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the currently focused app and window
+    pub fn current_focus(&self) -> Option<FocusEntry> {
+        let history = self.history.lock().ok()?;
+        history.entries.last().copied()
+    }
+
+    /// Returns the app and window that had focus before the current one
+    pub fn previous_focus(&self) -> Option<FocusEntry> {
+        let history = self.history.lock().ok()?;
+        history.entries.iter().rev().nth(1).copied()
+    }
+
+    /// Returns how long the given app was focused in total within the
+    /// remembered history, including the time it has been focused so far if
+    /// it currently has focus
+    pub fn time_in_focus(&self, app_id: u32) -> Duration {
+        self.time_in_focus_at(app_id, Instant::now())
+    }
+
+    /// Returns how long the given app was focused until the given time
+    fn time_in_focus_at(&self, app_id: u32, now: Instant) -> Duration {
+        let Ok(history) = self.history.lock() else {
+            return Duration::ZERO;
+        };
+        history
+            .entries
+            .iter()
+            .filter(|entry| entry.app_id == Some(app_id))
+            .map(|entry| entry.duration_until(now))
+            .sum()
+    }
+
+    /// Forgets all remembered focus changes
+    pub fn clear(&self) {
+        if let Ok(mut history) = self.history.lock() {
+            history.entries.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_tracker_history() {
+        let tracker = FocusTracker::with_limit(3);
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+
+        tracker.record_at(Some(769), Some(10), at(0));
+        tracker.record_at(Some(620), Some(20), at(5));
+        tracker.record_at(Some(620), Some(20), at(6));
+        tracker.record_at(Some(769), Some(10), at(15));
+
+        let previous = tracker.previous_focus().unwrap();
+        assert_eq!(previous.app_id, Some(620));
+        assert_eq!(previous.unfocused_at, Some(at(15)));
+        assert_eq!(tracker.current_focus().unwrap().app_id, Some(769));
+        assert_eq!(
+            tracker.time_in_focus_at(620, at(20)),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            tracker.time_in_focus_at(769, at(20)),
+            Duration::from_secs(10)
+        );
+
+        tracker.record_at(None, None, at(30));
+        assert_eq!(tracker.history().len(), 3);
+        assert_eq!(
+            tracker.time_in_focus_at(769, at(40)),
+            Duration::from_secs(15)
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use x11rb::protocol::xproto::ConnectionExt;
+
+use crate::{
+    atoms::GamescopeAtom,
+    connection::SharedConnection,
+    extension::{self, Extension},
+    watchdog::is_process_running,
+    worker, x11,
+    xwayland::{WindowLifecycleEvent, XWayland},
+};
+
+/// Events emitted when the process and windows of an app get out of sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AppHealthEvent {
+    /// The process of an app died while its windows still exist
+    AppCrashed { app_id: u32, pid: u32 },
+    /// All windows of an app were destroyed while its process is still running
+    AppWindowsLost { app_id: u32, pid: u32 },
+}
+
+/// An app is identified by its app id and the process ID owning its windows
+type AppKey = (u32, u32);
+
+/// [AppTracker] keeps track of which windows belong to which app and decides
+/// when an app is considered crashed. Apps are only reported once their state
+/// has been inconsistent for longer than the grace period, so that normal app
+/// exits (where windows and process go away at slightly different times) are
+/// not reported.
+#[derive(Debug)]
+struct AppTracker {
+    grace_period: Duration,
+    windows: HashMap<u32, AppKey>,
+    suspects: HashMap<AppKey, Instant>,
+    reported: HashSet<AppKey>,
+}
+
+impl AppTracker {
+    fn new(grace_period: Duration) -> Self {
+        Self {
+            grace_period,
+            windows: HashMap::new(),
+            suspects: HashMap::new(),
+            reported: HashSet::new(),
+        }
+    }
+
+    /// Start tracking the given window as belonging to the given app
+    fn track(&mut self, window_id: u32, app_id: u32, pid: u32) {
+        self.windows.insert(window_id, (app_id, pid));
+    }
+
+    /// Stop tracking the given window. If it was the last window of its app,
+    /// the app is checked on the next call to [AppTracker::check].
+    fn remove(&mut self, window_id: u32, now: Instant) {
+        let Some(key) = self.windows.remove(&window_id) else {
+            return;
+        };
+        if !self.windows.values().any(|other| *other == key) {
+            self.suspects.entry(key).or_insert(now);
+        }
+    }
+
+    /// Returns the events for all apps whose state has been inconsistent for
+    /// longer than the grace period.
+    fn check<F>(&mut self, now: Instant, is_running: F) -> Vec<AppHealthEvent>
+    where
+        F: Fn(u32) -> bool,
+    {
+        let apps: HashSet<AppKey> = self.windows.values().copied().collect();
+        let mut events: Vec<AppHealthEvent> = Vec::new();
+
+        // Apps that still have windows
+        for key in apps.iter() {
+            let (app_id, pid) = *key;
+            if is_running(pid) || self.reported.contains(key) {
+                self.suspects.remove(key);
+                continue;
+            }
+            let since = *self.suspects.entry(*key).or_insert(now);
+            if now.duration_since(since) >= self.grace_period {
+                self.suspects.remove(key);
+                self.reported.insert(*key);
+                events.push(AppHealthEvent::AppCrashed { app_id, pid });
+            }
+        }
+
+        // Apps whose windows are all gone
+        let lost: Vec<(AppKey, Instant)> = self
+            .suspects
+            .iter()
+            .filter(|(key, _)| !apps.contains(key))
+            .map(|(key, since)| (*key, *since))
+            .collect();
+        for (key, since) in lost {
+            let (app_id, pid) = key;
+            if !is_running(pid) {
+                self.suspects.remove(&key);
+                self.reported.remove(&key);
+                continue;
+            }
+            if now.duration_since(since) >= self.grace_period {
+                self.suspects.remove(&key);
+                events.push(AppHealthEvent::AppWindowsLost { app_id, pid });
+            }
+        }
+
+        events
+    }
+}
+
+/// Spawns a thread that combines window lifecycle events with process
+/// liveness checks at the given interval to detect crashed apps.
+pub(crate) fn spawn_app_health_monitor(
+    xwayland: &XWayland,
+    conn: SharedConnection,
+    interval: Duration,
+) -> Result<(JoinHandle<()>, Receiver<AppHealthEvent>), Box<dyn std::error::Error>> {
+    let (_, lifecycle) = xwayland.listen_for_window_lifecycle()?;
+    let (tx, rx): (Sender<AppHealthEvent>, Receiver<AppHealthEvent>) = mpsc::channel();
+
+    let display = conn.name().to_string();
+    let options = conn.options().clone();
+    let child = worker::spawn(&display, "health", &options, move || {
+        let mut tracker = AppTracker::new(interval);
+        let mut next_check = Instant::now();
+        loop {
+            let timeout = next_check.saturating_duration_since(Instant::now());
+            match lifecycle.recv_timeout(timeout) {
+                Ok((WindowLifecycleEvent::Destroyed, window_id)) => {
+                    tracker.remove(window_id, Instant::now());
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => (),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            next_check = Instant::now() + interval;
+
+            if let Err(err) = scan_app_windows(&conn, &mut tracker) {
+                log::debug!("Error scanning app windows: {}", err);
+            }
+            for event in tracker.check(Instant::now(), is_process_running) {
+                if tx.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok((child, rx))
+}
+
+/// Starts tracking all top-level windows that have an app id set. The process
+/// owning each window is looked up with XRes, since '_NET_WM_PID' holds the
+/// pid inside the client's own pid namespace (E.g. Flatpak or
+/// pressure-vessel), which may not exist on the host. Without XRes no window
+/// is tracked.
+fn scan_app_windows(
+    conn: &SharedConnection,
+    tracker: &mut AppTracker,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let root_window_id = conn.root_window_id();
+    let conn = conn.get();
+    if !extension::has_extension(conn.as_ref(), Extension::XRes)? {
+        log::debug!("XRes is missing, unable to look up app processes");
+        return Ok(());
+    }
+    let windows = conn.query_tree(root_window_id)?.reply()?.children;
+
+    let steam_game = GamescopeAtom::SteamGame.to_string();
+    for window_id in windows {
+        // Windows may be destroyed while scanning, so skip any errors
+        let Ok(Some(value)) = x11::get_property(conn.as_ref(), window_id, steam_game.as_str())
+        else {
+            continue;
+        };
+        let app_id = value.first().copied().unwrap_or_default();
+        if app_id == 0 {
+            continue;
+        }
+        // The owning process of a window does not change
+        if tracker
+            .windows
+            .get(&window_id)
+            .is_some_and(|(tracked, _)| *tracked == app_id)
+        {
+            continue;
+        }
+        let Ok(pids) = x11::get_window_pids(conn.as_ref(), window_id) else {
+            continue;
+        };
+        if let Some(pid) = pids.first() {
+            tracker.track(window_id, app_id, *pid);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_app_tracker() {
+        let grace = Duration::from_secs(1);
+        let start = Instant::now();
+        let later = start + grace;
+        let mut tracker = AppTracker::new(grace);
+        tracker.track(1, 620, 100);
+        tracker.track(2, 730, 200);
+
+        // Process 100 died but its window remains
+        assert!(tracker.check(start, |pid| pid != 100).is_empty());
+        assert_eq!(
+            tracker.check(later, |pid| pid != 100),
+            vec![AppHealthEvent::AppCrashed {
+                app_id: 620,
+                pid: 100
+            }]
+        );
+        assert!(tracker.check(later, |pid| pid != 100).is_empty());
+
+        // The window of process 200 is gone but the process is still running
+        tracker.remove(2, start);
+        assert_eq!(
+            tracker.check(later, |_| true),
+            vec![AppHealthEvent::AppWindowsLost {
+                app_id: 730,
+                pid: 200
+            }]
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::{connection::ConnectOptions, worker};
+
+/// Events emitted by the idle monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IdleEvent {
+    /// The user provided input after being idle
+    UserActive,
+    /// The user has not provided any input for the given duration
+    UserIdle(Duration),
+}
+
+/// [IdleMonitor] tracks user activity based on changes to the Gamescope input
+/// counter (GAMESCOPE_INPUT_COUNTER) and decides when the user became idle or
+/// active again, so frontends can dim the screen or go to sleep.
+#[derive(Debug, Clone)]
+pub struct IdleMonitor {
+    timeout: Duration,
+    last_input: Instant,
+    idle: bool,
+}
+
+impl IdleMonitor {
+    /// Create a monitor that considers the user idle after no input was
+    /// received for the given timeout
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_input: Instant::now(),
+            idle: false,
+        }
+    }
+
+    /// Returns the timeout after which the user is considered idle
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns true if the user is currently considered idle
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Handles input received at the given time. Returns
+    /// [IdleEvent::UserActive] if the user was idle before.
+    pub fn on_input(&mut self, now: Instant) -> Option<IdleEvent> {
+        self.last_input = now;
+        if !self.idle {
+            return None;
+        }
+        self.idle = false;
+        Some(IdleEvent::UserActive)
+    }
+
+    /// Checks whether the user became idle at the given time. Returns
+    /// [IdleEvent::UserIdle] once per idle period.
+    pub fn poll(&mut self, now: Instant) -> Option<IdleEvent> {
+        let elapsed = now.saturating_duration_since(self.last_input);
+        if self.idle || elapsed < self.timeout {
+            return None;
+        }
+        self.idle = true;
+        Some(IdleEvent::UserIdle(elapsed))
+    }
+
+    /// Returns how long to wait at the given time before the user may become
+    /// idle, or None if the user is already idle
+    fn time_until_idle(&self, now: Instant) -> Option<Duration> {
+        if self.idle {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.last_input);
+        Some(self.timeout.saturating_sub(elapsed))
+    }
+}
+
+/// Spawns a thread that feeds the input counter changes received from the
+/// given channel into an [IdleMonitor] with the given timeout and sends the
+/// resulting [IdleEvent]s.
+pub(crate) fn spawn_idle_monitor(
+    display: &str,
+    options: &ConnectOptions,
+    counter_rx: Receiver<u32>,
+    timeout: Duration,
+) -> (JoinHandle<()>, Receiver<IdleEvent>) {
+    let (tx, rx): (Sender<IdleEvent>, Receiver<IdleEvent>) = mpsc::channel();
+
+    let child = worker::spawn(display, "idle", options, move || {
+        let mut monitor = IdleMonitor::new(timeout);
+        loop {
+            let result = match monitor.time_until_idle(Instant::now()) {
+                Some(wait) => counter_rx.recv_timeout(wait),
+                None => counter_rx
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            let event = match result {
+                Ok(_) => monitor.on_input(Instant::now()),
+                Err(RecvTimeoutError::Timeout) => monitor.poll(Instant::now()),
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            let Some(event) = event else {
+                continue;
+            };
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    (child, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_monitor_events() {
+        let timeout = Duration::from_secs(30);
+        let mut monitor = IdleMonitor::new(timeout);
+        let start = Instant::now();
+
+        assert_eq!(monitor.on_input(start), None);
+        assert_eq!(monitor.poll(start + Duration::from_secs(10)), None);
+
+        let idle_at = start + Duration::from_secs(31);
+        assert_eq!(
+            monitor.poll(idle_at),
+            Some(IdleEvent::UserIdle(Duration::from_secs(31)))
+        );
+        assert!(monitor.is_idle());
+        assert_eq!(monitor.poll(idle_at + timeout), None);
+
+        assert_eq!(
+            monitor.on_input(idle_at + Duration::from_secs(5)),
+            Some(IdleEvent::UserActive)
+        );
+        assert!(!monitor.is_idle());
+    }
+}
@@ -0,0 +1,47 @@
+use x11rb::protocol::xproto;
+
+/// Synthetic input event sent to a window through the XTEST extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InputEvent {
+    /// Press the key with the given X keycode
+    KeyPress(u8),
+    /// Release the key with the given X keycode
+    KeyRelease(u8),
+    /// Press the given pointer button (E.g. 1 for the left button)
+    ButtonPress(u8),
+    /// Release the given pointer button
+    ButtonRelease(u8),
+    /// Move the pointer to the given position relative to the window
+    Motion { x: i16, y: i16 },
+}
+
+impl InputEvent {
+    /// Returns the X event type XTEST should generate for this event
+    pub(crate) fn event_type(&self) -> u8 {
+        match self {
+            InputEvent::KeyPress(_) => xproto::KEY_PRESS_EVENT,
+            InputEvent::KeyRelease(_) => xproto::KEY_RELEASE_EVENT,
+            InputEvent::ButtonPress(_) => xproto::BUTTON_PRESS_EVENT,
+            InputEvent::ButtonRelease(_) => xproto::BUTTON_RELEASE_EVENT,
+            InputEvent::Motion { .. } => xproto::MOTION_NOTIFY_EVENT,
+        }
+    }
+
+    /// Returns the keycode or button of the event
+    pub(crate) fn detail(&self) -> u8 {
+        match self {
+            InputEvent::KeyPress(detail)
+            | InputEvent::KeyRelease(detail)
+            | InputEvent::ButtonPress(detail)
+            | InputEvent::ButtonRelease(detail) => *detail,
+            InputEvent::Motion { .. } => 0,
+        }
+    }
+
+    /// Returns true if this is a key event, which is delivered to the window
+    /// with input focus
+    pub(crate) fn is_key(&self) -> bool {
+        matches!(self, InputEvent::KeyPress(_) | InputEvent::KeyRelease(_))
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::property::PropertyValue;
+
+/// A single property change made by this crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JournalEntry {
+    /// Name of the display the change was made on (E.g. ":0")
+    pub display: String,
+    /// The window the property was changed on
+    pub window_id: u32,
+    /// The name of the property (E.g. "STEAM_INPUT_FOCUS")
+    pub property: String,
+    /// The value before the change, or None if the property was not set
+    pub old: Option<PropertyValue>,
+    /// The value after the change, or None if the property was removed
+    pub new: Option<PropertyValue>,
+}
+
+/// [Journal] records every property change made through an
+/// [crate::xwayland::XWayland] instance it is enabled on to a file, one entry
+/// per line. If a daemon crashes while properties like STEAM_INPUT_FOCUS are
+/// still set, the next instance can use the journal to restore the values
+/// they had before (See [crate::manager::GamescopeManager::rollback_journal]).
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl Journal {
+    /// Create a journal that is stored in the given file. Entries left by a
+    /// previous process are kept until the journal is cleared.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Returns the path of the journal file
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Appends the given entry to the journal
+    pub fn record(&self, entry: &JournalEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let _lock = self.lock.lock().map_err(|err| err.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", encode_entry(entry))?;
+
+        Ok(())
+    }
+
+    /// Returns all entries in the order they were recorded
+    pub fn entries(&self) -> Result<Vec<JournalEntry>, Box<dyn std::error::Error>> {
+        let _lock = self.lock.lock().map_err(|err| err.to_string())?;
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut entries: Vec<JournalEntry> = Vec::new();
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            match decode_entry(line) {
+                Some(entry) => entries.push(entry),
+                None => log::warn!("Ignoring invalid journal entry: {}", line),
+            }
+        }
+
+        Ok(entries)
+    }
+
+    /// Removes all entries from the journal
+    pub fn clear(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let _lock = self.lock.lock().map_err(|err| err.to_string())?;
+        match fs::remove_file(&self.path) {
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Returns the entries needed to undo all of the given changes: the first
+/// change of every property, newest first, whose old value is the value the
+/// property had before this crate touched it.
+pub(crate) fn rollback_entries(entries: &[JournalEntry]) -> Vec<&JournalEntry> {
+    let mut seen: HashSet<(&str, u32, &str)> = HashSet::new();
+    let mut first: Vec<&JournalEntry> = entries
+        .iter()
+        .filter(|entry| {
+            seen.insert((
+                entry.display.as_str(),
+                entry.window_id,
+                entry.property.as_str(),
+            ))
+        })
+        .collect();
+    first.reverse();
+    first
+}
+
+/// Encodes the given entry as a single tab separated line
+fn encode_entry(entry: &JournalEntry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}",
+        escape(&entry.display),
+        entry.window_id,
+        escape(&entry.property),
+        encode_value(entry.old.as_ref()),
+        encode_value(entry.new.as_ref())
+    )
+}
+
+/// Decodes an entry from a line written by [encode_entry]
+fn decode_entry(line: &str) -> Option<JournalEntry> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [display, window_id, property, old, new] = fields.as_slice() else {
+        return None;
+    };
+
+    Some(JournalEntry {
+        display: unescape(display),
+        window_id: window_id.parse().ok()?,
+        property: unescape(property),
+        old: decode_value(old)?,
+        new: decode_value(new)?,
+    })
+}
+
+/// Encodes a property value. Only values this crate writes (cardinals, window
+/// ids and strings) are stored, anything else is recorded as unset.
+fn encode_value(value: Option<&PropertyValue>) -> String {
+    match value {
+        Some(PropertyValue::Cardinal(values)) => format!("c:{}", encode_numbers(values)),
+        Some(PropertyValue::Window(values)) => format!("w:{}", encode_numbers(values)),
+        Some(PropertyValue::String(value)) => format!("s:{}", escape(value)),
+        _ => "-".to_string(),
+    }
+}
+
+/// Decodes a property value written by [encode_value]. Returns None if the
+/// value is invalid.
+fn decode_value(value: &str) -> Option<Option<PropertyValue>> {
+    if value == "-" {
+        return Some(None);
+    }
+    if let Some(values) = value.strip_prefix("c:") {
+        return Some(Some(PropertyValue::Cardinal(decode_numbers(values)?)));
+    }
+    if let Some(values) = value.strip_prefix("w:") {
+        return Some(Some(PropertyValue::Window(decode_numbers(values)?)));
+    }
+    let value = value.strip_prefix("s:")?;
+
+    Some(Some(PropertyValue::String(unescape(value))))
+}
+
+/// Encodes a list of numbers separated by commas
+fn encode_numbers(values: &[u32]) -> String {
+    let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    values.join(",")
+}
+
+/// Decodes a list of numbers written by [encode_numbers]. Returns None if any
+/// of them is invalid.
+fn decode_numbers(values: &str) -> Option<Vec<u32>> {
+    values
+        .split(',')
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse().ok())
+        .collect()
+}
+
+/// Escapes characters that would break the line format
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+/// Reverses [escape]
+pub(crate) fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_rollback_entries() {
+        let change = |window_id, property: &str, old: Option<u32>, new: Option<u32>| JournalEntry {
+            display: ":1".into(),
+            window_id,
+            property: property.into(),
+            old: old.map(|value| PropertyValue::Cardinal(vec![value])),
+            new: new.map(|value| PropertyValue::Cardinal(vec![value])),
+        };
+        let lut = JournalEntry {
+            display: ":1".into(),
+            window_id: 1,
+            property: "GAMESCOPE_COLOR_3DLUT_OVERRIDE".into(),
+            old: None,
+            new: Some(PropertyValue::String("/tmp/a\tb.cube".into())),
+        };
+        let baselayer = JournalEntry {
+            display: ":1".into(),
+            window_id: 1,
+            property: "GAMESCOPECTRL_BASELAYER_WINDOW".into(),
+            old: Some(PropertyValue::Window(vec![0x400001])),
+            new: Some(PropertyValue::Window(vec![])),
+        };
+        let entries = vec![
+            change(7, "STEAM_INPUT_FOCUS", None, Some(1)),
+            change(1, "GAMESCOPECTRL_BASELAYER_APPID", Some(769), Some(620)),
+            change(7, "STEAM_INPUT_FOCUS", Some(1), Some(0)),
+            lut.clone(),
+        ];
+
+        for entry in entries.iter().chain([&baselayer]) {
+            assert_eq!(decode_entry(&encode_entry(entry)).as_ref(), Some(entry));
+        }
+
+        let rollback = rollback_entries(&entries);
+        assert_eq!(rollback, vec![&lut, &entries[1], &entries[0]]);
+    }
+}
@@ -0,0 +1,200 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::atoms::GamescopeAtom;
+
+/// Upper bounds of the histogram buckets in milliseconds. Samples above the
+/// last bound are counted in an overflow bucket.
+const BUCKET_BOUNDS_MS: [u64; 10] = [1, 2, 5, 10, 20, 50, 100, 200, 500, 1000];
+
+/// Writes older than this are assumed to not have changed the focus and are
+/// not matched with later feedback
+const MAX_PENDING_AGE: Duration = Duration::from_secs(5);
+
+/// Properties written by this crate that make Gamescope change focus
+pub(crate) const FOCUS_REQUEST_ATOMS: [GamescopeAtom; 3] = [
+    GamescopeAtom::BaselayerAppId,
+    GamescopeAtom::BaselayerWindow,
+    GamescopeAtom::SteamInputFocus,
+];
+
+/// Properties Gamescope updates when the focus changed
+pub(crate) const FOCUS_FEEDBACK_ATOMS: [GamescopeAtom; 3] = [
+    GamescopeAtom::FocusedApp,
+    GamescopeAtom::FocusedAppGFX,
+    GamescopeAtom::FocusedWindow,
+];
+
+/// Histogram of latency samples with fixed buckets from 1ms to 1s
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyHistogram {
+    counts: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    total: Duration,
+    min: Option<Duration>,
+    max: Option<Duration>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; BUCKET_BOUNDS_MS.len() + 1],
+            total: Duration::ZERO,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Adds the given sample to the histogram
+    pub fn record(&mut self, latency: Duration) {
+        let millis = latency.as_millis();
+        let index = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| millis <= *bound as u128)
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.counts[index] += 1;
+        self.total += latency;
+        self.min = Some(self.min.map_or(latency, |min| min.min(latency)));
+        self.max = Some(self.max.map_or(latency, |max| max.max(latency)));
+    }
+
+    /// Returns the number of samples
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the shortest sample
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// Returns the longest sample
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    /// Returns the average of all samples
+    pub fn mean(&self) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        Some(self.total / count as u32)
+    }
+
+    /// Returns the upper bound of the bucket containing the given percentile
+    /// (E.g. 0.95), or the longest sample if it falls in the overflow bucket
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let target = ((count as f64 * percentile.clamp(0.0, 1.0)).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, bucket) in self.counts.iter().enumerate() {
+            seen += bucket;
+            if seen < target {
+                continue;
+            }
+            return match BUCKET_BOUNDS_MS.get(index) {
+                Some(bound) => Some(Duration::from_millis(*bound)),
+                None => self.max,
+            };
+        }
+
+        self.max
+    }
+
+    /// Returns the upper bound and number of samples of every bucket. The
+    /// overflow bucket has no upper bound.
+    pub fn buckets(&self) -> Vec<(Option<Duration>, u64)> {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(index, count)| {
+                let bound = BUCKET_BOUNDS_MS.get(index).copied();
+                (bound.map(Duration::from_millis), *count)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    pending: Option<Instant>,
+    histogram: LatencyHistogram,
+}
+
+/// Matches focus requests written by this crate with the focus feedback from
+/// Gamescope to measure how long it takes to switch apps
+#[derive(Debug, Default)]
+pub(crate) struct FocusLatencyTracker {
+    state: Mutex<TrackerState>,
+}
+
+impl FocusLatencyTracker {
+    /// Remembers that a focus request was written at the given time. Only the
+    /// first of several writes before the feedback arrives is kept.
+    pub fn on_request(&self, now: Instant) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        let stale = state
+            .pending
+            .is_some_and(|pending| now.saturating_duration_since(pending) > MAX_PENDING_AGE);
+        if state.pending.is_none() || stale {
+            state.pending = Some(now);
+        }
+    }
+
+    /// Handles focus feedback received at the given time. Returns the latency
+    /// since the pending request, if there is one.
+    pub fn on_feedback(&self, now: Instant) -> Option<Duration> {
+        let mut state = self.state.lock().ok()?;
+        let latency = now.saturating_duration_since(state.pending.take()?);
+        if latency > MAX_PENDING_AGE {
+            return None;
+        }
+        state.histogram.record(latency);
+        Some(latency)
+    }
+
+    /// Returns a copy of the measured latencies
+    pub fn histogram(&self) -> LatencyHistogram {
+        self.state
+            .lock()
+            .map(|state| state.histogram.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_latency_histogram() {
+        let tracker = FocusLatencyTracker::default();
+        let start = Instant::now();
+        assert_eq!(tracker.on_feedback(start), None);
+
+        tracker.on_request(start);
+        tracker.on_request(start + Duration::from_millis(3));
+        let latency = tracker.on_feedback(start + Duration::from_millis(8));
+        assert_eq!(latency, Some(Duration::from_millis(8)));
+        assert_eq!(tracker.on_feedback(start + Duration::from_millis(9)), None);
+
+        tracker.on_request(start + Duration::from_secs(1));
+        tracker.on_feedback(start + Duration::from_millis(1040));
+
+        let histogram = tracker.histogram();
+        assert_eq!(histogram.count(), 2);
+        assert_eq!(histogram.min(), Some(Duration::from_millis(8)));
+        assert_eq!(histogram.max(), Some(Duration::from_millis(40)));
+        assert_eq!(histogram.mean(), Some(Duration::from_millis(24)));
+        assert_eq!(histogram.percentile(0.5), Some(Duration::from_millis(10)));
+        assert_eq!(histogram.percentile(1.0), Some(Duration::from_millis(50)));
+    }
+}
@@ -1,18 +1,82 @@
-use std::fs;
+use std::path::Path;
 
-use x11rb::connection::Connection;
+use discovery::{
+    DiscoveredDisplay, DiscoveryError, DiscoveryOptions, DiscoveryReport, DisplayDiagnostic,
+    DisplayStatus, UnixSocket, X11_SOCKET_DIR,
+};
+use xwayland::Disconnected;
 
+mod atom_cache;
 pub mod atoms;
+#[cfg(not(feature = "xcb"))]
+mod auth;
+pub mod builder;
+pub mod callback;
+pub mod channel;
+pub mod color;
+pub mod connection;
+pub mod control;
+pub mod coordination;
+#[cfg(feature = "dbus")]
+pub mod dbus;
+pub mod debounce;
+pub mod discovery;
+pub mod display;
+mod event_loop;
+pub mod extension;
+pub mod fake;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod focus;
+pub mod health;
+pub mod idle;
+pub mod input;
+pub mod journal;
+pub mod latency;
+pub mod manager;
+pub mod metadata;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod mode;
+pub mod overrides;
+mod pool;
+pub mod profiles;
+pub mod property;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod screenshot;
+pub mod state;
+pub mod stats;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod transaction;
+pub mod validation;
+pub mod watch;
+pub mod watchdog;
+pub mod window;
+pub mod window_tracker;
+mod worker;
 mod x11;
 pub mod xwayland;
 
 // Returns instances to all available Gamescope XWaylands
-pub fn discover_gamescope_xwaylands() -> Result<Vec<xwayland::XWayland>, Box<dyn std::error::Error>>
-{
-    let gamescope_displays = discover_gamescope_displays()?;
+pub fn discover_gamescope_xwaylands(
+) -> Result<Vec<xwayland::XWayland<Disconnected>>, Box<dyn std::error::Error>> {
+    discover_gamescope_xwaylands_with_options(&DiscoveryOptions::default())
+}
+
+/// Returns instances to all available Gamescope XWaylands found using the
+/// given options. The instances connect using the connect options (E.g. an
+/// explicit Xauthority file for displays owned by another user).
+pub fn discover_gamescope_xwaylands_with_options(
+    options: &DiscoveryOptions,
+) -> Result<Vec<xwayland::XWayland<Disconnected>>, Box<dyn std::error::Error>> {
+    let gamescope_displays = discover_gamescope_displays_with_options(options)?;
     let xwaylands = gamescope_displays
         .iter()
-        .map(|display_name| xwayland::XWayland::new(display_name.into()))
+        .map(|display_name| {
+            xwayland::XWayland::with_options(display_name.into(), options.connect.clone())
+        })
         .collect();
 
     Ok(xwaylands)
@@ -20,61 +84,208 @@ pub fn discover_gamescope_xwaylands() -> Result<Vec<xwayland::XWayland>, Box<dyn
 
 /// Returns all gamescope xwayland names (E.g. [":0", ":1"])
 pub fn discover_gamescope_displays() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    discover_gamescope_displays_with_options(&DiscoveryOptions::default())
+}
+
+/// Returns all gamescope xwayland names (E.g. [":0", ":1"]) found using the
+/// given options.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options), err(Display)))]
+pub fn discover_gamescope_displays_with_options(
+    options: &DiscoveryOptions,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
     // Discover all x11 displays
-    let x11_displays = discover_x11_displays()?;
+    let x11_displays = discover_x11_displays_with_options(options)?;
 
     // Array of gamescope xwayland displays
     let mut gamescope_displays: Vec<String> = Vec::new();
 
-    // Check to see if the root window of these displays has gamescope-specific properties
-    for display in x11_displays {
-        // Connect to the display
-        let result = x11rb::connect(Some(display.as_str()));
-        if result.is_err() {
+    // Check to see if the root window of these displays has gamescope-specific
+    // properties. All displays are probed at the same time so a hung server
+    // can't stall discovery.
+    let probes = discovery::probe_displays(&x11_displays, options);
+    for (display, probe) in x11_displays.into_iter().zip(probes) {
+        match probe.status {
+            DisplayStatus::Gamescope => gamescope_displays.push(display),
+            DisplayStatus::QueryFailed(err) => return Err(err.into()),
+            _ => continue,
+        }
+    }
+
+    Ok(gamescope_displays)
+}
+
+/// Discovers and connects to the primary Gamescope XWayland instance. Returns
+/// a [DiscoveryError::NoPrimaryFound] error if there is none.
+pub fn discover_primary_xwayland() -> Result<xwayland::XWayland, Box<dyn std::error::Error>> {
+    discover_primary_xwayland_with_options(&DiscoveryOptions::default())
+}
+
+/// Discovers and connects to the primary Gamescope XWayland instance using the
+/// given options
+pub fn discover_primary_xwayland_with_options(
+    options: &DiscoveryOptions,
+) -> Result<xwayland::XWayland, Box<dyn std::error::Error>> {
+    let displays = discover_x11_displays_with_options(options)?;
+    let probes = discovery::probe_displays(&displays, options);
+    let Some((display, _)) = displays
+        .into_iter()
+        .zip(probes)
+        .find(|(_, probe)| probe.status == DisplayStatus::Gamescope && probe.is_primary)
+    else {
+        return Err(DiscoveryError::NoPrimaryFound.into());
+    };
+
+    let xwayland = xwayland::XWayland::with_options(display, options.connect.clone()).connect()?;
+
+    Ok(xwayland)
+}
+
+/// Discovers and connects to all Gamescope XWayland instances except for the
+/// primary one
+pub fn discover_secondary_xwaylands() -> Result<Vec<xwayland::XWayland>, Box<dyn std::error::Error>>
+{
+    discover_secondary_xwaylands_with_options(&DiscoveryOptions::default())
+}
+
+/// Discovers and connects to all Gamescope XWayland instances except for the
+/// primary one using the given options
+pub fn discover_secondary_xwaylands_with_options(
+    options: &DiscoveryOptions,
+) -> Result<Vec<xwayland::XWayland>, Box<dyn std::error::Error>> {
+    let displays = discover_x11_displays_with_options(options)?;
+    let probes = discovery::probe_displays(&displays, options);
+
+    let mut xwaylands: Vec<xwayland::XWayland> = Vec::new();
+    for (display, probe) in displays.into_iter().zip(probes) {
+        if probe.status != DisplayStatus::Gamescope || probe.is_primary {
             continue;
         }
-        let (conn, screen_num) = result.unwrap();
-        let screen = &conn.setup().roots[screen_num];
+        let xwayland = xwayland::XWayland::with_options(display, options.connect.clone());
+        xwaylands.push(xwayland.connect()?);
+    }
+
+    Ok(xwaylands)
+}
 
-        let root_window_id = screen.root;
+/// Discovers all available Gamescope XWaylands and returns them along with
+/// diagnostics for every X11 display that was probed (E.g. a display whose
+/// socket exists but rejected the connection because of authentication).
+pub fn discover_with_report() -> Result<DiscoveryReport, Box<dyn std::error::Error>> {
+    discover_with_report_and_options(&DiscoveryOptions::default())
+}
+
+/// Discovers all available Gamescope XWaylands using the given options and
+/// returns them along with diagnostics for every probed display.
+pub fn discover_with_report_and_options(
+    options: &DiscoveryOptions,
+) -> Result<DiscoveryReport, Box<dyn std::error::Error>> {
+    let displays = discover_x11_displays_with_options(options)?;
+    let probes = discovery::probe_displays(&displays, options);
 
-        // Add the display name to the list of gamescope displays
-        if x11::is_gamescope_xwayland(conn, root_window_id)? {
-            gamescope_displays.push(display);
+    let mut report = DiscoveryReport::default();
+    for (display, probe) in displays.into_iter().zip(probes) {
+        let status = probe.status;
+        if status == DisplayStatus::Gamescope {
+            let connect = options.connect.clone();
+            let xwayland = xwayland::XWayland::with_options(display.clone(), connect);
+            report.xwaylands.push(xwayland);
         }
+        report.diagnostics.push(DisplayDiagnostic {
+            name: display,
+            status,
+        });
     }
 
-    Ok(gamescope_displays)
+    Ok(report)
+}
+
+/// Returns details about every X11 display found using the default options,
+/// including its socket path, the process ID of its Xwayland server and
+/// whether it is the primary Gamescope XWayland.
+pub fn discover_detailed() -> Result<Vec<DiscoveredDisplay>, Box<dyn std::error::Error>> {
+    discover_detailed_with_options(&DiscoveryOptions::default())
+}
+
+/// Returns details about every X11 display found using the given options
+pub fn discover_detailed_with_options(
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredDisplay>, Box<dyn std::error::Error>> {
+    let sockets = discovery::list_unix_sockets().unwrap_or_default();
+    let names = discover_x11_displays_with_options(options)?;
+    let probes = discovery::probe_displays(&names, options);
+
+    let mut displays: Vec<DiscoveredDisplay> = Vec::new();
+    for (name, probe) in names.into_iter().zip(probes) {
+        // Find the sockets of the display, preferring the filesystem socket
+        let display_sockets: Vec<&UnixSocket> = sockets
+            .iter()
+            .filter(|socket| socket.display == name || socket.path == name)
+            .collect();
+        let socket_path = display_sockets
+            .iter()
+            .map(|socket| socket.path.clone())
+            .min_by_key(|path| path.starts_with('@'))
+            .or_else(|| discovery::socket_path_for_display(name.as_str()));
+        let inodes: Vec<u64> = display_sockets.iter().map(|socket| socket.inode).collect();
+        let xwayland_pid = discovery::find_socket_owner(&inodes);
+
+        displays.push(DiscoveredDisplay {
+            name,
+            socket_path,
+            xwayland_pid,
+            is_gamescope: probe.status == DisplayStatus::Gamescope,
+            is_primary: probe.is_primary,
+        });
+    }
+
+    Ok(displays)
 }
 
 /// Returns all x11 display names (E.g. [":0", ":1"])
 pub fn discover_x11_displays() -> Result<Vec<String>, Box<dyn std::error::Error>> {
-    // Array of X11 displays
-    let mut display_names: Vec<String> = Vec::new();
-
     // X11 displays have a corresponding socket in /tmp/.X11-unix
     // The sockets are named like: X0, X1, X2, etc.
-    let sockets = fs::read_dir("/tmp/.X11-unix")?;
-
-    // Loop through each socket file and derive the display number
-    for socket in sockets {
-        let dir_entry = &socket?;
-        let path = &dir_entry.path();
-
-        // Get the name of the socket (E.g. "X0") and get its suffix
-        // (E.g. "0")
-        let socket = path.file_name().unwrap().to_os_string();
-        let socket = socket.into_string();
-        let socket = &socket.unwrap();
-        let suffix = socket.strip_prefix('X').unwrap();
-
-        // Skip X11 sockets with weird names
-        if suffix.parse::<u64>().is_err() {
-            continue;
-        }
+    let from_dir = discovery::list_socket_dir(Path::new(X11_SOCKET_DIR));
+
+    // Xwayland may only listen on an abstract socket (E.g. "@/tmp/.X11-unix/X1"),
+    // which can only be found in /proc/net/unix
+    let from_proc = discovery::list_proc_net_unix();
+
+    let mut display_names: Vec<String> = match (from_dir, from_proc) {
+        (Err(err), Err(_)) => return Err(err.into()),
+        (from_dir, from_proc) => from_dir
+            .unwrap_or_default()
+            .into_iter()
+            .chain(from_proc.unwrap_or_default())
+            .collect(),
+    };
+    discovery::dedup_displays(&mut display_names);
+
+    Ok(display_names)
+}
 
-        display_names.push(format!(":{}", suffix));
+/// Returns all x11 display names found using the strategies selected in the
+/// given options. Displays found by multiple strategies are only returned
+/// once.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options), err(Display)))]
+pub fn discover_x11_displays_with_options(
+    options: &DiscoveryOptions,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut display_names: Vec<String> = Vec::new();
+    if options.socket_dir {
+        match discover_x11_displays() {
+            Ok(displays) => display_names.extend(displays),
+            Err(err) => log::debug!("Unable to list {}: {}", X11_SOCKET_DIR, err),
+        }
     }
+    if options.xdg_runtime_dir {
+        display_names.extend(discovery::list_xdg_runtime_dir());
+    }
+    if options.display_env {
+        display_names.extend(discovery::display_from_env());
+    }
+
+    discovery::dedup_displays(&mut display_names);
 
     Ok(display_names)
 }
@@ -86,8 +297,8 @@ mod tests {
     #[test]
     fn test_discover_gamescope_displays() {
         let xwaylands = discover_gamescope_xwaylands().unwrap();
-        for mut xwayland in xwaylands {
-            xwayland.connect().unwrap();
+        for xwayland in xwaylands {
+            let xwayland = xwayland.connect().unwrap();
             //xwayland.get_focusable_apps();
             let is_primary = xwayland.is_primary_instance().unwrap();
             println!(
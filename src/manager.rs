@@ -0,0 +1,874 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use x11rb::protocol::xproto::EventMask;
+
+use crate::{
+    atoms::GamescopeAtom,
+    connection::ConnectionEvent,
+    discover_gamescope_xwaylands_with_options,
+    discovery::DiscoveryOptions,
+    input::InputEvent,
+    journal::{self, Journal},
+    overrides::{self, AppIdOverride},
+    profiles::{ProfileApplier, ProfileStore},
+    property::PropertyValue,
+    watch::{self, WatchListener, WatchSpec, WindowSelector},
+    worker,
+    xwayland::{Primary, ScreenshotType, XWayland},
+};
+
+/// Maximum number of baselayer focus changes remembered by the manager
+const FOCUS_HISTORY_LIMIT: usize = 32;
+
+/// How often discovery is retried while waiting for Gamescope to start
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Control atoms Gamescope interns on startup that must exist before the
+/// primary instance is considered ready
+const READY_ATOMS: [GamescopeAtom; 4] = [
+    GamescopeAtom::FocusableApps,
+    GamescopeAtom::FocusedApp,
+    GamescopeAtom::BaselayerAppId,
+    GamescopeAtom::BaselayerWindow,
+];
+
+/// Screenshot path or error of each instance, keyed by display name
+pub type ScreenshotResults = HashMap<String, Result<PathBuf, Box<dyn std::error::Error>>>;
+
+/// Root window properties this crate may change, captured by
+/// [GamescopeManager::capture_defaults] so they can be restored on shutdown
+const SESSION_ATOMS: [GamescopeAtom; 26] = [
+    GamescopeAtom::FPSLimit,
+    GamescopeAtom::BlurMode,
+    GamescopeAtom::BlurRadius,
+    GamescopeAtom::AllowTearing,
+    GamescopeAtom::ForceWindowsFullscreen,
+    GamescopeAtom::ScalingFilter,
+    GamescopeAtom::FSRSharpness,
+    GamescopeAtom::XWaylandModeControl,
+    GamescopeAtom::DynamicRefresh,
+    GamescopeAtom::VRREnabled,
+    GamescopeAtom::DisplayHDREnabled,
+    GamescopeAtom::SDROnHDRContentBrightness,
+    GamescopeAtom::HDRItmEnable,
+    GamescopeAtom::HDRItmSDRNits,
+    GamescopeAtom::HDRItmTargetNits,
+    GamescopeAtom::ColorGain,
+    GamescopeAtom::ColorLinearGain,
+    GamescopeAtom::ColorLinearGainBlend,
+    GamescopeAtom::ColorMatrix,
+    GamescopeAtom::ColorSDRGamutWideness,
+    GamescopeAtom::ColorNightMode,
+    GamescopeAtom::Color3DLutOverride,
+    GamescopeAtom::ColorShaperLutOverride,
+    GamescopeAtom::BaselayerWindow,
+    GamescopeAtom::BaselayerAppId,
+    GamescopeAtom::Overview,
+];
+
+/// Callback invoked with the affected instance by the instance hooks
+pub type InstanceHook = Arc<dyn Fn(&XWayland) + Send + Sync>;
+
+/// Hooks registered with [GamescopeManager::on_instance_lost] and
+/// [GamescopeManager::on_instance_restored]
+#[derive(Default, Clone)]
+struct InstanceHooks {
+    lost: Vec<InstanceHook>,
+    restored: Vec<InstanceHook>,
+}
+
+impl fmt::Debug for InstanceHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstanceHooks")
+            .field("lost", &self.lost.len())
+            .field("restored", &self.restored.len())
+            .finish()
+    }
+}
+
+/// Value a root window property had when the session defaults were captured
+#[derive(Debug, Clone)]
+struct SessionDefault {
+    display: String,
+    property: GamescopeAtom,
+    value: Option<PropertyValue>,
+}
+
+/// [GamescopeManager] owns every discovered Gamescope XWayland instance and
+/// provides functionality that spans across all of them.
+#[derive(Debug, Default)]
+pub struct GamescopeManager {
+    xwaylands: Vec<XWayland>,
+    app_id_overrides: Vec<AppIdOverride>,
+    focus_history: Vec<u32>,
+    journal: Option<Arc<Journal>>,
+    defaults: Vec<SessionDefault>,
+    hooks: InstanceHooks,
+    watches: Vec<WatchSpec>,
+}
+
+impl GamescopeManager {
+    /// Create a new manager from the given XWayland instances
+    pub fn new(xwaylands: Vec<XWayland>) -> Self {
+        Self {
+            xwaylands,
+            app_id_overrides: Vec::new(),
+            focus_history: Vec::new(),
+            journal: None,
+            defaults: Vec::new(),
+            hooks: InstanceHooks::default(),
+            watches: Vec::new(),
+        }
+    }
+
+    /// Discover and connect to all available Gamescope XWayland instances
+    pub fn discover() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::discover_with_options(&DiscoveryOptions::default())
+    }
+
+    /// Discover and connect to all available Gamescope XWayland instances
+    /// using the given discovery options
+    pub fn discover_with_options(
+        options: &DiscoveryOptions,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let xwaylands = discover_gamescope_xwaylands_with_options(options)?
+            .into_iter()
+            .map(|xwayland| xwayland.connect())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self::new(xwaylands))
+    }
+
+    /// Discovers and connects to all Gamescope XWayland instances once the
+    /// primary instance exists and exposes its control atoms, retrying until
+    /// the given timeout elapses. Use this when starting in parallel with
+    /// Gamescope (E.g. from a systemd unit) to avoid racing its startup.
+    pub fn wait_until_ready(timeout: Duration) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::wait_until_ready_with_options(&DiscoveryOptions::default(), timeout)
+    }
+
+    /// Waits until Gamescope is ready using the given discovery options. See
+    /// [GamescopeManager::wait_until_ready].
+    pub fn wait_until_ready_with_options(
+        options: &DiscoveryOptions,
+        timeout: Duration,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let last_error = match Self::discover_with_options(options) {
+                Ok(manager) => match manager.is_ready() {
+                    Ok(true) => return Ok(manager),
+                    Ok(false) => "primary instance is not ready".to_string(),
+                    Err(err) => err.to_string(),
+                },
+                Err(err) => err.to_string(),
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(format!("Timed out waiting for Gamescope: {}", last_error).into());
+            }
+            log::debug!("Waiting for Gamescope to become ready: {}", last_error);
+            thread::sleep(READY_POLL_INTERVAL.min(remaining));
+        }
+    }
+
+    /// Returns true if a connected primary instance is managed and it exposes
+    /// all expected control atoms
+    fn is_ready(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let Some(primary) = self.get_primary()? else {
+            return Ok(false);
+        };
+        if !primary.is_connected() {
+            return Ok(false);
+        }
+        for atom in READY_ATOMS {
+            if !primary.has_atom(atom)? {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Returns all managed XWayland instances
+    pub fn get_xwaylands(&self) -> &[XWayland] {
+        self.xwaylands.as_slice()
+    }
+
+    /// Returns the managed XWayland instance with the given name (E.g. ":0")
+    pub fn get_xwayland(&self, name: &str) -> Option<&XWayland> {
+        self.xwaylands
+            .iter()
+            .find(|xwayland| xwayland.get_name() == name)
+    }
+
+    /// Returns the primary Gamescope XWayland instance
+    pub fn get_primary(&self) -> Result<Option<&XWayland>, Box<dyn std::error::Error>> {
+        for xwayland in self.xwaylands.iter() {
+            if xwayland.is_primary_instance()? {
+                return Ok(Some(xwayland));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the Steam client UI windows across all managed instances,
+    /// paired with the instance they were found on. See
+    /// [XWayland::find_steam_ui_windows].
+    pub fn find_steam_ui_windows(
+        &self,
+    ) -> Result<Vec<(&XWayland, u32)>, Box<dyn std::error::Error>> {
+        let mut windows = Vec::new();
+        for xwayland in self.xwaylands.iter() {
+            for window_id in xwayland.find_steam_ui_windows()? {
+                windows.push((xwayland, window_id));
+            }
+        }
+
+        Ok(windows)
+    }
+
+    /// Returns the instance the Steam client UI is running on, if any
+    pub fn get_steam_ui_instance(&self) -> Result<Option<&XWayland>, Box<dyn std::error::Error>> {
+        for xwayland in self.xwaylands.iter() {
+            if !xwayland.find_steam_ui_windows()?.is_empty() {
+                return Ok(Some(xwayland));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Returns all instances with an attached value of the given type that
+    /// matches the given predicate. See [XWayland::set_metadata].
+    pub fn find_by_metadata<T, P>(&self, predicate: P) -> Vec<&XWayland>
+    where
+        T: Any + Send + Sync,
+        P: Fn(&T) -> bool,
+    {
+        self.xwaylands
+            .iter()
+            .filter(|xwayland| xwayland.with_metadata(&predicate).unwrap_or_default())
+            .collect()
+    }
+
+    /// Returns the first instance with an attached value of the given type
+    /// that matches the given predicate
+    pub fn find_one_by_metadata<T, P>(&self, predicate: P) -> Option<&XWayland>
+    where
+        T: Any + Send + Sync,
+        P: Fn(&T) -> bool,
+    {
+        self.xwaylands
+            .iter()
+            .find(|xwayland| xwayland.with_metadata(&predicate).unwrap_or_default())
+    }
+
+    /// Returns all instances with an attached value of the given type, along
+    /// with a copy of the value
+    pub fn get_metadata<T>(&self) -> Vec<(&XWayland, T)>
+    where
+        T: Any + Send + Sync + Clone,
+    {
+        self.xwaylands
+            .iter()
+            .filter_map(|xwayland| Some((xwayland, xwayland.get_metadata::<T>()?)))
+            .collect()
+    }
+
+    /// Returns the instance and main top-level window hosting the given app id
+    /// (STEAM_GAME), searching all managed instances. See
+    /// [XWayland::get_main_window].
+    pub fn find_app_window(
+        &self,
+        app_id: u32,
+    ) -> Result<Option<(&XWayland, u32)>, Box<dyn std::error::Error>> {
+        for xwayland in self.xwaylands.iter() {
+            let windows = xwayland.find_app_windows(app_id)?;
+            if let Some(window_id) = xwayland.get_main_window(&windows)? {
+                return Ok(Some((xwayland, window_id)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Sends the given synthetic input event to the app with the given app id,
+    /// on whichever instance hosts it (E.g. a game on a nested XWayland
+    /// server). See [XWayland::send_input].
+    pub fn send_input_to_app(
+        &self,
+        app_id: u32,
+        event: InputEvent,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (xwayland, window_id) = self
+            .find_app_window(app_id)?
+            .ok_or_else(|| format!("No window found for app {}", app_id))?;
+        xwayland.send_input(window_id, event)
+    }
+
+    /// Returns the instance whose XWayland server currently has focus according
+    /// to GAMESCOPE_FOCUS_DISPLAY on the primary instance. Gamescope numbers
+    /// its XWayland servers in the order of their display numbers, starting
+    /// with the primary. Returns None if the focused server is not managed.
+    pub fn get_focused_instance(&self) -> Result<Option<&XWayland>, Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?;
+        let index = primary.get_focus_display()?.unwrap_or_default();
+        if index == 0 {
+            return Ok(Some(primary));
+        }
+
+        let Some(primary_display) = display_number(primary.get_name().as_str()) else {
+            return Ok(None);
+        };
+        let focused = self.xwaylands.iter().find(|xwayland| {
+            display_number(xwayland.get_name().as_str()) == Some(primary_display + index)
+        });
+
+        Ok(focused)
+    }
+
+    /// Returns the instance whose XWayland server currently has keyboard focus
+    /// according to GAMESCOPE_KEYBOARD_FOCUS_DISPLAY on the primary instance.
+    /// Returns None if the focused server is not managed.
+    pub fn get_keyboard_focused_instance(
+        &self,
+    ) -> Result<Option<&XWayland>, Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?;
+        let display = primary.get_keyboard_focus_display()?;
+        let Some(number) = display.as_deref().and_then(display_number) else {
+            return Ok(None);
+        };
+        let focused = self
+            .xwaylands
+            .iter()
+            .find(|xwayland| display_number(xwayland.get_name().as_str()) == Some(number));
+
+        Ok(focused)
+    }
+
+    /// Sets the app id used to identify the overlay app on all managed
+    /// instances. See [XWayland::set_overlay_app_id].
+    pub fn set_overlay_app_id(&self, app_id: u32) {
+        for xwayland in self.xwaylands.iter() {
+            xwayland.set_overlay_app_id(app_id);
+        }
+    }
+
+    /// Records every property change made through the managed instances in a
+    /// journal stored at the given path. Entries a crashed process left in the
+    /// journal are kept so they can be restored with
+    /// [GamescopeManager::rollback_journal].
+    pub fn enable_journal(&mut self, path: impl Into<PathBuf>) {
+        let journal = Arc::new(Journal::new(path));
+        for xwayland in self.xwaylands.iter() {
+            xwayland.set_journal(Some(journal.clone()));
+        }
+        self.journal = Some(journal);
+    }
+
+    /// Stops recording property changes
+    pub fn disable_journal(&mut self) {
+        for xwayland in self.xwaylands.iter() {
+            xwayland.set_journal(None);
+        }
+        self.journal = None;
+    }
+
+    /// Returns the journal property changes are recorded in, if enabled
+    pub fn get_journal(&self) -> Option<&Journal> {
+        self.journal.as_deref()
+    }
+
+    /// Restores every property recorded in the journal to the value it had
+    /// before it was first changed (E.g. input focus or baselayer settings a
+    /// crashed daemon left behind) and clears the journal. Entries for
+    /// instances that are not managed or windows that no longer exist are
+    /// skipped. Returns the number of restored properties.
+    pub fn rollback_journal(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let journal = self.journal.as_ref().ok_or("Journal is not enabled")?;
+        let entries = journal.entries()?;
+
+        let mut count = 0;
+        for entry in journal::rollback_entries(&entries) {
+            let Some(xwayland) = self.get_xwayland(entry.display.as_str()) else {
+                log::debug!("Skipping journal entry for {}", entry.display);
+                continue;
+            };
+            let value = entry.old.as_ref();
+            match xwayland.restore_property(entry.window_id, entry.property.as_str(), value) {
+                Ok(_) => count += 1,
+                Err(err) => log::warn!(
+                    "Unable to restore {} on window {}: {}",
+                    entry.property,
+                    entry.window_id,
+                    err
+                ),
+            }
+        }
+        journal.clear()?;
+
+        Ok(count)
+    }
+
+    /// Captures the current value of every root window property this crate
+    /// may change on all managed instances (E.g. FPS limit, scaling, color
+    /// and baselayer settings). Call this at startup, before changing
+    /// anything, so [GamescopeManager::restore_defaults] can put the session
+    /// back the way it was. Any previously captured defaults are replaced.
+    /// Properties the running Gamescope does not know are skipped.
+    pub fn capture_defaults(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut defaults = Vec::new();
+        for xwayland in self.xwaylands.iter() {
+            let root_id = xwayland.get_root_window_id()?;
+            for property in SESSION_ATOMS {
+                // Reading a property interns its atom, which would make a
+                // feature like the overview look supported
+                if !xwayland.has_atom(property)? {
+                    continue;
+                }
+                let value = xwayland.get_decoded_xprop(root_id, property.to_string().as_str())?;
+                defaults.push(SessionDefault {
+                    display: xwayland.get_name(),
+                    property,
+                    value,
+                });
+            }
+        }
+        self.defaults = defaults;
+
+        Ok(())
+    }
+
+    /// Returns true if session defaults were captured and not yet restored
+    pub fn has_defaults(&self) -> bool {
+        !self.defaults.is_empty()
+    }
+
+    /// Restores every property captured by [GamescopeManager::capture_defaults]
+    /// that has changed since, then forgets the captured values. This is also
+    /// done automatically when the manager is dropped. Properties that can't
+    /// be restored are logged and skipped. Returns the number of restored
+    /// properties.
+    pub fn restore_defaults(&mut self) -> Result<usize, Box<dyn std::error::Error>> {
+        let defaults = std::mem::take(&mut self.defaults);
+
+        let mut count = 0;
+        for default in defaults.iter() {
+            let Some(xwayland) = self.get_xwayland(default.display.as_str()) else {
+                log::debug!("Skipping session default for {}", default.display);
+                continue;
+            };
+            let property = default.property.to_string();
+            let result = xwayland.get_root_window_id().and_then(|root_id| {
+                let current = xwayland.get_decoded_xprop(root_id, property.as_str())?;
+                if current == default.value {
+                    return Ok(false);
+                }
+                xwayland.restore_property(root_id, property.as_str(), default.value.as_ref())?;
+                Ok(true)
+            });
+            match result {
+                Ok(true) => count += 1,
+                Ok(false) => (),
+                Err(err) => log::warn!(
+                    "Unable to restore {} on {}: {}",
+                    property,
+                    default.display,
+                    err
+                ),
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Takes a screenshot of the given type on the primary instance and on
+    /// every other instance whose server knows the screenshot request atom,
+    /// waiting up to the given timeout for each. Screenshots are taken one
+    /// after another because Gamescope writes them all to the same directory.
+    /// Returns the path of the screenshot or the error of every instance,
+    /// keyed by display name (E.g. ":0").
+    pub fn screenshot_all(
+        &self,
+        screenshot_type: ScreenshotType,
+        timeout: Duration,
+    ) -> Result<ScreenshotResults, Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?;
+        let mut results = HashMap::new();
+        results.insert(
+            primary.get_name(),
+            primary.take_screenshot_with_type(screenshot_type, timeout),
+        );
+
+        for xwayland in self.xwaylands.iter() {
+            if xwayland.get_name() == primary.get_name() {
+                continue;
+            }
+            match xwayland.has_atom(GamescopeAtom::RequestScreenshot) {
+                Ok(true) => (),
+                Ok(false) => {
+                    log::debug!("Screenshots not supported on {}", xwayland.get_name());
+                    continue;
+                }
+                Err(err) => {
+                    results.insert(xwayland.get_name(), Err(err));
+                    continue;
+                }
+            }
+            let result = xwayland.take_screenshot_with_type(screenshot_type, timeout);
+            results.insert(xwayland.get_name(), result);
+        }
+
+        Ok(results)
+    }
+
+    /// Registers a hook that is called when the connection to an instance is
+    /// lost (E.g. because Gamescope restarted). Hooks only run for instances
+    /// watched with [GamescopeManager::watch_instances].
+    pub fn on_instance_lost<F>(&mut self, hook: F)
+    where
+        F: Fn(&XWayland) + Send + Sync + 'static,
+    {
+        self.hooks.lost.push(Arc::new(hook));
+    }
+
+    /// Registers a hook that is called when the connection to a lost instance
+    /// was re-established. Use this to re-apply overlay registrations or
+    /// profiles on the restarted Gamescope. Hooks only run for instances
+    /// watched with [GamescopeManager::watch_instances].
+    pub fn on_instance_restored<F>(&mut self, hook: F)
+    where
+        F: Fn(&XWayland) + Send + Sync + 'static,
+    {
+        self.hooks.restored.push(Arc::new(hook));
+    }
+
+    /// Watches every managed instance for its connection being lost and
+    /// restored, and calls the registered hooks. Instances must have a
+    /// [crate::connection::ReconnectPolicy] to be restored. For each instance
+    /// the hooks run on a single thread in the order they were registered,
+    /// and a restart always runs in this order:
+    ///
+    /// 1. The lost hooks, before the first reconnect attempt
+    /// 2. Reconnecting and subscribing all active listeners again
+    /// 3. Re-applying the app id override rules to the windows of the new
+    ///    server
+    /// 4. The restored hooks
+    ///
+    /// Hooks registered after this is called are not used.
+    pub fn watch_instances(&self) -> Result<Vec<JoinHandle<()>>, Box<dyn std::error::Error>> {
+        let mut handles: Vec<JoinHandle<()>> = Vec::new();
+        for xwayland in self.xwaylands.iter() {
+            let rx = xwayland.listen_for_connection_events()?;
+            let watcher = xwayland.clone();
+            let hooks = self.hooks.clone();
+            let rules = self.app_id_overrides.clone();
+            let options = xwayland.get_connect_options();
+            let handle = worker::spawn(&xwayland.get_name(), "instance", &options, move || {
+                for event in rx {
+                    match event {
+                        ConnectionEvent::Disconnected => {
+                            log::info!("Lost Gamescope instance {}", watcher.get_name());
+                            hooks.lost.iter().for_each(|hook| hook(&watcher));
+                        }
+                        ConnectionEvent::Reconnected => {
+                            if let Err(err) = apply_overrides_on(&watcher, &rules) {
+                                log::error!("Error applying app id overrides: {}", err);
+                            }
+                            log::info!("Restored Gamescope instance {}", watcher.get_name());
+                            hooks.restored.iter().for_each(|hook| hook(&watcher));
+                        }
+                    }
+                }
+            });
+            handles.push(handle);
+        }
+
+        Ok(handles)
+    }
+
+    /// Returns the configured watches
+    pub fn get_watches(&self) -> &[WatchSpec] {
+        self.watches.as_slice()
+    }
+
+    /// Replaces all configured watches (E.g. with ones loaded from disk)
+    pub fn set_watches(&mut self, watches: Vec<WatchSpec>) {
+        self.watches = watches;
+    }
+
+    /// Adds the given watch, replacing any watch with the same name
+    pub fn add_watch(&mut self, spec: WatchSpec) {
+        self.remove_watch(&spec.name);
+        self.watches.push(spec);
+    }
+
+    /// Removes the watch with the given name
+    pub fn remove_watch(&mut self, name: &str) -> Option<WatchSpec> {
+        let index = self.watches.iter().position(|spec| spec.name == name)?;
+        Some(self.watches.remove(index))
+    }
+
+    /// Establishes all configured watches on the instances they select.
+    /// Watches of the root window stay subscribed when a connection is
+    /// re-established, and watches of other windows are established again
+    /// for the windows selected after reconnecting. Returns the join handles
+    /// of the watching threads and a receiver channel that receives every
+    /// matching property change.
+    pub fn start_watches(&self) -> Result<WatchListener, Box<dyn std::error::Error>> {
+        let (tx, rx) = mpsc::channel();
+
+        let mut handles: Vec<JoinHandle<()>> = Vec::new();
+        for xwayland in self.xwaylands.iter() {
+            let mut specs: Vec<WatchSpec> = Vec::new();
+            for spec in self.watches.iter() {
+                if spec.instance.matches(xwayland)? {
+                    handles.extend(watch::establish(xwayland, spec, &tx)?);
+                    specs.push(spec.clone());
+                }
+            }
+
+            specs.retain(|spec| spec.window != WindowSelector::Root);
+            if specs.is_empty() {
+                continue;
+            }
+            let events = xwayland.listen_for_connection_events()?;
+            let watcher = xwayland.clone();
+            let tx = tx.clone();
+            let options = xwayland.get_connect_options();
+            let handle = worker::spawn(&xwayland.get_name(), "watches", &options, move || {
+                for event in events {
+                    if event != ConnectionEvent::Reconnected {
+                        continue;
+                    }
+                    for spec in specs.iter() {
+                        if let Err(err) = watch::establish(&watcher, spec, &tx) {
+                            log::error!("Error establishing watch {}: {}", spec.name, err);
+                        }
+                    }
+                }
+            });
+            handles.push(handle);
+        }
+
+        Ok((handles, rx))
+    }
+
+    /// Returns the configured app id override rules
+    pub fn get_app_id_overrides(&self) -> &[AppIdOverride] {
+        self.app_id_overrides.as_slice()
+    }
+
+    /// Replaces all app id override rules (E.g. with rules loaded from disk)
+    pub fn set_app_id_overrides(&mut self, rules: Vec<AppIdOverride>) {
+        self.app_id_overrides = rules;
+    }
+
+    /// Replaces all app id override rules with the ones stored in the given
+    /// file (See [overrides::load_app_id_overrides]). Returns the number of
+    /// loaded rules.
+    pub fn load_app_id_overrides(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        self.app_id_overrides = overrides::load_app_id_overrides(path)?;
+        Ok(self.app_id_overrides.len())
+    }
+
+    /// Stores the configured app id override rules in the given file, so they
+    /// can be restored with [GamescopeManager::load_app_id_overrides]
+    pub fn save_app_id_overrides(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        overrides::save_app_id_overrides(path, &self.app_id_overrides)
+    }
+
+    /// Adds an app id override rule. Rules are evaluated in the order they
+    /// were added and the first matching rule wins.
+    pub fn add_app_id_override(&mut self, rule: AppIdOverride) {
+        self.app_id_overrides.push(rule);
+    }
+
+    /// Sets the app id on all existing top-level windows that match one of the
+    /// configured override rules. Returns the number of windows that were
+    /// tagged.
+    pub fn apply_overrides(&self) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut count = 0;
+        for xwayland in self.xwaylands.iter() {
+            count += apply_overrides_on(xwayland, &self.app_id_overrides)?;
+        }
+
+        Ok(count)
+    }
+
+    /// Applies the configured override rules to existing windows and then to
+    /// every top-level window as it is mapped. Returns the join handles of the
+    /// threads watching each instance.
+    pub fn watch_overrides(&self) -> Result<Vec<JoinHandle<()>>, Box<dyn std::error::Error>> {
+        self.apply_overrides()?;
+
+        let mut handles: Vec<JoinHandle<()>> = Vec::new();
+        for xwayland in self.xwaylands.iter() {
+            let root = xwayland.get_root_window_id()?;
+            let (_, rx) = xwayland.listen_for_window_changes(
+                root,
+                EventMask::SUBSTRUCTURE_NOTIFY,
+                |_, tx, event| {
+                    if let x11rb::protocol::Event::MapNotify(event) = event {
+                        tx.send(event.window)?;
+                    }
+
+                    Ok(())
+                },
+            )?;
+
+            let watcher = xwayland.clone();
+            let rules = self.app_id_overrides.clone();
+            let options = xwayland.get_connect_options();
+            let handle = worker::spawn(&xwayland.get_name(), "overrides", &options, move || {
+                for window_id in rx {
+                    if let Err(err) = overrides::apply_app_id_overrides(&watcher, &rules, window_id)
+                    {
+                        log::error!("Error applying app id overrides: {}", err);
+                    }
+                }
+            });
+            handles.push(handle);
+        }
+
+        Ok(handles)
+    }
+
+    /// Applies the matching profile from the given store whenever the focused
+    /// app (GAMESCOPE_FOCUSED_APP) on the primary instance changes, and
+    /// restores the session defaults when the game loses focus or exits.
+    /// Returns the join handle of the watching thread.
+    pub fn watch_profiles(
+        &self,
+        store: ProfileStore,
+    ) -> Result<JoinHandle<()>, Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?.clone();
+        let (_, rx) = primary.listen_for_atom_changes(&[GamescopeAtom::FocusedApp])?;
+
+        let mut applier = ProfileApplier::new(store);
+        applier.on_focused_app_changed(&primary, primary.get_focused_app()?)?;
+
+        let options = primary.get_connect_options();
+        let handle = worker::spawn(&primary.get_name(), "profiles", &options, move || {
+            for _ in rx {
+                let result = primary
+                    .get_focused_app()
+                    .and_then(|app_id| applier.on_focused_app_changed(&primary, app_id));
+                if let Err(err) = result {
+                    log::error!("Error applying game profile: {}", err);
+                }
+            }
+            if let Err(err) = applier.restore(&primary) {
+                log::error!("Error restoring session defaults: {}", err);
+            }
+        });
+
+        Ok(handle)
+    }
+
+    /// Focuses the app with the given app id on the primary instance by
+    /// setting the baselayer app id. The previously focused app is remembered
+    /// so it can be returned to with [GamescopeManager::focus_previous].
+    pub fn focus_app(&mut self, app_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?;
+        let current = primary.get_baselayer_app_id()?;
+        primary.set_baselayer_app_id(app_id)?;
+
+        if let Some(current) = current.filter(|current| *current != app_id) {
+            self.focus_history.push(current);
+            if self.focus_history.len() > FOCUS_HISTORY_LIMIT {
+                self.focus_history.remove(0);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Focuses the app that was focused before the last call to
+    /// [GamescopeManager::focus_app]. Returns the app id that was focused, or
+    /// None if there is no history to go back to.
+    pub fn focus_previous(&mut self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let primary = self.require_primary()?;
+        let current = primary.get_baselayer_app_id()?;
+
+        // Skip entries that are already focused
+        while let Some(app_id) = self.focus_history.pop() {
+            if Some(app_id) == current {
+                continue;
+            }
+            self.require_primary()?.set_baselayer_app_id(app_id)?;
+            return Ok(Some(app_id));
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the baselayer focus history, with the most recent entry last
+    pub fn get_focus_history(&self) -> &[u32] {
+        self.focus_history.as_slice()
+    }
+
+    /// Forgets all previously focused apps
+    pub fn clear_focus_history(&mut self) {
+        self.focus_history.clear();
+    }
+
+    /// Returns the primary Gamescope XWayland instance or an error if none
+    /// is managed
+    fn require_primary(&self) -> Result<&XWayland, Box<dyn std::error::Error>> {
+        self.get_primary()?
+            .ok_or_else(|| "No primary Gamescope XWayland instance found".into())
+    }
+}
+
+impl Drop for GamescopeManager {
+    fn drop(&mut self) {
+        if !self.has_defaults() {
+            return;
+        }
+        match self.restore_defaults() {
+            Ok(count) => log::debug!("Restored {} session defaults", count),
+            Err(err) => log::warn!("Unable to restore session defaults: {}", err),
+        }
+    }
+}
+
+/// Applies the given app id override rules to all top-level windows of the
+/// given instance. Returns the number of windows that were changed.
+fn apply_overrides_on(
+    xwayland: &XWayland,
+    rules: &[AppIdOverride],
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut count = 0;
+    let root = xwayland.get_root_window_id()?;
+    for window_id in xwayland.get_window_children(root)? {
+        if overrides::apply_app_id_overrides(xwayland, rules, window_id)?.is_some() {
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
+
+/// Returns the display number of the given display name (E.g. 1 for ":1.0")
+fn display_number(name: &str) -> Option<u32> {
+    let (_, display) = name.rsplit_once(':')?;
+    let display = display.split('.').next()?;
+    display.parse().ok()
+}
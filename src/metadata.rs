@@ -0,0 +1,92 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+
+/// [Metadata] stores at most one value of each type, so independent callers
+/// can attach their own information to an [XWayland](crate::xwayland::XWayland)
+/// instance (E.g. the app id it hosts) by defining their own types.
+#[derive(Default)]
+pub struct Metadata {
+    values: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl fmt::Debug for Metadata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Metadata")
+            .field("len", &self.values.len())
+            .finish()
+    }
+}
+
+impl Metadata {
+    /// Create an empty metadata store
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the given value, returning the previous value of the same type
+    pub fn insert<T: Any + Send + Sync>(&mut self, value: T) -> Option<T> {
+        let previous = self.values.insert(TypeId::of::<T>(), Box::new(value))?;
+        previous.downcast().ok().map(|value| *value)
+    }
+
+    /// Returns the stored value of the given type
+    pub fn get<T: Any + Send + Sync>(&self) -> Option<&T> {
+        self.values.get(&TypeId::of::<T>())?.downcast_ref()
+    }
+
+    /// Returns the stored value of the given type for modification
+    pub fn get_mut<T: Any + Send + Sync>(&mut self) -> Option<&mut T> {
+        self.values.get_mut(&TypeId::of::<T>())?.downcast_mut()
+    }
+
+    /// Returns true if a value of the given type is stored
+    pub fn contains<T: Any + Send + Sync>(&self) -> bool {
+        self.values.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Removes and returns the stored value of the given type
+    pub fn remove<T: Any + Send + Sync>(&mut self) -> Option<T> {
+        let value = self.values.remove(&TypeId::of::<T>())?;
+        value.downcast().ok().map(|value| *value)
+    }
+
+    /// Returns the number of stored values
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if no values are stored
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Removes all stored values
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct HostedApp(u32);
+
+    #[test]
+    fn test_metadata_by_type() {
+        let mut metadata = Metadata::new();
+        assert_eq!(metadata.insert(HostedApp(620)), None);
+        assert_eq!(metadata.insert("game"), None);
+        assert_eq!(metadata.insert(HostedApp(769)), Some(HostedApp(620)));
+
+        assert_eq!(metadata.get::<HostedApp>(), Some(&HostedApp(769)));
+        assert_eq!(metadata.get::<&str>(), Some(&"game"));
+        assert!(!metadata.contains::<u32>());
+
+        metadata.get_mut::<HostedApp>().unwrap().0 = 620;
+        assert_eq!(metadata.remove::<HostedApp>(), Some(HostedApp(620)));
+        assert_eq!(metadata.len(), 1);
+    }
+}
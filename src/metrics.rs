@@ -0,0 +1,218 @@
+use std::fmt::Write;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{
+    callback::{self, CallbackHandle},
+    xwayland::{Primary, XWayland},
+};
+
+/// Values sampled from an XWayland instance by a [MetricsRegistry]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MetricsSample {
+    /// The FPS limit (GAMESCOPE_FPS_LIMIT)
+    pub fps_limit: Option<u32>,
+    /// The refresh rate of the display (GAMESCOPE_DISPLAY_REFRESH_RATE_FEEDBACK)
+    pub refresh_rate: Option<u32>,
+    /// The app id of the focused app (GAMESCOPE_FOCUSED_APP)
+    pub focused_app: Option<u32>,
+    /// How much the input counter (GAMESCOPE_INPUT_COUNTER) increased since
+    /// the previous sample
+    pub input_events: u32,
+}
+
+#[derive(Debug, Default)]
+struct RegistryState {
+    display: String,
+    latest: Option<MetricsSample>,
+    samples: u64,
+    input_events_total: u64,
+    last_input_counter: Option<u32>,
+}
+
+/// [MetricsRegistry] periodically samples the FPS limit, refresh rate, focused
+/// app and input activity of an XWayland instance, so system monitors can
+/// graph Gamescope behavior. The latest values can be pulled from the registry
+/// (E.g. in the Prometheus text format with [MetricsRegistry::render]) or
+/// pushed to a callback. Clones share the same values.
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRegistry {
+    state: Arc<Mutex<RegistryState>>,
+}
+
+impl MetricsRegistry {
+    /// Create a new, empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Samples the given instance every interval into this registry until the
+    /// returned handle is unregistered
+    pub fn start(&self, xwayland: &XWayland, interval: Duration) -> CallbackHandle {
+        self.start_with_callback(xwayland, interval, |_| ())
+    }
+
+    /// Samples the given instance every interval into this registry and calls
+    /// the given callback with each sample until the returned handle is
+    /// unregistered
+    pub fn start_with_callback<F>(
+        &self,
+        xwayland: &XWayland,
+        interval: Duration,
+        mut callback: F,
+    ) -> CallbackHandle
+    where
+        F: FnMut(&MetricsSample) + Send + 'static,
+    {
+        self.lock().display = xwayland.get_name();
+        let registry = self.clone();
+        let source = xwayland.clone();
+        let options = xwayland.get_connect_options();
+        let name = xwayland.get_name();
+        callback::spawn_periodic_callback(&name, "metrics", &options, interval, move || {
+            let sample = match read_sample(&source) {
+                Ok(sample) => sample,
+                Err(err) => {
+                    log::error!("Error sampling metrics: {}", err);
+                    return;
+                }
+            };
+            let (fps_limit, refresh_rate, focused_app, input_counter) = sample;
+            let sample = registry.record(fps_limit, refresh_rate, focused_app, input_counter);
+            callback(&sample);
+        })
+    }
+
+    /// Returns the latest sample
+    pub fn latest(&self) -> Option<MetricsSample> {
+        self.lock().latest
+    }
+
+    /// Returns the number of samples taken
+    pub fn samples(&self) -> u64 {
+        self.lock().samples
+    }
+
+    /// Returns how much the input counter increased over all samples
+    pub fn input_events_total(&self) -> u64 {
+        self.lock().input_events_total
+    }
+
+    /// Returns the latest values in the Prometheus text exposition format.
+    /// Values that are not set are left out.
+    pub fn render(&self) -> String {
+        let state = self.lock();
+        let mut output = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: Option<u64>| {
+            let Some(value) = value else {
+                return;
+            };
+            let _ = writeln!(output, "# HELP {} {}", name, help);
+            let _ = writeln!(output, "# TYPE {} {}", name, kind);
+            let _ = writeln!(
+                output,
+                "{}{{display=\"{}\"}} {}",
+                name, state.display, value
+            );
+        };
+
+        let latest = state.latest.unwrap_or_default();
+        let fps_limit = latest.fps_limit.map(u64::from);
+        let refresh_rate = latest.refresh_rate.map(u64::from);
+        let focused_app = latest.focused_app.map(u64::from);
+        metric("gamescope_fps_limit", "gauge", "FPS limit", fps_limit);
+        metric(
+            "gamescope_refresh_rate_hertz",
+            "gauge",
+            "Refresh rate of the display",
+            refresh_rate,
+        );
+        metric(
+            "gamescope_focused_app_id",
+            "gauge",
+            "App id of the focused app",
+            focused_app,
+        );
+        metric(
+            "gamescope_input_events_total",
+            "counter",
+            "Increase of the input counter",
+            Some(state.input_events_total),
+        );
+
+        output
+    }
+
+    /// Records the given sampled values and returns the resulting sample
+    fn record(
+        &self,
+        fps_limit: Option<u32>,
+        refresh_rate: Option<u32>,
+        focused_app: Option<u32>,
+        input_counter: Option<u32>,
+    ) -> MetricsSample {
+        let mut state = self.lock();
+
+        // The counter wraps around, and the first sample has nothing to
+        // compare against
+        let input_events = match (state.last_input_counter, input_counter) {
+            (Some(last), Some(current)) => current.wrapping_sub(last),
+            _ => 0,
+        };
+        let sample = MetricsSample {
+            fps_limit,
+            refresh_rate,
+            focused_app,
+            input_events,
+        };
+        state.last_input_counter = input_counter;
+        state.latest = Some(sample);
+        state.samples += 1;
+        state.input_events_total += u64::from(input_events);
+
+        sample
+    }
+
+    fn lock(&self) -> MutexGuard<'_, RegistryState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Sampled values of (FPS limit, refresh rate, focused app, input counter)
+type RawSample = (Option<u32>, Option<u32>, Option<u32>, Option<u32>);
+
+/// Reads the sampled values from the given instance
+fn read_sample(xwayland: &XWayland) -> Result<RawSample, Box<dyn std::error::Error>> {
+    Ok((
+        xwayland.get_fps_limit()?,
+        xwayland.get_display_refresh_rate()?,
+        xwayland.get_focused_app()?,
+        xwayland.get_input_counter()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_registry() {
+        let registry = MetricsRegistry::new();
+        registry.lock().display = ":1".into();
+
+        let sample = registry.record(Some(60), Some(90), Some(620), Some(u32::MAX - 1));
+        assert_eq!(sample.input_events, 0);
+        let sample = registry.record(Some(30), Some(90), None, Some(3));
+        assert_eq!(sample.input_events, 5);
+        assert_eq!(registry.samples(), 2);
+        assert_eq!(registry.input_events_total(), 5);
+
+        let output = registry.render();
+        assert!(output.contains("gamescope_fps_limit{display=\":1\"} 30\n"));
+        assert!(output.contains("gamescope_input_events_total{display=\":1\"} 5\n"));
+        assert!(!output.contains("gamescope_focused_app_id"));
+    }
+}
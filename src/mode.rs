@@ -0,0 +1,96 @@
+/// Layouts of the GAMESCOPE_XWAYLAND_MODE_CONTROL payload used by different
+/// Gamescope versions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ModeControlLayout {
+    /// [server, width, height] used by older Gamescope versions
+    Legacy,
+    /// [server, width, height, allow super resolution]
+    #[default]
+    Current,
+}
+
+impl ModeControlLayout {
+    /// Returns the number of values in a payload of this layout
+    pub fn value_count(&self) -> usize {
+        match self {
+            ModeControlLayout::Legacy => 3,
+            ModeControlLayout::Current => 4,
+        }
+    }
+
+    /// Returns the layout of a payload with the given number of values
+    pub fn from_value_count(count: usize) -> Option<Self> {
+        match count {
+            3 => Some(ModeControlLayout::Legacy),
+            4 => Some(ModeControlLayout::Current),
+            _ => None,
+        }
+    }
+}
+
+/// Mode of an XWayland server requested through
+/// GAMESCOPE_XWAYLAND_MODE_CONTROL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModeControl {
+    /// Index of the XWayland server to change the mode of, where 0 is the
+    /// primary instance
+    pub server_id: u32,
+    /// Width of the mode in pixels
+    pub width: u32,
+    /// Height of the mode in pixels
+    pub height: u32,
+    /// Allow the mode to be larger than the output (super resolution). Ignored
+    /// by Gamescope versions using [ModeControlLayout::Legacy].
+    pub allow_super_res: bool,
+}
+
+impl ModeControl {
+    /// Encodes the mode into the CARDINAL values of the given layout
+    pub fn encode(&self, layout: ModeControlLayout) -> Vec<u32> {
+        let mut values = vec![self.server_id, self.width, self.height];
+        if layout == ModeControlLayout::Current {
+            values.push(self.allow_super_res as u32);
+        }
+        values
+    }
+
+    /// Decodes the mode from the CARDINAL values of the property. The layout
+    /// is detected from the number of values. Returns None if the values don't
+    /// match any known layout.
+    pub fn decode(values: &[u32]) -> Option<Self> {
+        let layout = ModeControlLayout::from_value_count(values.len())?;
+        Some(Self {
+            server_id: values[0],
+            width: values[1],
+            height: values[2],
+            allow_super_res: layout == ModeControlLayout::Current && values[3] != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_control_layouts() {
+        let mode = ModeControl {
+            server_id: 1,
+            width: 1280,
+            height: 800,
+            allow_super_res: true,
+        };
+        let current = mode.encode(ModeControlLayout::Current);
+        assert_eq!(current, vec![1, 1280, 800, 1]);
+        assert_eq!(ModeControl::decode(&current), Some(mode));
+
+        let legacy = mode.encode(ModeControlLayout::Legacy);
+        assert_eq!(legacy, vec![1, 1280, 800]);
+        let decoded = ModeControl::decode(&legacy).unwrap();
+        assert!(!decoded.allow_super_res);
+
+        assert_eq!(ModeControl::decode(&[1, 2]), None);
+    }
+}
@@ -0,0 +1,262 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{
+    control::GamescopeControl,
+    journal::{escape, unescape},
+};
+
+/// [AppIdOverride] is a rule that assigns an app id (STEAM_GAME) to every
+/// window that matches all of its patterns. Patterns support the `*` and `?`
+/// wildcards. A rule without any patterns never matches.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppIdOverride {
+    /// The app id to set on matching windows
+    pub app_id: u32,
+    /// Pattern matched against either the instance or class name in WM_CLASS
+    pub class: Option<String>,
+    /// Pattern matched against the window name (WM_NAME)
+    pub name: Option<String>,
+    /// Process ID of the client that owns the window, as found with XRes (see
+    /// [GamescopeControl::get_pids_for_window])
+    pub pid: Option<u32>,
+}
+
+impl AppIdOverride {
+    /// Create a new override rule for the given app id without any patterns
+    pub fn new(app_id: u32) -> Self {
+        Self {
+            app_id,
+            ..Default::default()
+        }
+    }
+
+    /// Match windows whose WM_CLASS instance or class matches the given pattern
+    pub fn with_class(mut self, pattern: &str) -> Self {
+        self.class = Some(pattern.to_string());
+        self
+    }
+
+    /// Match windows whose name matches the given pattern
+    pub fn with_name(mut self, pattern: &str) -> Self {
+        self.name = Some(pattern.to_string());
+        self
+    }
+
+    /// Match windows owned by the given process ID
+    pub fn with_pid(mut self, pid: u32) -> Self {
+        self.pid = Some(pid);
+        self
+    }
+
+    /// Returns true if the given window on the given Gamescope instance matches
+    /// all patterns of this rule.
+    pub fn matches(
+        &self,
+        xwayland: &dyn GamescopeControl,
+        window_id: u32,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.class.is_none() && self.name.is_none() && self.pid.is_none() {
+            return Ok(false);
+        }
+
+        if let Some(pid) = self.pid {
+            if !xwayland.get_pids_for_window(window_id)?.contains(&pid) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(pattern) = self.class.as_ref() {
+            let Some((instance, class)) = xwayland.get_window_class(window_id)? else {
+                return Ok(false);
+            };
+            if !glob_match(pattern, &instance) && !glob_match(pattern, &class) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(pattern) = self.name.as_ref() {
+            let Some(name) = xwayland.get_window_name(window_id)? else {
+                return Ok(false);
+            };
+            if !glob_match(pattern, &name) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Sets the app id of the first matching rule on the given window. Returns the
+/// app id that was set, or None if no rule matched or the window already had
+/// the matching app id.
+pub fn apply_app_id_overrides(
+    xwayland: &dyn GamescopeControl,
+    rules: &[AppIdOverride],
+    window_id: u32,
+) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+    for rule in rules {
+        if !rule.matches(xwayland, window_id)? {
+            continue;
+        }
+        if xwayland.get_app_id(window_id)? == Some(rule.app_id) {
+            return Ok(None);
+        }
+        log::debug!(
+            "Setting app id {} on window {} on {}",
+            rule.app_id,
+            window_id,
+            xwayland.get_name()
+        );
+        xwayland.set_app_id(window_id, rule.app_id)?;
+        return Ok(Some(rule.app_id));
+    }
+
+    Ok(None)
+}
+
+/// Reads the override rules stored in the given file with
+/// [save_app_id_overrides], one rule per line. Returns no rules if the file
+/// does not exist. Invalid lines are skipped.
+pub fn load_app_id_overrides(
+    path: impl AsRef<Path>,
+) -> Result<Vec<AppIdOverride>, Box<dyn std::error::Error>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut rules: Vec<AppIdOverride> = Vec::new();
+    for line in contents.lines().filter(|line| !line.is_empty()) {
+        match decode_rule(line) {
+            Some(rule) => rules.push(rule),
+            None => log::warn!("Ignoring invalid app id override: {}", line),
+        }
+    }
+
+    Ok(rules)
+}
+
+/// Writes the given override rules to the given file, replacing its contents
+pub fn save_app_id_overrides(
+    path: impl AsRef<Path>,
+    rules: &[AppIdOverride],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let lines: Vec<String> = rules.iter().map(encode_rule).collect();
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/// Encodes the given rule as a single tab separated line. Unset patterns are
+/// written as "-" and set ones with an "s:" prefix, so "-" stays a valid
+/// pattern.
+fn encode_rule(rule: &AppIdOverride) -> String {
+    let pattern = |pattern: &Option<String>| match pattern {
+        Some(pattern) => format!("s:{}", escape(pattern)),
+        None => "-".to_string(),
+    };
+    let pid = match rule.pid {
+        Some(pid) => pid.to_string(),
+        None => "-".to_string(),
+    };
+
+    format!(
+        "{}\t{}\t{}\t{}",
+        rule.app_id,
+        pattern(&rule.class),
+        pattern(&rule.name),
+        pid
+    )
+}
+
+/// Decodes a rule from a line written by [encode_rule]
+fn decode_rule(line: &str) -> Option<AppIdOverride> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    let [app_id, class, name, pid] = fields.as_slice() else {
+        return None;
+    };
+    let pattern = |value: &str| -> Option<Option<String>> {
+        if value == "-" {
+            return Some(None);
+        }
+        Some(Some(unescape(value.strip_prefix("s:")?)))
+    };
+    let pid = match *pid {
+        "-" => None,
+        pid => Some(pid.parse().ok()?),
+    };
+
+    Some(AppIdOverride {
+        app_id: app_id.parse().ok()?,
+        class: pattern(class)?,
+        name: pattern(name)?,
+        pid,
+    })
+}
+
+/// Returns true if the given text matches the given pattern, where `*` matches
+/// any sequence of characters and `?` matches any single character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last '*' consume one more character and try again
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, t));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("steam_app_*", "steam_app_620"));
+        assert!(glob_match("*Portal*", "Portal 2"));
+        assert!(glob_match("game?", "game1"));
+        assert!(glob_match("*", ""));
+        assert!(!glob_match("steam_app_*", "steam"));
+        assert!(!glob_match("game?", "game12"));
+    }
+
+    #[test]
+    fn test_app_id_override_lines() {
+        let rules = [
+            AppIdOverride::new(769).with_class("steam_app_*"),
+            AppIdOverride::new(0x8000_0001)
+                .with_name("Portal\t2 -")
+                .with_pid(1234),
+            AppIdOverride::new(620).with_class("-"),
+        ];
+        for rule in rules.iter() {
+            assert_eq!(decode_rule(&encode_rule(rule)).as_ref(), Some(rule));
+        }
+
+        assert_eq!(decode_rule("769\tsteam_app_*\t-\t-"), None);
+        assert_eq!(decode_rule("769\t-\t-"), None);
+    }
+}
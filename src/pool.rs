@@ -0,0 +1,124 @@
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+use crate::connection::{SharedConnection, X11Connection};
+
+#[derive(Debug, Default)]
+struct State {
+    /// Connections that are not borrowed right now
+    idle: Vec<Arc<X11Connection>>,
+    /// The shared connection the pool was filled for. The pool is refilled
+    /// when it was replaced by a reconnect.
+    generation: Option<Arc<X11Connection>>,
+}
+
+/// [ConnectionPool] holds a small number of extra connections to a display
+/// that parallel read-only operations can borrow, so they don't all serialize
+/// on the lock of the shared connection. When every pooled connection is
+/// borrowed, the shared connection is used instead.
+#[derive(Debug)]
+pub(crate) struct ConnectionPool {
+    conn: SharedConnection,
+    size: usize,
+    state: Mutex<State>,
+}
+
+impl ConnectionPool {
+    /// Create a pool of the given size for the given display. Connections are
+    /// opened the first time they are needed.
+    pub fn new(conn: SharedConnection, size: usize) -> Self {
+        Self {
+            conn,
+            size,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Returns the number of extra connections in the pool
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Borrow a connection from the pool. The connection is returned to the
+    /// pool when the borrow is dropped.
+    pub fn get(&self) -> PooledConnection<'_> {
+        let conn = self.take_idle();
+        match conn {
+            Some(conn) => PooledConnection {
+                pool: Some(self),
+                conn,
+            },
+            None => PooledConnection {
+                pool: None,
+                conn: self.conn.get(),
+            },
+        }
+    }
+
+    /// Returns an idle connection, filling the pool first if it is empty or
+    /// the shared connection was replaced since it was filled
+    fn take_idle(&self) -> Option<Arc<X11Connection>> {
+        let mut state = self.state.lock().ok()?;
+        let current = self.conn.get();
+        let stale = state
+            .generation
+            .as_ref()
+            .map(|generation| !Arc::ptr_eq(generation, &current))
+            .unwrap_or(true);
+        if stale {
+            state.idle.clear();
+            for _ in 0..self.size {
+                match self.conn.open_extra() {
+                    Ok(conn) => state.idle.push(conn),
+                    Err(err) => {
+                        log::warn!("Unable to open pooled connection: {}", err);
+                        break;
+                    }
+                }
+            }
+            state.generation = Some(current);
+        }
+
+        state.idle.pop()
+    }
+
+    /// Return a borrowed connection to the pool
+    fn put(&self, conn: Arc<X11Connection>) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        // Connections opened before a reconnect are dropped
+        let current = state
+            .generation
+            .as_ref()
+            .is_some_and(|generation| Arc::ptr_eq(generation, &self.conn.get()));
+        if current {
+            state.idle.push(conn);
+        }
+    }
+}
+
+/// A connection borrowed from a [ConnectionPool]
+#[derive(Debug)]
+pub(crate) struct PooledConnection<'a> {
+    /// The pool to return the connection to, or None if this is the shared
+    /// connection
+    pool: Option<&'a ConnectionPool>,
+    conn: Arc<X11Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = X11Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.conn.as_ref()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(pool) = self.pool {
+            pool.put(self.conn.clone());
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use crate::{
+    atoms::GamescopeAtom,
+    control::GamescopeControl,
+    mode::ModeControl,
+    xwayland::{BlurMode, Primary, ScalingFilter},
+};
+
+/// Gamescope settings that can be changed per game. Settings that are None
+/// are left as they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GamescopeSettings {
+    /// Frame rate limit
+    pub fps_limit: Option<u32>,
+    /// Blur mode used behind overlays like the Steam UI
+    pub blur_mode: Option<BlurMode>,
+    /// Blur radius when blur is active
+    pub blur_radius: Option<u32>,
+    /// Allow tearing
+    pub allow_tearing: Option<bool>,
+    /// Scaler and filter used to upscale the game
+    pub scaling_filter: Option<ScalingFilter>,
+    /// FSR sharpness from 0 (sharpest) to 20
+    pub fsr_sharpness: Option<u32>,
+    /// Use variable refresh rate if the display supports it
+    pub vrr_enabled: Option<bool>,
+    /// Output HDR if the display supports it
+    pub hdr_enabled: Option<bool>,
+    /// Force all windows to be fullscreen
+    pub force_windows_fullscreen: Option<bool>,
+    /// Properties that were not set when these settings were captured with
+    /// [GamescopeSettings::capture]. Applying the settings removes them, so
+    /// Gamescope goes back to what it was started with (E.g.
+    /// `--adaptive-sync`).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub unset: Vec<GamescopeAtom>,
+}
+
+impl GamescopeSettings {
+    /// Returns settings with every setting set to its default, so
+    /// [GamescopeSettings::capture] captures all of them
+    pub fn all() -> Self {
+        Self {
+            fps_limit: Some(0),
+            blur_mode: Some(BlurMode::Off),
+            blur_radius: Some(0),
+            allow_tearing: Some(false),
+            scaling_filter: Some(ScalingFilter::default()),
+            fsr_sharpness: Some(0),
+            vrr_enabled: Some(false),
+            hdr_enabled: Some(false),
+            force_windows_fullscreen: Some(false),
+            unset: Vec::new(),
+        }
+    }
+
+    /// Returns the property of every setting that is set in these settings
+    fn properties(&self) -> Vec<GamescopeAtom> {
+        let settings = [
+            (self.fps_limit.is_some(), GamescopeAtom::FPSLimit),
+            (self.blur_mode.is_some(), GamescopeAtom::BlurMode),
+            (self.blur_radius.is_some(), GamescopeAtom::BlurRadius),
+            (self.allow_tearing.is_some(), GamescopeAtom::AllowTearing),
+            (self.scaling_filter.is_some(), GamescopeAtom::ScalingFilter),
+            (self.fsr_sharpness.is_some(), GamescopeAtom::FSRSharpness),
+            (self.vrr_enabled.is_some(), GamescopeAtom::VRREnabled),
+            (self.hdr_enabled.is_some(), GamescopeAtom::DisplayHDREnabled),
+            (
+                self.force_windows_fullscreen.is_some(),
+                GamescopeAtom::ForceWindowsFullscreen,
+            ),
+        ];
+        settings
+            .into_iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, property)| property)
+            .collect()
+    }
+
+    /// Applies every setting that is set to the given primary instance and
+    /// removes the [GamescopeSettings::unset] properties
+    pub fn apply<P>(&self, primary: &P) -> Result<(), Box<dyn std::error::Error>>
+    where
+        P: GamescopeControl + ?Sized,
+    {
+        if !self.unset.is_empty() {
+            let root = primary.get_root_window_id()?;
+            for property in self.unset.iter() {
+                primary.remove_xprop(root, *property)?;
+            }
+        }
+        if let Some(fps) = self.fps_limit {
+            primary.set_fps_limit(fps)?;
+        }
+        if let Some(mode) = self.blur_mode {
+            primary.set_blur_mode(mode)?;
+        }
+        if let Some(radius) = self.blur_radius {
+            primary.set_blur_radius(radius)?;
+        }
+        if let Some(allow) = self.allow_tearing {
+            primary.set_allow_tearing(allow)?;
+        }
+        if let Some(filter) = self.scaling_filter {
+            primary.set_scaling_filter(filter)?;
+        }
+        if let Some(sharpness) = self.fsr_sharpness {
+            primary.set_fsr_sharpness(sharpness)?;
+        }
+        if let Some(enabled) = self.vrr_enabled {
+            primary.set_vrr_enabled(enabled)?;
+        }
+        if let Some(enabled) = self.hdr_enabled {
+            primary.set_hdr_enabled(enabled)?;
+        }
+        if let Some(force) = self.force_windows_fullscreen {
+            primary.set_force_windows_fullscreen(force)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the current values on the given primary instance of every
+    /// setting that is set in these settings, so they can be restored after
+    /// applying them. Settings whose property is not set are left None and
+    /// listed in [GamescopeSettings::unset] instead.
+    pub fn capture<P>(&self, primary: &P) -> Result<GamescopeSettings, Box<dyn std::error::Error>>
+    where
+        P: GamescopeControl + ?Sized,
+    {
+        let root = primary.get_root_window_id()?;
+        let mut current = GamescopeSettings::default();
+        for property in self.properties() {
+            if !primary.has_xprop(root, property)? {
+                current.unset.push(property);
+                continue;
+            }
+            match property {
+                GamescopeAtom::FPSLimit => current.fps_limit = primary.get_fps_limit()?,
+                GamescopeAtom::BlurMode => current.blur_mode = primary.get_blur_mode()?,
+                GamescopeAtom::BlurRadius => current.blur_radius = primary.get_blur_radius()?,
+                GamescopeAtom::AllowTearing => {
+                    current.allow_tearing = Some(primary.get_allow_tearing()?)
+                }
+                GamescopeAtom::ScalingFilter => {
+                    current.scaling_filter = primary.get_scaling_filter()?
+                }
+                GamescopeAtom::FSRSharpness => {
+                    current.fsr_sharpness = primary.get_fsr_sharpness()?
+                }
+                GamescopeAtom::VRREnabled => {
+                    current.vrr_enabled = Some(primary.get_vrr_state()?.enabled)
+                }
+                GamescopeAtom::DisplayHDREnabled => {
+                    current.hdr_enabled = Some(primary.get_hdr_status()?.enabled)
+                }
+                GamescopeAtom::ForceWindowsFullscreen => {
+                    current.force_windows_fullscreen = Some(primary.get_force_windows_fullscreen()?)
+                }
+                _ => (),
+            }
+        }
+
+        Ok(current)
+    }
+}
+
+/// Settings applied while the game with the given app id is focused
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameProfile {
+    /// The app id (STEAM_GAME) of the game
+    pub app_id: u32,
+    /// Settings to apply while the game is focused
+    pub settings: GamescopeSettings,
+    /// Mode to request for the XWayland server while the game is focused
+    pub mode: Option<ModeControl>,
+}
+
+/// [ProfileStore] holds the [GameProfile] of every configured game. With the
+/// `serde` feature it can be loaded from and saved to any serde format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfileStore {
+    profiles: Vec<GameProfile>,
+}
+
+impl ProfileStore {
+    /// Create a store from the given profiles
+    pub fn new(profiles: Vec<GameProfile>) -> Self {
+        Self { profiles }
+    }
+
+    /// Returns all profiles
+    pub fn profiles(&self) -> &[GameProfile] {
+        self.profiles.as_slice()
+    }
+
+    /// Returns the profile of the given app id
+    pub fn get(&self, app_id: u32) -> Option<&GameProfile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.app_id == app_id)
+    }
+
+    /// Adds the given profile, replacing any existing profile of the same app
+    pub fn insert(&mut self, profile: GameProfile) {
+        self.remove(profile.app_id);
+        self.profiles.push(profile);
+    }
+
+    /// Removes and returns the profile of the given app id
+    pub fn remove(&mut self, app_id: u32) -> Option<GameProfile> {
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.app_id == app_id)?;
+        Some(self.profiles.remove(index))
+    }
+}
+
+/// Session values a profile replaced, restored when the game loses focus
+#[derive(Debug, Clone)]
+struct Applied {
+    app_id: u32,
+    defaults: GamescopeSettings,
+    mode: Option<ModeControl>,
+}
+
+/// [ProfileApplier] applies the profile of the focused game and restores the
+/// session defaults when another app is focused or the game exits.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileApplier {
+    store: ProfileStore,
+    applied: Option<Applied>,
+}
+
+impl ProfileApplier {
+    /// Create an applier for the given profiles
+    pub fn new(store: ProfileStore) -> Self {
+        Self {
+            store,
+            applied: None,
+        }
+    }
+
+    /// Returns the app id whose profile is currently applied
+    pub fn get_applied_app_id(&self) -> Option<u32> {
+        self.applied.as_ref().map(|applied| applied.app_id)
+    }
+
+    /// Handles a change of the focused app (GAMESCOPE_FOCUSED_APP) on the
+    /// given primary instance. None means no app is focused.
+    pub fn on_focused_app_changed(
+        &mut self,
+        primary: &dyn GamescopeControl,
+        app_id: Option<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if app_id.is_some() && app_id == self.get_applied_app_id() {
+            return Ok(());
+        }
+        self.restore(primary)?;
+
+        let Some(profile) = app_id.and_then(|app_id| self.store.get(app_id)) else {
+            return Ok(());
+        };
+        let defaults = profile.settings.capture(primary)?;
+        // Without a requested mode, restore an empty mode for the same server
+        let mode = match profile.mode {
+            Some(mode) => Some(primary.get_mode_control()?.unwrap_or(ModeControl {
+                server_id: mode.server_id,
+                ..Default::default()
+            })),
+            None => None,
+        };
+        self.applied = Some(Applied {
+            app_id: profile.app_id,
+            defaults,
+            mode,
+        });
+
+        profile.settings.apply(primary)?;
+        if let Some(mode) = profile.mode {
+            primary.set_mode_control(mode)?;
+        }
+
+        Ok(())
+    }
+
+    /// Restores the session defaults replaced by the applied profile, if any
+    pub fn restore(
+        &mut self,
+        primary: &dyn GamescopeControl,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(applied) = self.applied.take() else {
+            return Ok(());
+        };
+        applied.defaults.apply(primary)?;
+        if let Some(mode) = applied.mode {
+            primary.set_mode_control(mode)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeXWayland;
+
+    #[test]
+    fn test_profile_applied_and_restored() {
+        let primary = FakeXWayland::new_primary(":0");
+        primary.set_fps_limit(60).unwrap();
+
+        let profile = GameProfile {
+            app_id: 620,
+            settings: GamescopeSettings {
+                fps_limit: Some(30),
+                allow_tearing: Some(true),
+                ..Default::default()
+            },
+            mode: None,
+        };
+        let mut applier = ProfileApplier::new(ProfileStore::new(vec![profile]));
+
+        applier.on_focused_app_changed(&primary, Some(620)).unwrap();
+        assert_eq!(primary.get_fps_limit().unwrap(), Some(30));
+        assert!(primary.get_allow_tearing().unwrap());
+        assert_eq!(applier.get_applied_app_id(), Some(620));
+
+        applier.on_focused_app_changed(&primary, Some(769)).unwrap();
+        assert_eq!(primary.get_fps_limit().unwrap(), Some(60));
+        assert!(!primary.get_allow_tearing().unwrap());
+        assert_eq!(applier.get_applied_app_id(), None);
+    }
+
+    #[test]
+    fn test_profile_restores_every_default() {
+        let primary = FakeXWayland::new_primary(":0");
+        primary.set_vrr_enabled(true).unwrap();
+        let defaults = primary.capture_settings().unwrap();
+        assert_eq!(defaults.vrr_enabled, Some(true));
+        assert!(defaults.unset.contains(&GamescopeAtom::FSRSharpness));
+
+        let profile = GameProfile {
+            app_id: 620,
+            settings: GamescopeSettings {
+                fps_limit: Some(30),
+                blur_mode: Some(BlurMode::Always),
+                blur_radius: Some(10),
+                allow_tearing: Some(true),
+                scaling_filter: Some(ScalingFilter::Nearest),
+                fsr_sharpness: Some(5),
+                vrr_enabled: Some(true),
+                hdr_enabled: Some(true),
+                force_windows_fullscreen: Some(true),
+                ..Default::default()
+            },
+            mode: Some(ModeControl {
+                server_id: 0,
+                width: 1280,
+                height: 720,
+                allow_super_res: false,
+            }),
+        };
+        let mut applier = ProfileApplier::new(ProfileStore::new(vec![profile]));
+
+        applier.on_focused_app_changed(&primary, Some(620)).unwrap();
+        assert_ne!(primary.capture_settings().unwrap(), defaults);
+        applier.on_focused_app_changed(&primary, None).unwrap();
+        assert_eq!(primary.capture_settings().unwrap(), defaults);
+        assert_eq!(primary.get_fsr_sharpness().unwrap(), None);
+        assert!(primary.get_vrr_state().unwrap().enabled);
+        assert_eq!(
+            primary.get_mode_control().unwrap(),
+            Some(ModeControl::default())
+        );
+    }
+
+    #[test]
+    fn test_settings_captured_and_restored() {
+        let primary = FakeXWayland::new_primary(":0");
+        primary.set_fps_limit(60).unwrap();
+        primary.set_blur_mode(BlurMode::Cond).unwrap();
+        let previous = primary.capture_settings().unwrap();
+        assert_eq!(previous.fps_limit, Some(60));
+        assert_eq!(previous.blur_mode, Some(BlurMode::Cond));
+
+        let profile = GamescopeSettings {
+            fps_limit: Some(30),
+            blur_mode: Some(BlurMode::Off),
+            blur_radius: Some(10),
+            ..Default::default()
+        };
+        primary.apply_settings(&profile).unwrap();
+        assert_eq!(primary.get_fps_limit().unwrap(), Some(30));
+        assert_eq!(primary.get_blur_mode().unwrap(), Some(BlurMode::Off));
+        assert_eq!(primary.get_blur_radius().unwrap(), Some(10));
+
+        primary.apply_settings(&previous).unwrap();
+        assert_eq!(primary.capture_settings().unwrap(), previous);
+    }
+}
@@ -0,0 +1,61 @@
+use crate::atoms::GamescopeAtom;
+
+/// [PropertyValue] is the value of an X window property decoded according to
+/// the known type of a [GamescopeAtom], or the type the X server reported
+/// for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropertyValue {
+    /// CARDINAL or INTEGER values, or the numbers of any other 16- or 32-bit
+    /// property (E.g. WM_HINTS). 8- and 16-bit values are widened.
+    Cardinal(Vec<u32>),
+    /// WINDOW values (window ids)
+    Window(Vec<u32>),
+    /// ATOM values resolved to their names
+    Atom(Vec<String>),
+    /// STRING or UTF8_STRING values
+    String(String),
+    /// Values of any other 8-bit property
+    Bytes(Vec<u8>),
+}
+
+/// A change of a property on a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PropertyChange {
+    /// The window the property changed on
+    pub window_id: u32,
+    /// The name of the property (E.g. "GAMESCOPE_FOCUSED_APP")
+    pub property: String,
+    /// The decoded new value, or None if the property was removed
+    pub value: Option<PropertyValue>,
+}
+
+impl PropertyChange {
+    /// Returns the known atom of the changed property, or None if the
+    /// property is not a [GamescopeAtom]
+    pub fn atom(&self) -> Option<GamescopeAtom> {
+        GamescopeAtom::from_property_name(&self.property)
+    }
+}
+
+impl PropertyValue {
+    /// Returns the value as a list of numbers if it is a cardinal or window
+    /// property
+    pub fn as_u32s(&self) -> Option<&[u32]> {
+        match self {
+            PropertyValue::Cardinal(values) | PropertyValue::Window(values) => {
+                Some(values.as_slice())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a string if it is a string property
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(value) => Some(value.as_str()),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::{
+    atoms::GamescopeAtom,
+    property::{PropertyChange, PropertyValue},
+    xwayland::{BlurMode, Primary, ScreenshotType, XWayland},
+};
+
+/// Converts an error of this crate into a Python exception
+fn runtime_error(err: Box<dyn std::error::Error>) -> PyErr {
+    PyRuntimeError::new_err(err.to_string())
+}
+
+/// Returns the [GamescopeAtom] with the given name (E.g. "GAMESCOPE_FPS_LIMIT")
+fn parse_atom(name: &str) -> PyResult<GamescopeAtom> {
+    GamescopeAtom::from_property_name(name)
+        .ok_or_else(|| PyValueError::new_err(format!("Unknown atom: {}", name)))
+}
+
+/// Converts a property value into a list of ints, a string, a list of atom
+/// names or bytes
+fn value_to_py(py: Python<'_>, value: &PropertyValue) -> PyResult<Py<PyAny>> {
+    let value = match value {
+        PropertyValue::Cardinal(values) | PropertyValue::Window(values) => {
+            values.clone().into_pyobject(py)?.into_any()
+        }
+        PropertyValue::Atom(names) => names.clone().into_pyobject(py)?.into_any(),
+        PropertyValue::String(value) => value.clone().into_pyobject(py)?.into_any(),
+        PropertyValue::Bytes(bytes) => PyBytes::new(py, bytes).into_any(),
+    };
+    Ok(value.unbind())
+}
+
+/// Python wrapper of a connected [XWayland]
+#[pyclass(name = "XWayland", module = "gamescope_x11")]
+struct PyXWayland {
+    xwayland: XWayland,
+}
+
+#[pymethods]
+impl PyXWayland {
+    /// Connects to the given XWayland display (E.g. ":1")
+    #[new]
+    fn new(display: &str) -> PyResult<Self> {
+        let xwayland = XWayland::new(display.to_string())
+            .connect()
+            .map_err(runtime_error)?;
+        Ok(Self { xwayland })
+    }
+
+    /// Returns the name of the display
+    fn name(&self) -> String {
+        self.xwayland.get_name()
+    }
+
+    /// Returns true if this is the primary Gamescope XWayland
+    fn is_primary_instance(&self) -> PyResult<bool> {
+        self.xwayland.is_primary_instance().map_err(runtime_error)
+    }
+
+    /// Returns the root window id
+    fn root_window_id(&self) -> PyResult<u32> {
+        self.xwayland.get_root_window_id().map_err(runtime_error)
+    }
+
+    /// Returns the decoded value of the given property on the given window,
+    /// or None if it is not set
+    fn get_xprop(&self, py: Python<'_>, window_id: u32, name: &str) -> PyResult<Option<Py<PyAny>>> {
+        let value = self
+            .xwayland
+            .get_decoded_xprop(window_id, name)
+            .map_err(runtime_error)?;
+        value.map(|value| value_to_py(py, &value)).transpose()
+    }
+
+    /// Sets the given Gamescope property on the given window
+    fn set_xprop(&self, window_id: u32, name: &str, values: Vec<u32>) -> PyResult<()> {
+        let atom = parse_atom(name)?;
+        self.xwayland
+            .set_xprop(window_id, atom, values)
+            .map_err(runtime_error)
+    }
+
+    /// Removes the given Gamescope property from the given window
+    fn remove_xprop(&self, window_id: u32, name: &str) -> PyResult<()> {
+        let atom = parse_atom(name)?;
+        self.xwayland
+            .remove_xprop(window_id, atom)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the name of the given window
+    fn get_window_name(&self, window_id: u32) -> PyResult<Option<String>> {
+        self.xwayland
+            .get_window_name(window_id)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the app id (STEAM_GAME) of the given window
+    fn get_app_id(&self, window_id: u32) -> PyResult<Option<u32>> {
+        self.xwayland.get_app_id(window_id).map_err(runtime_error)
+    }
+
+    /// Sets the app id (STEAM_GAME) of the given window
+    fn set_app_id(&self, window_id: u32, app_id: u32) -> PyResult<()> {
+        self.xwayland
+            .set_app_id(window_id, app_id)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the process IDs of the given window
+    fn get_pids_for_window(&self, window_id: u32) -> PyResult<Vec<u32>> {
+        self.xwayland
+            .get_pids_for_window(window_id)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the windows of the given process ID
+    fn get_windows_for_pid(&self, pid: u32) -> PyResult<Vec<u32>> {
+        self.xwayland
+            .get_windows_for_pid(pid)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the app ids of the focusable apps
+    fn get_focusable_apps(&self) -> PyResult<Option<Vec<u32>>> {
+        self.xwayland.get_focusable_apps().map_err(runtime_error)
+    }
+
+    /// Returns the ids of the focusable windows
+    fn get_focusable_windows(&self) -> PyResult<Option<Vec<u32>>> {
+        self.xwayland.get_focusable_windows().map_err(runtime_error)
+    }
+
+    /// Returns the app id of the focused app
+    fn get_focused_app(&self) -> PyResult<Option<u32>> {
+        self.xwayland.get_focused_app().map_err(runtime_error)
+    }
+
+    /// Returns the id of the focused window
+    fn get_focused_window(&self) -> PyResult<Option<u32>> {
+        self.xwayland.get_focused_window().map_err(runtime_error)
+    }
+
+    /// Focuses the given window
+    fn set_main_app(&self, window_id: u32) -> PyResult<()> {
+        self.xwayland.set_main_app(window_id).map_err(runtime_error)
+    }
+
+    /// Returns the FPS limit
+    fn get_fps_limit(&self) -> PyResult<Option<u32>> {
+        self.xwayland.get_fps_limit().map_err(runtime_error)
+    }
+
+    /// Sets the FPS limit (0 to disable it)
+    fn set_fps_limit(&self, fps: u32) -> PyResult<()> {
+        self.xwayland.set_fps_limit(fps).map_err(runtime_error)
+    }
+
+    /// Sets the blur mode (0 = off, 1 = conditional, 2 = always)
+    fn set_blur_mode(&self, mode: u32) -> PyResult<()> {
+        let mode = match mode {
+            0 => BlurMode::Off,
+            1 => BlurMode::Cond,
+            2 => BlurMode::Always,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid blur mode: {}",
+                    mode
+                )))
+            }
+        };
+        self.xwayland.set_blur_mode(mode).map_err(runtime_error)
+    }
+
+    /// Sets the blur radius in pixels
+    fn set_blur_radius(&self, radius: u32) -> PyResult<()> {
+        self.xwayland.set_blur_radius(radius).map_err(runtime_error)
+    }
+
+    /// Returns the app id of the baselayer app
+    fn get_baselayer_app_id(&self) -> PyResult<Option<u32>> {
+        self.xwayland.get_baselayer_app_id().map_err(runtime_error)
+    }
+
+    /// Sets the app id of the baselayer app
+    fn set_baselayer_app_id(&self, app_id: u32) -> PyResult<()> {
+        self.xwayland
+            .set_baselayer_app_id(app_id)
+            .map_err(runtime_error)
+    }
+
+    /// Removes the baselayer app id
+    fn remove_baselayer_app_id(&self) -> PyResult<()> {
+        self.xwayland
+            .remove_baselayer_app_id()
+            .map_err(runtime_error)
+    }
+
+    /// Takes a screenshot of all layers and returns the path of the written
+    /// file, waiting up to the given number of seconds
+    #[pyo3(signature = (timeout = 5.0))]
+    fn take_screenshot(&self, py: Python<'_>, timeout: f64) -> PyResult<String> {
+        let timeout = Duration::try_from_secs_f64(timeout)
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        let xwayland = &self.xwayland;
+        let path = py
+            .detach(|| {
+                xwayland
+                    .take_screenshot_with_type(ScreenshotType::AllRealLayers, timeout)
+                    .map_err(|err| err.to_string())
+            })
+            .map_err(PyRuntimeError::new_err)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Returns an iterator over the property changes on the given window, or
+    /// on the root window if none is given
+    #[pyo3(signature = (window_id = None))]
+    fn property_changes(&self, window_id: Option<u32>) -> PyResult<PyPropertyChanges> {
+        let window_id = match window_id {
+            Some(window_id) => window_id,
+            None => self.root_window_id()?,
+        };
+        let (_, rx) = self
+            .xwayland
+            .listen_for_window_property_values(window_id)
+            .map_err(runtime_error)?;
+        Ok(PyPropertyChanges { rx: Mutex::new(rx) })
+    }
+}
+
+/// Python wrapper of a [PropertyChange]
+#[pyclass(name = "PropertyChange", module = "gamescope_x11", get_all)]
+struct PyPropertyChange {
+    /// The window the property changed on
+    window_id: u32,
+    /// The name of the property
+    property: String,
+    /// The decoded new value, or None if the property was removed
+    value: Option<Py<PyAny>>,
+}
+
+#[pymethods]
+impl PyPropertyChange {
+    fn __repr__(&self) -> String {
+        format!(
+            "PropertyChange(window_id={}, property={:?})",
+            self.window_id, self.property
+        )
+    }
+}
+
+/// Iterator that waits for the next [PropertyChange] without holding the GIL
+#[pyclass(name = "PropertyChanges", module = "gamescope_x11")]
+struct PyPropertyChanges {
+    rx: Mutex<Receiver<PropertyChange>>,
+}
+
+#[pymethods]
+impl PyPropertyChanges {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyPropertyChange>> {
+        let rx = &self.rx;
+        let change = py.detach(|| rx.lock().ok().and_then(|rx| rx.recv().ok()));
+        let Some(change) = change else {
+            return Ok(None);
+        };
+        let value = change
+            .value
+            .map(|value| value_to_py(py, &value))
+            .transpose()?;
+        Ok(Some(PyPropertyChange {
+            window_id: change.window_id,
+            property: change.property,
+            value,
+        }))
+    }
+}
+
+/// Returns the names of all Gamescope XWayland displays (E.g. [":0", ":1"])
+#[pyfunction]
+fn discover_gamescope_displays() -> PyResult<Vec<String>> {
+    crate::discover_gamescope_displays().map_err(runtime_error)
+}
+
+/// Connects to the primary Gamescope XWayland
+#[pyfunction]
+fn discover_primary_xwayland() -> PyResult<PyXWayland> {
+    let xwayland = crate::discover_primary_xwayland().map_err(runtime_error)?;
+    Ok(PyXWayland { xwayland })
+}
+
+/// Python bindings, importable as `gamescope_x11` when the crate is built as
+/// an extension module (E.g. with maturin and the "pyo3/extension-module"
+/// feature)
+#[pymodule(name = "gamescope_x11")]
+fn gamescope_x11(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyXWayland>()?;
+    m.add_class::<PyPropertyChange>()?;
+    m.add_class::<PyPropertyChanges>()?;
+    m.add_function(wrap_pyfunction!(discover_gamescope_displays, m)?)?;
+    m.add_function(wrap_pyfunction!(discover_primary_xwayland, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_atom() {
+        assert_eq!(
+            parse_atom("GAMESCOPE_FPS_LIMIT").unwrap(),
+            GamescopeAtom::FPSLimit
+        );
+        assert!(parse_atom("FPSLimit").is_err());
+    }
+}
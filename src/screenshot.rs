@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Directory Gamescope writes screenshots to
+pub const SCREENSHOT_DIR: &str = "/tmp";
+
+/// How often the screenshot directory is checked for a new file
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Returns the paths of all Gamescope screenshots in the given directory
+/// (E.g. "/tmp/gamescope_2024-01-01_12-00-00.png")
+pub(crate) fn list_screenshots(dir: &Path) -> HashSet<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return HashSet::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_screenshot(path))
+        .collect()
+}
+
+/// Waits until a screenshot that is not in the given list of existing
+/// screenshots appears in the given directory and has been completely
+/// written. Returns its path.
+pub(crate) fn wait_for_screenshot(
+    dir: &Path,
+    existing: &HashSet<PathBuf>,
+    timeout: Duration,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    let mut last_size: Option<(PathBuf, u64)> = None;
+    loop {
+        let new = list_screenshots(dir)
+            .into_iter()
+            .filter(|path| !existing.contains(path))
+            .max();
+        if let Some(path) = new {
+            // Gamescope writes the file in place, so wait until it stops growing
+            let size = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+            let current = (path, size);
+            if size > 0 && last_size.as_ref() == Some(&current) {
+                return Ok(current.0);
+            }
+            last_size = Some(current);
+        }
+
+        if Instant::now() >= deadline {
+            return Err("Timed out waiting for the screenshot to be written".into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Returns true if the given path looks like a Gamescope screenshot
+fn is_screenshot(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    name.starts_with("gamescope_") && name.ends_with(".png")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_screenshot() {
+        assert!(is_screenshot(Path::new(
+            "/tmp/gamescope_2024-01-01_12-00-00.png"
+        )));
+        assert!(!is_screenshot(Path::new("/tmp/gamescope_debug.txt")));
+        assert!(!is_screenshot(Path::new("/tmp/screenshot.png")));
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::atoms::GamescopeAtom;
+
+/// A changed property as a tuple of (property, old value, new value)
+type PropertyDiff = (String, Option<Vec<u32>>, Option<Vec<u32>>);
+
+/// [SessionState] is a point-in-time capture of the properties Gamescope
+/// exposes on a single [crate::xwayland::XWayland] instance. Two states can be
+/// compared with [diff_states] to see what changed between them.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionState {
+    /// Name of the XWayland instance the state was captured from (E.g. ":0")
+    pub name: String,
+    /// Index Gamescope assigned to the XWayland server
+    /// (GAMESCOPE_XWAYLAND_SERVER_ID), where 0 is the primary server
+    pub server_id: Option<u32>,
+    /// All cardinal properties set on the root window
+    pub root_properties: HashMap<String, Vec<u32>>,
+    /// All string properties set on the root window (E.g.
+    /// GAMESCOPE_KEYBOARD_FOCUS_DISPLAY)
+    pub root_string_properties: HashMap<String, String>,
+    /// Windows Gamescope can focus (GAMESCOPE_FOCUSABLE_WINDOWS)
+    pub focusable_windows: Vec<FocusableWindow>,
+    /// Top-level and focusable windows and their properties, keyed by window
+    /// id
+    pub windows: HashMap<u32, WindowState>,
+}
+
+impl SessionState {
+    /// Returns the value of every known Gamescope property set on the root
+    /// window, in the order of [GamescopeAtom]
+    pub fn gamescope_properties(&self) -> Vec<(GamescopeAtom, &[u32])> {
+        GamescopeAtom::all()
+            .filter_map(|atom| {
+                let values = self.root_properties.get(&atom.to_string())?;
+                Some((atom, values.as_slice()))
+            })
+            .collect()
+    }
+}
+
+/// A window Gamescope can focus, as published in GAMESCOPE_FOCUSABLE_WINDOWS
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FocusableWindow {
+    /// The focusable window
+    pub window_id: u32,
+    /// The app id of the window (STEAM_GAME), or 0 if it has none
+    pub app_id: u32,
+    /// The process ID of the window, or 0 if it is unknown
+    pub pid: u32,
+}
+
+impl FocusableWindow {
+    /// Decodes the (window, app id, pid) triplets of
+    /// GAMESCOPE_FOCUSABLE_WINDOWS. An incomplete trailing triplet is ignored.
+    pub fn from_values(values: &[u32]) -> Vec<Self> {
+        values
+            .chunks_exact(3)
+            .map(|triplet| Self {
+                window_id: triplet[0],
+                app_id: triplet[1],
+                pid: triplet[2],
+            })
+            .collect()
+    }
+}
+
+/// Captured state of a single top-level window
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowState {
+    /// The window name (WM_NAME), if set
+    pub name: Option<String>,
+    /// The app id of the window (STEAM_GAME), if set
+    pub app_id: Option<u32>,
+    /// The process ID of the window (_NET_WM_PID), if set
+    pub pid: Option<u32>,
+    /// All cardinal properties set on the window
+    pub properties: HashMap<String, Vec<u32>>,
+}
+
+/// A single difference between two [SessionState] captures
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateChange {
+    /// A root window property was added, removed, or changed value
+    RootProperty {
+        property: String,
+        old: Option<Vec<u32>>,
+        new: Option<Vec<u32>>,
+    },
+    /// A window exists in the new state but not in the old one
+    WindowAdded { window_id: u32 },
+    /// A window exists in the old state but not in the new one
+    WindowRemoved { window_id: u32 },
+    /// The name of a window changed
+    WindowName {
+        window_id: u32,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A property on a window was added, removed, or changed value
+    WindowProperty {
+        window_id: u32,
+        property: String,
+        old: Option<Vec<u32>>,
+        new: Option<Vec<u32>>,
+    },
+}
+
+/// Returns the list of changes needed to go from state `a` to state `b`.
+/// Changes are ordered by root properties first, then by window id and
+/// property name so the output is stable between runs.
+pub fn diff_states(a: &SessionState, b: &SessionState) -> Vec<StateChange> {
+    let mut changes: Vec<StateChange> = Vec::new();
+
+    // Compare root window properties
+    for (property, old, new) in diff_properties(&a.root_properties, &b.root_properties) {
+        changes.push(StateChange::RootProperty { property, old, new });
+    }
+
+    // Compare windows
+    let window_ids: BTreeSet<&u32> = a.windows.keys().chain(b.windows.keys()).collect();
+    for window_id in window_ids {
+        let window_id = *window_id;
+        let (old, new) = match (a.windows.get(&window_id), b.windows.get(&window_id)) {
+            (Some(old), Some(new)) => (old, new),
+            (None, Some(_)) => {
+                changes.push(StateChange::WindowAdded { window_id });
+                continue;
+            }
+            (Some(_), None) => {
+                changes.push(StateChange::WindowRemoved { window_id });
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        if old.name != new.name {
+            changes.push(StateChange::WindowName {
+                window_id,
+                old: old.name.clone(),
+                new: new.name.clone(),
+            });
+        }
+
+        for (property, old, new) in diff_properties(&old.properties, &new.properties) {
+            changes.push(StateChange::WindowProperty {
+                window_id,
+                property,
+                old,
+                new,
+            });
+        }
+    }
+
+    changes
+}
+
+/// Returns every property whose value differs between the two maps, sorted by
+/// property name.
+fn diff_properties(
+    a: &HashMap<String, Vec<u32>>,
+    b: &HashMap<String, Vec<u32>>,
+) -> Vec<PropertyDiff> {
+    let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let old = a.get(key);
+            let new = b.get(key);
+            if old == new {
+                return None;
+            }
+            Some((key.clone(), old.cloned(), new.cloned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_states() {
+        let mut a = SessionState::default();
+        a.root_properties
+            .insert("GAMESCOPE_FOCUSED_APP".into(), vec![769]);
+        a.windows.insert(1, WindowState::default());
+        a.windows.insert(2, WindowState::default());
+
+        let mut b = a.clone();
+        b.root_properties
+            .insert("GAMESCOPE_FOCUSED_APP".into(), vec![620]);
+        b.windows.remove(&1);
+        b.windows.insert(3, WindowState::default());
+        b.windows
+            .get_mut(&2)
+            .unwrap()
+            .properties
+            .insert("STEAM_GAME".into(), vec![620]);
+
+        let changes = diff_states(&a, &b);
+        assert_eq!(
+            changes,
+            vec![
+                StateChange::RootProperty {
+                    property: "GAMESCOPE_FOCUSED_APP".into(),
+                    old: Some(vec![769]),
+                    new: Some(vec![620]),
+                },
+                StateChange::WindowRemoved { window_id: 1 },
+                StateChange::WindowProperty {
+                    window_id: 2,
+                    property: "STEAM_GAME".into(),
+                    old: None,
+                    new: Some(vec![620]),
+                },
+                StateChange::WindowAdded { window_id: 3 },
+            ]
+        );
+        assert!(diff_states(&b, &b).is_empty());
+    }
+
+    #[test]
+    fn test_focusable_window_triplets() {
+        let values = [0x400001, 769, 1234, 0x600003, 0, 0, 0x800001];
+        assert_eq!(
+            FocusableWindow::from_values(&values),
+            vec![
+                FocusableWindow {
+                    window_id: 0x400001,
+                    app_id: 769,
+                    pid: 1234
+                },
+                FocusableWindow {
+                    window_id: 0x600003,
+                    app_id: 0,
+                    pid: 0
+                },
+            ]
+        );
+    }
+}
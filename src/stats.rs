@@ -0,0 +1,143 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+use crate::{connection::ConnectOptions, worker};
+
+/// A record written by Gamescope to the stats pipe given with `--stats-path`.
+/// Gamescope writes one `key=value` pair per line. Known keys are parsed into
+/// typed records and all others are kept as [FrameStat::Other].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameStat {
+    /// The app id of the focused app ("app")
+    App(u32),
+    /// The focused window ("window"), written in hex (E.g. "0x400001")
+    Window(u32),
+    /// A record with a key this crate doesn't know about, or with a value
+    /// that could not be parsed
+    Other { key: String, value: String },
+}
+
+impl FrameStat {
+    /// Parses a single line of the stats pipe. Returns None for lines that
+    /// are not a `key=value` pair.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (key, value) = line.trim().split_once('=')?;
+        let parsed = match key {
+            "app" => value.parse().ok().map(FrameStat::App),
+            "window" => parse_number(value).map(FrameStat::Window),
+            _ => None,
+        };
+
+        Some(parsed.unwrap_or_else(|| FrameStat::Other {
+            key: key.to_string(),
+            value: value.to_string(),
+        }))
+    }
+}
+
+/// Parses a decimal or "0x" prefixed hex number
+fn parse_number(value: &str) -> Option<u32> {
+    match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Creates a FIFO at the given path for Gamescope to write its stats to, if
+/// nothing exists at the path yet
+pub fn create_stats_pipe(path: &Path) -> io::Result<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    if unsafe { libc::mkfifo(path.as_ptr(), 0o600) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Listen for the records Gamescope writes to the stats pipe at the given path
+/// (see `gamescope --stats-path`), creating the pipe if it does not exist.
+/// Returns a join handle of the reading thread and a receiver channel for the
+/// records. The pipe is opened again whenever Gamescope closes it (E.g. when
+/// it restarts), so the thread only exits when the receiver is dropped. A
+/// regular file is read once.
+pub fn listen_for_stats(
+    path: &Path,
+) -> Result<(JoinHandle<()>, Receiver<FrameStat>), Box<dyn std::error::Error>> {
+    create_stats_pipe(path)?;
+    let is_fifo = path.metadata()?.file_type().is_fifo();
+
+    let (tx, rx): (Sender<FrameStat>, Receiver<FrameStat>) = mpsc::channel();
+    let path: PathBuf = path.to_path_buf();
+    let options = ConnectOptions::default();
+    let name = path.to_string_lossy().to_string();
+    let child = worker::spawn(&name, "stats", &options, move || loop {
+        // Opening a FIFO blocks until Gamescope opens it for writing
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) => {
+                log::error!("Unable to open stats pipe {}: {}", path.display(), err);
+                break;
+            }
+        };
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    log::debug!("Error reading stats pipe: {}", err);
+                    break;
+                }
+            };
+            let Some(stat) = FrameStat::parse(&line) else {
+                continue;
+            };
+            if tx.send(stat).is_err() {
+                return;
+            }
+        }
+        if !is_fifo {
+            break;
+        }
+    });
+
+    Ok((child, rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stats() {
+        assert_eq!(FrameStat::parse("app=620\n"), Some(FrameStat::App(620)));
+        assert_eq!(
+            FrameStat::parse("window=0x400001"),
+            Some(FrameStat::Window(0x400001))
+        );
+        assert_eq!(
+            FrameStat::parse("app=steam"),
+            Some(FrameStat::Other {
+                key: "app".into(),
+                value: "steam".into()
+            })
+        );
+        assert_eq!(FrameStat::parse("garbage"), None);
+
+        let path = std::env::temp_dir().join(format!("gamescope-stats-{}", std::process::id()));
+        std::fs::write(&path, "app=769\nwindow=12\n").unwrap();
+        let (handle, rx) = listen_for_stats(&path).unwrap();
+        let stats: Vec<FrameStat> = rx.iter().collect();
+        handle.join().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(stats, vec![FrameStat::App(769), FrameStat::Window(12)]);
+    }
+}
@@ -0,0 +1,76 @@
+use std::pin::Pin;
+use std::sync::mpsc::Receiver;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::{self, UnboundedReceiver};
+use futures::Stream;
+
+use crate::{connection::ConnectOptions, worker};
+
+/// [Stream] of the events of a listener (E.g. from
+/// [crate::xwayland::XWayland::listen_for_property_values]), so async
+/// consumers can use [futures::StreamExt] combinators and merge the events of
+/// multiple instances (E.g. with [futures::stream::select_all]). The stream
+/// ends when the listener stops.
+#[derive(Debug)]
+pub struct EventStream<T> {
+    rx: UnboundedReceiver<T>,
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        Pin::new(&mut self.rx).poll_next(cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rx.size_hint()
+    }
+}
+
+/// Spawns a thread that forwards the events received from the given channel
+/// to an [EventStream]. The thread exits when the stream is dropped or the
+/// channel hangs up.
+pub(crate) fn spawn_stream<T>(
+    display: &str,
+    options: &ConnectOptions,
+    events: Receiver<T>,
+) -> EventStream<T>
+where
+    T: Send + 'static,
+{
+    let (tx, rx) = mpsc::unbounded();
+    worker::spawn(display, "stream", options, move || {
+        for event in events {
+            if tx.unbounded_send(event).is_err() {
+                break;
+            }
+        }
+    });
+
+    EventStream { rx }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[test]
+    fn test_event_stream() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let stream = spawn_stream(":99", &ConnectOptions::default(), rx);
+        for counter in 1..=4 {
+            tx.send(counter).unwrap();
+        }
+        drop(tx);
+
+        let events = futures::executor::block_on(
+            stream
+                .filter(|n| futures::future::ready(n % 2 == 0))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(events, vec![2, 4]);
+    }
+}
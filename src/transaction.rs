@@ -0,0 +1,190 @@
+use x11rb::{
+    connection::Connection,
+    cookie::VoidCookie,
+    protocol::xproto::{AtomEnum, ConnectionExt, PropMode},
+};
+
+use crate::{
+    atoms::GamescopeAtom, connection::X11Connection, property::PropertyValue, xwayland::XWayland,
+};
+
+/// A single change queued in a [PropertyTransaction]
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Set(Vec<u32>),
+    SetString(String),
+    Remove,
+}
+
+impl Change {
+    /// Returns the new value of the property for the journal
+    fn new_value(&self) -> Option<PropertyValue> {
+        match self {
+            Change::Set(values) => Some(PropertyValue::Cardinal(values.clone())),
+            Change::SetString(value) => Some(PropertyValue::String(value.clone())),
+            Change::Remove => None,
+        }
+    }
+}
+
+/// Result of a single change applied by [PropertyTransaction::commit]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyResult {
+    /// The window the property was changed on
+    pub window_id: u32,
+    /// The changed property
+    pub key: GamescopeAtom,
+    /// The error the X server reported for the change, if any
+    pub error: Option<String>,
+}
+
+impl PropertyResult {
+    /// Returns true if the X server applied the change
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// [PropertyTransaction] queues several property changes (E.g. a bundle of
+/// settings like the FPS limit, blur and tearing) and sends them together
+/// while the X server is grabbed, so Gamescope sees all of them at once. The
+/// connection is only flushed once and each change reports its own result.
+/// Create one with [XWayland::transaction].
+#[derive(Debug)]
+pub struct PropertyTransaction<'a> {
+    xwayland: &'a XWayland,
+    changes: Vec<(u32, GamescopeAtom, Change)>,
+}
+
+impl<'a> PropertyTransaction<'a> {
+    /// Create an empty transaction for the given instance
+    pub(crate) fn new(xwayland: &'a XWayland) -> Self {
+        Self {
+            xwayland,
+            changes: Vec::new(),
+        }
+    }
+
+    /// Queues setting the given property value(s) on the given window
+    pub fn set(mut self, window_id: u32, key: GamescopeAtom, values: Vec<u32>) -> Self {
+        self.changes.push((window_id, key, Change::Set(values)));
+        self
+    }
+
+    /// Queues setting the given string property on the given window
+    pub fn set_string(mut self, window_id: u32, key: GamescopeAtom, value: &str) -> Self {
+        self.changes
+            .push((window_id, key, Change::SetString(value.to_string())));
+        self
+    }
+
+    /// Queues removing the given property from the given window
+    pub fn remove(mut self, window_id: u32, key: GamescopeAtom) -> Self {
+        self.changes.push((window_id, key, Change::Remove));
+        self
+    }
+
+    /// Returns the number of queued changes
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Returns true if no changes are queued
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Sends all queued changes under a server grab and returns the result of
+    /// each change in the order they were queued. Fails only if the changes
+    /// could not be sent at all.
+    pub fn commit(self) -> Result<Vec<PropertyResult>, Box<dyn std::error::Error>> {
+        if self.changes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Resolve the atoms and record the old values before grabbing the
+        // server, since both may need round trips
+        let (conn, atoms) = self.xwayland.get_shared_connection()?.get_with_atoms();
+        let mut requests = Vec::with_capacity(self.changes.len());
+        for (window_id, key, change) in self.changes.iter() {
+            let atom = atoms.resolve(conn.as_ref(), *key)?;
+            let property = key.to_string();
+            self.xwayland
+                .journal_change(*window_id, &property, change.new_value())?;
+            requests.push((*window_id, atom, change));
+        }
+
+        // Always release the grab, even if sending a change failed
+        conn.grab_server()?;
+        let cookies: Result<Vec<_>, _> = requests
+            .into_iter()
+            .map(|(window_id, atom, change)| send_change(conn.as_ref(), window_id, atom, change))
+            .collect();
+        conn.ungrab_server()?;
+        conn.flush()?;
+        let cookies = cookies?;
+
+        let mut results = Vec::with_capacity(cookies.len());
+        for ((window_id, key, _), cookie) in self.changes.iter().zip(cookies) {
+            let error = cookie.check().err().map(|err| err.to_string());
+            if error.is_none() {
+                self.xwayland.track_focus_request(*key);
+            }
+            results.push(PropertyResult {
+                window_id: *window_id,
+                key: *key,
+                error,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Sends the given change without waiting for the X server to apply it
+fn send_change<'c>(
+    conn: &'c X11Connection,
+    window_id: u32,
+    atom: u32,
+    change: &Change,
+) -> Result<VoidCookie<'c, X11Connection>, Box<dyn std::error::Error>> {
+    use x11rb::wrapper::ConnectionExt as _;
+
+    let cookie = match change {
+        Change::Set(values) => conn.change_property32(
+            PropMode::REPLACE,
+            window_id,
+            atom,
+            AtomEnum::CARDINAL,
+            values.as_slice(),
+        )?,
+        Change::SetString(value) => conn.change_property8(
+            PropMode::REPLACE,
+            window_id,
+            atom,
+            AtomEnum::STRING,
+            value.as_bytes(),
+        )?,
+        Change::Remove => conn.delete_property(window_id, atom)?,
+    };
+
+    Ok(cookie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_change_values() {
+        assert_eq!(
+            Change::Set(vec![30]).new_value(),
+            Some(PropertyValue::Cardinal(vec![30]))
+        );
+        assert_eq!(
+            Change::SetString("DP-1".into()).new_value(),
+            Some(PropertyValue::String("DP-1".into()))
+        );
+        assert_eq!(Change::Remove.new_value(), None);
+    }
+}
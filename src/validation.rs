@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use crate::{
+    atoms::{AtomType, GamescopeAtom},
+    property::PropertyValue,
+};
+
+/// X resource ids never have any of the top three bits set
+const RESOURCE_ID_MASK: u32 = 0xE000_0000;
+
+/// Highest refresh rate in Hz that is considered plausible
+const MAX_REFRESH_RATE: u32 = 1000;
+
+/// Properties that hold window ids, in addition to the windows listed in
+/// [GamescopeAtom::FocusableWindows]
+const WINDOW_ID_ATOMS: [GamescopeAtom; 2] =
+    [GamescopeAtom::FocusedWindow, GamescopeAtom::BaselayerWindow];
+
+/// Properties that hold a list without duplicate entries, in addition to the
+/// windows listed in [GamescopeAtom::FocusableWindows]
+const UNIQUE_ATOMS: [GamescopeAtom; 1] = [GamescopeAtom::FocusableApps];
+
+/// Properties that are either 0 or 1
+const BOOLEAN_ATOMS: [GamescopeAtom; 11] = [
+    GamescopeAtom::AllowTearing,
+    GamescopeAtom::ForceWindowsFullscreen,
+    GamescopeAtom::CursorVisibleFeedback,
+    GamescopeAtom::DisplayIsExternal,
+    GamescopeAtom::VRRCapable,
+    GamescopeAtom::VRREnabled,
+    GamescopeAtom::VRRFeedback,
+    GamescopeAtom::DisplaySupportsHDR,
+    GamescopeAtom::DisplayHDREnabled,
+    GamescopeAtom::HDROutputFeedback,
+    GamescopeAtom::HDRItmEnable,
+];
+
+/// Properties that hold exactly one value, in addition to [BOOLEAN_ATOMS]
+const SINGLE_VALUE_ATOMS: [GamescopeAtom; 8] = [
+    GamescopeAtom::FocusedApp,
+    GamescopeAtom::FocusedAppGFX,
+    GamescopeAtom::FocusedWindow,
+    GamescopeAtom::FPSLimit,
+    GamescopeAtom::InputCounter,
+    GamescopeAtom::BlurMode,
+    GamescopeAtom::BlurRadius,
+    GamescopeAtom::DisplayRefreshRateFeedback,
+];
+
+/// Properties that hold a refresh rate in Hz
+const REFRESH_RATE_ATOMS: [GamescopeAtom; 2] = [
+    GamescopeAtom::DisplayRefreshRateFeedback,
+    GamescopeAtom::RequestedRefreshRate,
+];
+
+/// A property value that does not have the shape Gamescope is expected to
+/// publish, reported when strict validation is enabled with
+/// [XWayland::enable_strict_validation](crate::xwayland::XWayland::enable_strict_validation)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SuspiciousValue {
+    /// The window the property was read from
+    pub window_id: u32,
+    /// The property that was read
+    pub property: GamescopeAtom,
+    /// The value that was read
+    pub value: PropertyValue,
+    /// Why the value is suspicious
+    pub reason: String,
+}
+
+/// Checks the given value of the given property against the shape Gamescope
+/// is expected to publish. Returns why the value is suspicious, or None if it
+/// looks plausible.
+pub fn validate(property: GamescopeAtom, value: &PropertyValue) -> Option<String> {
+    if property.atom_type() == AtomType::String {
+        return match value {
+            PropertyValue::String(_) => None,
+            _ => Some("expected a string".into()),
+        };
+    }
+
+    let Some(values) = value.as_u32s() else {
+        return Some("expected numbers".into());
+    };
+
+    let is_boolean = BOOLEAN_ATOMS.contains(&property);
+    if (is_boolean || SINGLE_VALUE_ATOMS.contains(&property)) && values.len() != 1 {
+        return Some(format!("expected a single value, got {}", values.len()));
+    }
+    if is_boolean {
+        if let Some(value) = values.iter().find(|value| **value > 1) {
+            return Some(format!("expected 0 or 1, got {}", value));
+        }
+    }
+    // Focusable windows are published as (window, app id, pid) triplets, of
+    // which only the window ids have to be valid and unique
+    let is_focusable_windows = property == GamescopeAtom::FocusableWindows;
+    if is_focusable_windows && values.len() % 3 != 0 {
+        return Some(format!(
+            "expected (window, app id, pid) triplets, got {} values",
+            values.len()
+        ));
+    }
+    let window_ids: Vec<u32> = if is_focusable_windows {
+        values.iter().step_by(3).copied().collect()
+    } else if WINDOW_ID_ATOMS.contains(&property) {
+        values.to_vec()
+    } else {
+        Vec::new()
+    };
+    if let Some(value) = window_ids
+        .iter()
+        .find(|value| **value & RESOURCE_ID_MASK != 0)
+    {
+        return Some(format!("{:#x} is not a valid window id", value));
+    }
+    let unique: &[u32] = if is_focusable_windows {
+        window_ids.as_slice()
+    } else if UNIQUE_ATOMS.contains(&property) {
+        values
+    } else {
+        &[]
+    };
+    let mut seen = HashSet::new();
+    if let Some(value) = unique.iter().find(|value| !seen.insert(**value)) {
+        return Some(format!("{} is listed more than once", value));
+    }
+    if REFRESH_RATE_ATOMS.contains(&property) {
+        let implausible = |value: &&u32| **value == 0 || **value > MAX_REFRESH_RATE;
+        if let Some(value) = values.iter().find(implausible) {
+            return Some(format!("{} Hz is not a plausible refresh rate", value));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_property_values() {
+        let windows = PropertyValue::Window(vec![0x400001, 620, 1234, 0x600003, 620, 1234]);
+        assert_eq!(validate(GamescopeAtom::FocusableWindows, &windows), None);
+
+        let garbage = PropertyValue::Window(vec![0xffffffff, 620, 1234]);
+        assert_eq!(
+            validate(GamescopeAtom::FocusableWindows, &garbage),
+            Some("0xffffffff is not a valid window id".into())
+        );
+
+        let duplicates = PropertyValue::Window(vec![0x400001, 620, 1234, 0x400001, 620, 1234]);
+        assert!(validate(GamescopeAtom::FocusableWindows, &duplicates).is_some());
+
+        let partial = PropertyValue::Window(vec![0x400001, 620]);
+        assert!(validate(GamescopeAtom::FocusableWindows, &partial).is_some());
+
+        let empty = PropertyValue::Window(vec![]);
+        assert_eq!(validate(GamescopeAtom::FocusableWindows, &empty), None);
+        assert_eq!(validate(GamescopeAtom::FocusableApps, &empty), None);
+
+        // Non-Steam shortcuts have the high bit set
+        let apps = PropertyValue::Cardinal(vec![620, 0x8000_0001]);
+        assert_eq!(validate(GamescopeAtom::FocusableApps, &apps), None);
+
+        let tearing = PropertyValue::Cardinal(vec![2]);
+        assert!(validate(GamescopeAtom::AllowTearing, &tearing).is_some());
+
+        let focused = PropertyValue::Cardinal(vec![620, 769]);
+        assert!(validate(GamescopeAtom::FocusedApp, &focused).is_some());
+
+        let refresh = PropertyValue::Cardinal(vec![0]);
+        assert!(validate(GamescopeAtom::DisplayRefreshRateFeedback, &refresh).is_some());
+
+        let path = PropertyValue::String("/tmp/edid.bin".into());
+        assert_eq!(validate(GamescopeAtom::DisplayEdidPath, &path), None);
+    }
+}
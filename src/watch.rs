@@ -0,0 +1,188 @@
+use std::sync::mpsc::{Receiver, Sender};
+use std::thread::JoinHandle;
+
+use crate::{
+    debounce::Debounce,
+    property::PropertyChange,
+    worker,
+    xwayland::{Primary, XWayland},
+};
+
+/// Join handles of the threads watching properties and a receiver channel
+/// for the changes they match
+pub type WatchListener = (Vec<JoinHandle<()>>, Receiver<WatchEvent>);
+
+/// Instances a [WatchSpec] applies to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InstanceSelector {
+    /// Every managed instance
+    #[default]
+    All,
+    /// The primary instance
+    Primary,
+    /// The instance with the given display name (E.g. ":1")
+    Display(String),
+}
+
+impl InstanceSelector {
+    /// Returns true if the given instance is selected
+    pub fn matches(&self, xwayland: &XWayland) -> Result<bool, Box<dyn std::error::Error>> {
+        match self {
+            InstanceSelector::All => Ok(true),
+            InstanceSelector::Primary => xwayland.is_primary_instance(),
+            InstanceSelector::Display(name) => Ok(xwayland.get_name() == *name),
+        }
+    }
+}
+
+/// Windows a [WatchSpec] applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowSelector {
+    /// The root window
+    #[default]
+    Root,
+    /// The window with the given id
+    Window(u32),
+    /// The top-level windows with the given app id (STEAM_GAME) at the time
+    /// the watch is established
+    App(u32),
+    /// The window focused by Gamescope at the time the watch is established
+    Focused,
+}
+
+/// [WatchSpec] describes a property watch declaratively, so a daemon can keep
+/// its watch configuration in one place, persist it and have the
+/// [GamescopeManager](crate::manager::GamescopeManager) establish it again
+/// after a restart or reconnect. With the `serde` feature it can be loaded
+/// from and saved to any serde format.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchSpec {
+    /// Name identifying the watch in the events it produces
+    pub name: String,
+    /// Instances to watch
+    pub instance: InstanceSelector,
+    /// Windows to watch on each instance
+    pub window: WindowSelector,
+    /// Names of the properties to watch (E.g. "GAMESCOPE_FOCUSED_APP"), or
+    /// all properties if empty
+    pub properties: Vec<String>,
+    /// How often changes of each property are delivered
+    pub debounce: Debounce,
+}
+
+impl WatchSpec {
+    /// Create a watch of all properties on the root window of every instance
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Watch the given instances
+    pub fn with_instance(mut self, instance: InstanceSelector) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    /// Watch the given windows
+    pub fn with_window(mut self, window: WindowSelector) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Watch the given property, in addition to any others already added
+    pub fn with_property(mut self, property: &str) -> Self {
+        self.properties.push(property.to_string());
+        self
+    }
+
+    /// Limit how often changes are delivered
+    pub fn with_debounce(mut self, debounce: Debounce) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Returns true if changes to the given property are watched
+    pub fn watches_property(&self, property: &str) -> bool {
+        self.properties.is_empty() || self.properties.iter().any(|name| name == property)
+    }
+}
+
+/// A property change matched by a [WatchSpec]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WatchEvent {
+    /// Name of the watch that matched
+    pub watch: String,
+    /// Name of the instance the property changed on (E.g. ":0")
+    pub display: String,
+    /// The property change
+    pub change: PropertyChange,
+}
+
+/// Starts watching the windows selected by the given spec on the given
+/// instance and sends every matching change through the given channel.
+/// Returns the join handles of the watching threads.
+pub(crate) fn establish(
+    xwayland: &XWayland,
+    spec: &WatchSpec,
+    tx: &Sender<WatchEvent>,
+) -> Result<Vec<JoinHandle<()>>, Box<dyn std::error::Error>> {
+    let windows = match spec.window {
+        WindowSelector::Root => vec![xwayland.get_root_window_id()?],
+        WindowSelector::Window(window_id) => vec![window_id],
+        WindowSelector::App(app_id) => xwayland.find_app_windows(app_id)?,
+        WindowSelector::Focused => xwayland.get_focused_window()?.into_iter().collect(),
+    };
+
+    let mut handles: Vec<JoinHandle<()>> = Vec::new();
+    for window_id in windows {
+        let (_, rx) = xwayland.listen_for_window_property_values_with(window_id, spec.debounce)?;
+        let spec = spec.clone();
+        let tx = tx.clone();
+        let display = xwayland.get_name();
+        let options = xwayland.get_connect_options();
+        let handle = worker::spawn(&display.clone(), "watch", &options, move || {
+            for change in rx {
+                if !spec.watches_property(&change.property) {
+                    continue;
+                }
+                let event = WatchEvent {
+                    watch: spec.name.clone(),
+                    display: display.clone(),
+                    change,
+                };
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+        handles.push(handle);
+    }
+
+    Ok(handles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_watch_spec_properties() {
+        let spec = WatchSpec::new("focus");
+        assert!(spec.watches_property("GAMESCOPE_FOCUSED_APP"));
+
+        let spec = spec
+            .with_window(WindowSelector::Focused)
+            .with_property("STEAM_GAME")
+            .with_debounce(Debounce::Throttle(Duration::from_millis(100)));
+        assert_eq!(spec.window, WindowSelector::Focused);
+        assert!(spec.watches_property("STEAM_GAME"));
+        assert!(!spec.watches_property("GAMESCOPE_FOCUSED_APP"));
+    }
+}
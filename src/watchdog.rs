@@ -0,0 +1,111 @@
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use x11rb::protocol::xproto::ConnectionExt;
+
+use crate::{
+    atoms::GamescopeAtom,
+    connection::{SharedConnection, X11Connection},
+    extension::{self, Extension},
+    worker, x11,
+};
+
+/// Events emitted by the input focus watchdog
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WatchdogEvent {
+    /// STEAM_INPUT_FOCUS was removed from a window whose owning process no
+    /// longer exists
+    InputFocusCleared { window_id: u32, pid: u32 },
+}
+
+/// Spawns a thread that checks the top-level windows at the given interval for
+/// STEAM_INPUT_FOCUS being set on a window whose owning process has died (E.g.
+/// a crashed overlay). When found, the property is removed so input is routed
+/// to the game again.
+pub(crate) fn spawn_input_focus_watchdog(
+    conn: SharedConnection,
+    interval: Duration,
+) -> (JoinHandle<()>, Receiver<WatchdogEvent>) {
+    let (tx, rx): (Sender<WatchdogEvent>, Receiver<WatchdogEvent>) = mpsc::channel();
+
+    let display = conn.name().to_string();
+    let options = conn.options().clone();
+    let child = worker::spawn(&display, "watchdog", &options, move || loop {
+        let events = match check_input_focus(conn.get().as_ref(), conn.root_window_id()) {
+            Ok(events) => events,
+            // Keep checking while the connection may be re-established
+            Err(err) if conn.policy().is_some() => {
+                log::debug!("Error checking input focus: {}", err);
+                Vec::new()
+            }
+            Err(err) => {
+                log::error!("Error checking input focus: {}", err);
+                break;
+            }
+        };
+        for event in events {
+            if tx.send(event).is_err() {
+                return;
+            }
+        }
+        thread::sleep(interval);
+    });
+
+    (child, rx)
+}
+
+/// Removes STEAM_INPUT_FOCUS from all top-level windows whose owning process
+/// no longer exists. The owning processes are looked up with XRes, since
+/// '_NET_WM_PID' holds the pid inside the client's own pid namespace (E.g.
+/// Flatpak or pressure-vessel), which may not exist on the host. Without XRes
+/// nothing is cleared.
+fn check_input_focus(
+    conn: &X11Connection,
+    root_window_id: u32,
+) -> Result<Vec<WatchdogEvent>, Box<dyn std::error::Error>> {
+    if !extension::has_extension(conn, Extension::XRes)? {
+        log::debug!("XRes is missing, unable to check input focus owners");
+        return Ok(Vec::new());
+    }
+
+    let input_focus = GamescopeAtom::SteamInputFocus.to_string();
+    let windows = conn.query_tree(root_window_id)?.reply()?.children;
+
+    let mut events: Vec<WatchdogEvent> = Vec::new();
+    for window_id in windows {
+        // Windows may be destroyed while checking, so skip any errors
+        let Ok(Some(focus)) = x11::get_property(conn, window_id, input_focus.as_str()) else {
+            continue;
+        };
+        if focus.first().copied().unwrap_or_default() == 0 {
+            continue;
+        }
+        let Ok(pids) = x11::get_window_pids(conn, window_id) else {
+            continue;
+        };
+        let Some(pid) = pids.first().copied() else {
+            continue;
+        };
+        if pids.iter().any(|pid| is_process_running(*pid)) {
+            continue;
+        }
+
+        log::warn!(
+            "Clearing stale input focus on window {} owned by dead process {}",
+            window_id,
+            pid
+        );
+        x11::remove_property(conn, window_id, input_focus.as_str())?;
+        events.push(WatchdogEvent::InputFocusCleared { window_id, pid });
+    }
+
+    Ok(events)
+}
+
+/// Returns true if a process with the given process ID exists
+pub(crate) fn is_process_running(pid: u32) -> bool {
+    Path::new(format!("/proc/{}", pid).as_str()).exists()
+}
@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use x11rb::protocol::xproto::GetGeometryReply;
+
+use crate::{
+    atoms::GamescopeAtom,
+    xwayland::{FrameExtents, MotifHints, Primary, WindowType, XWayland},
+};
+
+/// [Window] is a handle to a single X window on a specific [XWayland]
+/// instance. Using a [Window] instead of a bare window id guarantees that
+/// operations are always performed against the instance the window belongs to.
+#[derive(Debug, Clone, Copy)]
+pub struct Window<'a> {
+    xwayland: &'a XWayland,
+    id: u32,
+}
+
+impl<'a> Window<'a> {
+    /// Create a new window handle for the given window id on the given
+    /// XWayland instance.
+    pub fn new(xwayland: &'a XWayland, id: u32) -> Self {
+        Self { xwayland, id }
+    }
+
+    /// Returns the X window id of this window
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the XWayland instance this window belongs to
+    pub fn xwayland(&self) -> &'a XWayland {
+        self.xwayland
+    }
+
+    /// Returns the window name
+    pub fn name(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.xwayland.get_window_name(self.id)
+    }
+
+    /// Returns the process ID of the window from the '_NET_WM_PID' atom
+    pub fn pid(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.xwayland.get_window_pid(self.id)
+    }
+
+    /// Tries to discover the process IDs that are associated with the window
+    pub fn pids(&self) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        self.xwayland.get_pids_for_window(self.id)
+    }
+
+    /// Returns the currently set app ID on the window
+    pub fn app_id(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.xwayland.get_app_id(self.id)
+    }
+
+    /// Sets the app ID on the window
+    pub fn set_app_id(&self, app_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_app_id(self.id, app_id)
+    }
+
+    /// Returns whether or not the window has an app ID set
+    pub fn has_app_id(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.xwayland.has_app_id(self.id)
+    }
+
+    /// Asks Gamescope to run the display at the given refresh rate in Hz while
+    /// this window is focused
+    pub fn set_requested_refresh_rate(
+        &self,
+        refresh_rate: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland
+            .set_requested_refresh_rate(self.id, refresh_rate)
+    }
+
+    /// Asks Gamescope to use the EDID at the given path while this window is
+    /// focused
+    pub fn set_display_edid_path(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_display_edid_path(self.id, path)
+    }
+
+    /// Returns true if the window is override-redirect (E.g. a tooltip or menu)
+    pub fn is_override_redirect(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.xwayland.is_override_redirect(self.id)
+    }
+
+    /// Returns the decoration hints of the window, if it has any
+    pub fn motif_hints(&self) -> Result<Option<MotifHints>, Box<dyn std::error::Error>> {
+        self.xwayland.get_motif_hints(self.id)
+    }
+
+    /// Returns the type of the window from its _NET_WM_WINDOW_TYPE property
+    pub fn window_type(&self) -> Result<WindowType, Box<dyn std::error::Error>> {
+        self.xwayland.get_window_type(self.id)
+    }
+
+    /// Returns the window this window is a transient for, if any
+    pub fn transient_for(&self) -> Result<Option<Window<'a>>, Box<dyn std::error::Error>> {
+        let parent = self.xwayland.get_transient_for(self.id)?;
+        Ok(parent.map(|id| Window::new(self.xwayland, id)))
+    }
+
+    /// Returns the leader of the window group this window belongs to, if any
+    pub fn group_leader(&self) -> Result<Option<Window<'a>>, Box<dyn std::error::Error>> {
+        let leader = self.xwayland.get_window_group(self.id)?;
+        Ok(leader.map(|id| Window::new(self.xwayland, id)))
+    }
+
+    /// Returns the direct children of the window
+    pub fn children(&self) -> Result<Vec<Window<'a>>, Box<dyn std::error::Error>> {
+        let children = self.xwayland.get_window_children(self.id)?;
+        Ok(children
+            .into_iter()
+            .map(|id| Window::new(self.xwayland, id))
+            .collect())
+    }
+
+    /// Gets the geometry of the window
+    pub fn geometry(&self) -> Result<GetGeometryReply, Box<dyn std::error::Error>> {
+        self.xwayland.get_geometry_for_window(self.id)
+    }
+
+    /// Gets the frame extents of the window, if it has any
+    pub fn frame_extents(&self) -> Result<Option<FrameExtents>, Box<dyn std::error::Error>> {
+        self.xwayland.get_frame_extents(self.id)
+    }
+
+    /// Returns true if the given property exists on the window
+    pub fn has_xprop(&self, key: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>> {
+        self.xwayland.has_xprop(self.id, key)
+    }
+
+    /// Returns the value(s) of the given property on the window
+    pub fn get_xprop(
+        &self,
+        key: GamescopeAtom,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        self.xwayland.get_xprop(self.id, key)
+    }
+
+    /// Sets the given property value(s) on the window
+    pub fn set_xprop(
+        &self,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_xprop(self.id, key, values)
+    }
+
+    /// Removes the given property from the window
+    pub fn remove_xprop(&self, key: GamescopeAtom) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.remove_xprop(self.id, key)
+    }
+
+    /// Returns the overlay status of the window
+    pub fn overlay(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.xwayland.get_overlay(self.id)
+    }
+
+    /// Sets the window as the overlay window
+    pub fn set_overlay(&self, value: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_overlay(self.id, value)
+    }
+
+    /// Sets the window as a notification
+    pub fn set_notification(&self, value: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_notification(self.id, value)
+    }
+
+    /// Sets the window as the primary overlay input focus
+    pub fn set_input_focus(&self, value: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_input_focus(self.id, value)
+    }
+
+    /// Sets the window as an external overlay
+    pub fn set_external_overlay(&self, value: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.xwayland.set_external_overlay(self.id, value)
+    }
+}
+
+impl PartialEq for Window<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self.xwayland.ptr_eq(other.xwayland)
+    }
+}
+
+impl Eq for Window<'_> {}
+
+/// [WindowIter] is a lazy breadth-first iterator over all descendants of a
+/// window. The children of a window are only queried from the X server once
+/// the iterator reaches them, so callers searching for a single window can stop
+/// early without walking the entire tree.
+pub struct WindowIter<'a> {
+    xwayland: &'a XWayland,
+    /// Windows that have been discovered but not yet yielded
+    ready: VecDeque<u32>,
+    /// Windows that have been yielded but whose children have not been queried
+    pending: VecDeque<u32>,
+}
+
+impl<'a> WindowIter<'a> {
+    /// Create a new iterator over all descendants of the given window
+    pub fn new(xwayland: &'a XWayland, window_id: u32) -> Self {
+        Self {
+            xwayland,
+            ready: VecDeque::new(),
+            pending: VecDeque::from([window_id]),
+        }
+    }
+}
+
+impl Iterator for WindowIter<'_> {
+    type Item = Result<u32, Box<dyn std::error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(window_id) = self.ready.pop_front() {
+                self.pending.push_back(window_id);
+                return Some(Ok(window_id));
+            }
+
+            // Query the children of the next window in line
+            let parent = self.pending.pop_front()?;
+            match self.xwayland.get_window_children(parent) {
+                Ok(children) => self.ready.extend(children),
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use x11rb::protocol::{
+    xproto::{EventMask, Property},
+    Event,
+};
+
+use crate::{atoms::GamescopeAtom, worker, xwayland::XWayland};
+
+/// Events emitted by a [WindowTracker]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AppWindowEvent {
+    /// A window with the given app id (STEAM_GAME) appeared, either because it
+    /// was created or because the app id was set on it
+    AppWindowAppeared { app_id: u32, window_id: u32 },
+    /// A window with the given app id went away, either because it was
+    /// destroyed or because its app id was changed or removed
+    AppWindowClosed { app_id: u32, window_id: u32 },
+}
+
+/// [WindowTracker] keeps a live map of app ids (STEAM_GAME) to the top-level
+/// windows of an instance by following window creation, destruction and
+/// changes to STEAM_GAME, so session managers do not need to poll for the
+/// windows of an app. Clones share the same map, so a clone can be passed to
+/// [WindowTracker::start] while the original is queried.
+#[derive(Debug, Clone, Default)]
+pub struct WindowTracker {
+    /// App id of each tracked window
+    windows: Arc<Mutex<HashMap<u32, u32>>>,
+}
+
+impl WindowTracker {
+    /// Create a new, empty tracker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the app ids of the current top-level windows of the given
+    /// instance and starts following their changes. Returns a join handle of
+    /// the tracking thread and a receiver channel that receives an
+    /// [AppWindowEvent::AppWindowAppeared] for every window that already
+    /// exists, followed by every change.
+    pub fn start(
+        &self,
+        xwayland: &XWayland,
+    ) -> Result<(JoinHandle<()>, Receiver<AppWindowEvent>), Box<dyn std::error::Error>> {
+        let root = xwayland.get_root_window_id()?;
+
+        // Subscribe before reading the windows so no change is missed
+        let (events_tx, events_rx) = mpsc::channel();
+        let events_tx = Arc::new(events_tx);
+        xwayland.subscribe_events(root, EventMask::SUBSTRUCTURE_NOTIFY, events_tx.clone())?;
+        let windows = xwayland.get_window_children(root)?;
+        for window_id in windows.iter() {
+            let result = xwayland.subscribe_events(
+                *window_id,
+                EventMask::PROPERTY_CHANGE,
+                events_tx.clone(),
+            );
+            // The window may be destroyed in the meantime
+            if let Err(err) = result {
+                log::debug!("Unable to follow window {}: {}", window_id, err);
+            }
+        }
+        let app_ids = xwayland.get_xprop_batch(&windows, GamescopeAtom::SteamGame)?;
+
+        let (tx, rx): (Sender<AppWindowEvent>, Receiver<AppWindowEvent>) = mpsc::channel();
+        for (window_id, app_id) in windows.into_iter().zip(app_ids) {
+            let app_id = app_id.and_then(|value| value.first().copied());
+            for event in self.set_app_id(window_id, app_id) {
+                tx.send(event)?;
+            }
+        }
+
+        // Only the event loop keeps the sender alive, so the thread exits when
+        // the event loop stops
+        let subscriber = Arc::downgrade(&events_tx);
+        drop(events_tx);
+
+        let tracker = self.clone();
+        let xwayland = xwayland.clone();
+        let options = xwayland.get_connect_options();
+        let handle = worker::spawn(&xwayland.get_name(), "tracker", &options, move || {
+            for (conn, atoms, event) in events_rx {
+                let events = match event {
+                    Event::CreateNotify(event) => {
+                        let Some(events_tx) = subscriber.upgrade() else {
+                            break;
+                        };
+                        let mask = EventMask::PROPERTY_CHANGE;
+                        if let Err(err) = xwayland.subscribe_events(event.window, mask, events_tx) {
+                            log::debug!("Unable to follow window {}: {}", event.window, err);
+                            continue;
+                        }
+                        // STEAM_GAME may have been set before subscribing
+                        let Ok(app_id) = xwayland.get_app_id(event.window) else {
+                            continue;
+                        };
+                        tracker.set_app_id(event.window, app_id)
+                    }
+                    Event::DestroyNotify(event) => tracker.remove_window(event.window),
+                    Event::PropertyNotify(event) => {
+                        let atom = [GamescopeAtom::SteamGame];
+                        match atoms.matches(conn.as_ref(), event.atom, &atom) {
+                            Ok(true) => (),
+                            Ok(false) => continue,
+                            Err(err) => {
+                                log::error!("Error resolving atom: {}", err);
+                                continue;
+                            }
+                        }
+                        let app_id = if event.state == Property::DELETE {
+                            None
+                        } else {
+                            let Ok(app_id) = xwayland.get_app_id(event.window) else {
+                                continue;
+                            };
+                            app_id
+                        };
+                        tracker.set_app_id(event.window, app_id)
+                    }
+                    _ => continue,
+                };
+
+                for event in events {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok((handle, rx))
+    }
+
+    /// Returns the tracked windows of every app, sorted by window id
+    pub fn app_windows(&self) -> HashMap<u32, Vec<u32>> {
+        let Ok(windows) = self.windows.lock() else {
+            return HashMap::new();
+        };
+        let mut apps: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (window_id, app_id) in windows.iter() {
+            apps.entry(*app_id).or_default().push(*window_id);
+        }
+        for windows in apps.values_mut() {
+            windows.sort_unstable();
+        }
+
+        apps
+    }
+
+    /// Returns the tracked windows of the given app, sorted by window id
+    pub fn windows_for_app(&self, app_id: u32) -> Vec<u32> {
+        self.app_windows().remove(&app_id).unwrap_or_default()
+    }
+
+    /// Returns the app id of the given window if it is tracked
+    pub fn app_id_for_window(&self, window_id: u32) -> Option<u32> {
+        let windows = self.windows.lock().ok()?;
+        windows.get(&window_id).copied()
+    }
+
+    /// Updates the app id of the given window and returns the resulting events
+    fn set_app_id(&self, window_id: u32, app_id: Option<u32>) -> Vec<AppWindowEvent> {
+        let Ok(mut windows) = self.windows.lock() else {
+            return Vec::new();
+        };
+        let previous = match app_id {
+            Some(app_id) => windows.insert(window_id, app_id),
+            None => windows.remove(&window_id),
+        };
+        if previous == app_id {
+            return Vec::new();
+        }
+
+        let closed = previous.map(|app_id| AppWindowEvent::AppWindowClosed { app_id, window_id });
+        let appeared = app_id.map(|app_id| AppWindowEvent::AppWindowAppeared { app_id, window_id });
+        closed.into_iter().chain(appeared).collect()
+    }
+
+    /// Forgets the given destroyed window and returns the resulting events
+    fn remove_window(&self, window_id: u32) -> Vec<AppWindowEvent> {
+        self.set_app_id(window_id, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_tracker_events() {
+        let tracker = WindowTracker::new();
+
+        assert_eq!(
+            tracker.set_app_id(10, Some(620)),
+            vec![AppWindowEvent::AppWindowAppeared {
+                app_id: 620,
+                window_id: 10
+            }]
+        );
+        assert!(tracker.set_app_id(10, Some(620)).is_empty());
+        assert!(tracker.set_app_id(11, None).is_empty());
+        tracker.set_app_id(12, Some(620));
+        assert_eq!(tracker.windows_for_app(620), vec![10, 12]);
+
+        assert_eq!(
+            tracker.set_app_id(10, Some(769)),
+            vec![
+                AppWindowEvent::AppWindowClosed {
+                    app_id: 620,
+                    window_id: 10
+                },
+                AppWindowEvent::AppWindowAppeared {
+                    app_id: 769,
+                    window_id: 10
+                }
+            ]
+        );
+        assert_eq!(
+            tracker.remove_window(12),
+            vec![AppWindowEvent::AppWindowClosed {
+                app_id: 620,
+                window_id: 12
+            }]
+        );
+        assert!(tracker.windows_for_app(620).is_empty());
+        assert_eq!(tracker.app_id_for_window(10), Some(769));
+    }
+}
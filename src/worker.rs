@@ -0,0 +1,110 @@
+use std::io;
+use std::thread::{self, JoinHandle, Scope, ScopedJoinHandle};
+
+use crate::connection::ConnectOptions;
+
+/// Maximum length of a thread name on Linux, without the trailing nul byte
+const MAX_THREAD_NAME_LEN: usize = 15;
+
+/// Spawns a background thread for the given display. The thread is named
+/// "gs/<display>/<role>" (E.g. "gs/:1/events") so it can be told apart in
+/// profilers, and runs with the thread priority from the given options. Names
+/// longer than the 15 bytes Linux keeps for a thread are cut off.
+pub(crate) fn spawn<F, T>(
+    display: &str,
+    role: &str,
+    options: &ConnectOptions,
+    f: F,
+) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let priority = options.thread_priority;
+    thread::Builder::new()
+        .name(thread_name(display, role))
+        .spawn(move || {
+            apply_priority(priority);
+            f()
+        })
+        .expect("failed to spawn thread")
+}
+
+/// Spawns a scoped thread for the given display, named and prioritized like
+/// the threads started with [spawn]
+pub(crate) fn spawn_scoped<'scope, 'env, F, T>(
+    scope: &'scope Scope<'scope, 'env>,
+    display: &str,
+    role: &str,
+    options: &ConnectOptions,
+    f: F,
+) -> ScopedJoinHandle<'scope, T>
+where
+    F: FnOnce() -> T + Send + 'scope,
+    T: Send + 'scope,
+{
+    let priority = options.thread_priority;
+    thread::Builder::new()
+        .name(thread_name(display, role))
+        .spawn_scoped(scope, move || {
+            apply_priority(priority);
+            f()
+        })
+        .expect("failed to spawn thread")
+}
+
+/// Sets the priority of the calling thread if one is configured
+fn apply_priority(priority: Option<i32>) {
+    let Some(nice) = priority else {
+        return;
+    };
+    if let Err(err) = set_current_thread_priority(nice) {
+        let current = thread::current();
+        let name = current.name().unwrap_or_default();
+        log::warn!("Unable to set priority of thread {}: {}", name, err);
+    }
+}
+
+/// Returns the name of the thread with the given role for the given display,
+/// trimmed to the 15 bytes Linux keeps for a thread name
+fn thread_name(display: &str, role: &str) -> String {
+    let mut name = format!("gs/{}/{}", display, role);
+    let mut len = name.len().min(MAX_THREAD_NAME_LEN);
+    while !name.is_char_boundary(len) {
+        len -= 1;
+    }
+    name.truncate(len);
+    name
+}
+
+/// Sets the nice value of the calling thread. Raising the priority above the
+/// one of the process requires CAP_SYS_NICE.
+fn set_current_thread_priority(nice: i32) -> io::Result<()> {
+    // On Linux the nice value is per thread when addressed by thread id
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawn_names_thread() {
+        let options = ConnectOptions {
+            thread_priority: Some(19),
+            ..Default::default()
+        };
+        let handle = spawn(":1", "events", &options, || {
+            thread::current().name().map(|name| name.to_string())
+        });
+        let name = handle.join().unwrap();
+        assert_eq!(name.as_deref(), Some("gs/:1/events"));
+        assert_eq!(thread_name(":1", "notification"), "gs/:1/notificat");
+        assert_eq!(thread_name(":1", "watchdogé"), "gs/:1/watchdog");
+    }
+}
@@ -1,13 +1,33 @@
+use std::collections::HashMap;
+
 use x11rb::{
     connection::Connection,
+    errors::ReplyError,
+    properties::WmHints,
     protocol::{
         res::{ClientIdMask, ClientIdSpec},
-        xproto::{intern_atom, AtomEnum, ConnectionExt, InputFocus, PropMode},
+        xproto::{intern_atom, AtomEnum, ConnectionExt, GetPropertyReply, InputFocus, PropMode},
+        ErrorKind,
     },
     CURRENT_TIME,
 };
 
-use crate::atoms::GamescopeAtom;
+use crate::{
+    atoms::{AtomType, GamescopeAtom},
+    extension::{self, Extension},
+    input::InputEvent,
+    property::PropertyValue,
+    xwayland::WindowRect,
+};
+
+/// Returns true if the given error is a BadWindow error reported by the X
+/// server (E.g. because the window was destroyed in the meantime)
+pub fn is_bad_window(err: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        err.downcast_ref::<ReplyError>(),
+        Some(ReplyError::X11Error(error)) if error.error_kind == ErrorKind::Window
+    )
+}
 
 /// Returns true if the given X server connection is a gamescope xwayland
 pub fn is_gamescope_xwayland<F>(
@@ -32,23 +52,81 @@ pub fn get_string_property<F>(
 where
     F: Connection,
 {
-    let atom = intern_atom(&conn, false, key.as_bytes())?;
-    let atom = atom.reply()?;
+    let mut values = get_string_properties_batch(conn, &[(window_id, key)])?;
+    Ok(values.pop().flatten())
+}
 
-    // Request the property from the X server
-    let response = conn.get_property(false, window_id, atom.atom, AtomEnum::STRING, 0, 8);
-    let value = response?.reply()?;
+/// Returns the string values of the given (window, property) pairs. All
+/// requests are sent before any reply is read, so the whole batch only costs
+/// two round trips to the X server.
+pub fn get_string_properties_batch<F>(
+    conn: F,
+    requests: &[(u32, &str)],
+) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let keys: Vec<&str> = requests.iter().map(|(_, key)| *key).collect();
+    let atoms = intern_atoms(&conn, keys.as_slice())?;
 
-    // Check to see if there was a value returned
-    if value.value_len == 0 {
-        return Ok(None);
+    // Request all properties from the X server
+    let mut cookies = Vec::with_capacity(requests.len());
+    for ((window_id, _), atom) in requests.iter().zip(atoms) {
+        cookies.push(conn.get_property(false, *window_id, atom, AtomEnum::STRING, 0, 8)?);
+    }
+
+    let mut values: Vec<Option<String>> = Vec::with_capacity(cookies.len());
+    for cookie in cookies {
+        let value = cookie.reply()?;
+
+        // Check to see if there was a value returned
+        if value.value_len == 0 {
+            values.push(None);
+            continue;
+        }
+
+        let Some(bytes) = value.value8() else {
+            values.push(None);
+            continue;
+        };
+        values.push(Some(String::from_utf8(bytes.collect())?));
     }
 
-    let values: Vec<u8> = value.value8().unwrap().collect();
-    Ok(Some(String::from_utf8(values)?))
+    Ok(values)
 }
 
-/// Returns true if the given window has the given property
+/// Interns all of the given atom names. All requests are sent before any reply
+/// is read, so the whole batch only costs a single round trip.
+pub fn intern_atoms<F>(conn: F, keys: &[&str]) -> Result<Vec<u32>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let mut cookies = Vec::with_capacity(keys.len());
+    for key in keys {
+        cookies.push(intern_atom(&conn, false, key.as_bytes())?);
+    }
+
+    let mut atoms: Vec<u32> = Vec::with_capacity(cookies.len());
+    for cookie in cookies {
+        atoms.push(cookie.reply()?.atom);
+    }
+
+    Ok(atoms)
+}
+
+/// Returns true if the given atom was interned on the X server. Gamescope
+/// interns all atoms it supports on startup, so this can be used to detect if
+/// a feature is supported.
+pub fn atom_exists<F>(conn: F, key: &str) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atom = intern_atom(&conn, true, key.as_bytes())?.reply()?.atom;
+    Ok(atom != u32::from(AtomEnum::NONE))
+}
+
+/// Returns true if the given window has the given property, regardless of
+/// its type
 pub fn has_property<F>(
     conn: F,
     window_id: u32,
@@ -57,11 +135,172 @@ pub fn has_property<F>(
 where
     F: Connection,
 {
-    Ok(get_property(conn, window_id, key)?.is_some())
+    Ok(get_any_property(conn, window_id, key)?.is_some())
+}
+
+/// Returns true if the given window has the given property atom, regardless
+/// of its type
+pub fn has_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    Ok(get_any_property_by_atom(conn, window_id, atom)?.is_some())
+}
+
+/// Returns the value of the given x property on the given window, decoded
+/// based on the property type reported by the X server.
+pub fn get_any_property<F>(
+    conn: F,
+    window_id: u32,
+    key: &str,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    get_any_property_by_atom(conn, window_id, atom)
+}
+
+/// Returns the value of the given x property atom on the given window,
+/// decoded based on the property type reported by the X server.
+pub fn get_any_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    // Request the property from the X server
+    let response = conn.get_property(false, window_id, atom, AtomEnum::ANY, 0, u32::MAX);
+    let reply = response?.reply()?;
+
+    // Check to see if the property exists
+    let kind = reply.type_;
+    if kind == u32::from(AtomEnum::NONE) {
+        return Ok(None);
+    }
+
+    // Decode the value based on its type
+    let value = if kind == u32::from(AtomEnum::CARDINAL) || kind == u32::from(AtomEnum::INTEGER) {
+        property_values(&reply).map(PropertyValue::Cardinal)
+    } else if kind == u32::from(AtomEnum::WINDOW) {
+        property_values(&reply).map(PropertyValue::Window)
+    } else if kind == u32::from(AtomEnum::ATOM) {
+        match reply.value32() {
+            Some(atoms) => {
+                let mut names: Vec<String> = Vec::new();
+                for atom in atoms {
+                    names.push(get_atom_name(&conn, atom)?);
+                }
+                Some(PropertyValue::Atom(names))
+            }
+            None => None,
+        }
+    } else if kind == u32::from(AtomEnum::STRING) || get_atom_name(&conn, kind)? == "UTF8_STRING" {
+        let value = String::from_utf8_lossy(&reply.value).to_string();
+        Some(PropertyValue::String(value))
+    } else if reply.format == 8 {
+        None
+    } else {
+        // Other types like WM_HINTS are structs of 16- or 32-bit numbers
+        property_values(&reply).map(PropertyValue::Cardinal)
+    };
+
+    Ok(Some(value.unwrap_or(PropertyValue::Bytes(reply.value))))
+}
+
+/// Returns the value of the given x property on the given window decoded as
+/// the given type, regardless of the type the property was set with.
+pub fn get_typed_property<F>(
+    conn: F,
+    window_id: u32,
+    key: &str,
+    atom_type: AtomType,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    get_typed_property_by_atom(conn, window_id, atom, atom_type)
+}
+
+/// Returns the value of the given x property atom on the given window decoded
+/// as the given type, regardless of the type the property was set with.
+pub fn get_typed_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    atom_type: AtomType,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    // Request the property from the X server
+    let response = conn.get_property(false, window_id, atom, AtomEnum::ANY, 0, u32::MAX);
+    let reply = response?.reply()?;
+
+    // Check to see if the property exists
+    if reply.type_ == u32::from(AtomEnum::NONE) {
+        return Ok(None);
+    }
+
+    let value = match atom_type {
+        AtomType::Cardinal => property_values(&reply).map(PropertyValue::Cardinal),
+        AtomType::Window => property_values(&reply).map(PropertyValue::Window),
+        AtomType::String => {
+            let value = String::from_utf8_lossy(&reply.value);
+            Some(PropertyValue::String(
+                value.trim_end_matches('\0').to_string(),
+            ))
+        }
+    };
+
+    Ok(Some(value.unwrap_or(PropertyValue::Bytes(reply.value))))
+}
+
+/// Returns the value of the given x property on the given window. Known
+/// Gamescope properties are decoded according to their [AtomType] and all
+/// others according to the type reported by the X server.
+pub fn get_decoded_property<F>(
+    conn: F,
+    window_id: u32,
+    key: &str,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    match GamescopeAtom::from_property_name(key) {
+        Some(atom) => get_typed_property(conn, window_id, key, atom.atom_type()),
+        None => get_any_property(conn, window_id, key),
+    }
+}
+
+/// Returns the value of the given x property on the given window like
+/// [get_decoded_property], using the already known atom of the property
+/// instead of interning its name.
+pub fn get_decoded_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    key: &str,
+) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    match GamescopeAtom::from_property_name(key) {
+        Some(key) => get_typed_property_by_atom(conn, window_id, atom, key.atom_type()),
+        None => get_any_property_by_atom(conn, window_id, atom),
+    }
 }
 
-/// Returns the value of the given x property on the given window.
-/// TODO: We assume everything is a cardinal
+/// Returns the numeric value(s) of the given x property on the given window.
+/// CARDINAL, INTEGER and WINDOW properties are read, any other type is
+/// returned as None.
 pub fn get_property<F>(
     conn: F,
     window_id: u32,
@@ -70,20 +309,138 @@ pub fn get_property<F>(
 where
     F: Connection,
 {
-    let atom = intern_atom(&conn, false, key.as_bytes())?;
-    let atom = atom.reply()?;
+    let mut values = get_properties_batch(conn, &[(window_id, key)])?;
+    Ok(values.pop().flatten())
+}
 
-    // Request the property from the X server
-    let response = conn.get_property(false, window_id, atom.atom, AtomEnum::CARDINAL, 0, u32::MAX);
-    let value = response?.reply()?;
+/// Returns the value of the given x property atom on the given window.
+pub fn get_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let mut values = get_atom_properties_batch(conn, &[(window_id, atom)])?;
+    Ok(values.pop().flatten())
+}
 
-    // Check to see if there was a value returned
-    if value.value_len == 0 {
+/// Returns the values of the given (window, property) pairs. All requests are
+/// sent before any reply is read, so the whole batch only costs two round
+/// trips to the X server.
+pub fn get_properties_batch<F>(
+    conn: F,
+    requests: &[(u32, &str)],
+) -> Result<Vec<Option<Vec<u32>>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let keys: Vec<&str> = requests.iter().map(|(_, key)| *key).collect();
+    let atoms = intern_atoms(&conn, keys.as_slice())?;
+    let requests: Vec<(u32, u32)> = requests
+        .iter()
+        .zip(atoms)
+        .map(|((window_id, _), atom)| (*window_id, atom))
+        .collect();
+
+    get_atom_properties_batch(conn, requests.as_slice())
+}
+
+/// Returns the numeric values of the given (window, atom) pairs like
+/// [get_property]. All requests are sent before any reply is read.
+pub fn get_atom_properties_batch<F>(
+    conn: F,
+    requests: &[(u32, u32)],
+) -> Result<Vec<Option<Vec<u32>>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    // Request all properties from the X server
+    let mut cookies = Vec::with_capacity(requests.len());
+    for (window_id, atom) in requests {
+        cookies.push(conn.get_property(false, *window_id, *atom, AtomEnum::ANY, 0, u32::MAX)?);
+    }
+
+    let numeric = [AtomEnum::CARDINAL, AtomEnum::INTEGER, AtomEnum::WINDOW];
+    let mut values: Vec<Option<Vec<u32>>> = Vec::with_capacity(cookies.len());
+    for cookie in cookies {
+        let value = cookie.reply()?;
+
+        // Check to see if there was a numeric value returned
+        let is_numeric = numeric.iter().any(|kind| u32::from(*kind) == value.type_);
+        if !is_numeric || value.value_len == 0 {
+            values.push(None);
+            continue;
+        }
+
+        values.push(property_values(&value));
+    }
+
+    Ok(values)
+}
+
+/// Returns the window ids stored in the given x property atom on the given
+/// window. Both WINDOW and CARDINAL properties are read, since clients differ
+/// in the type they store window ids with.
+pub fn get_window_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let response = conn.get_property(false, window_id, atom, AtomEnum::ANY, 0, u32::MAX);
+    let reply = response?.reply()?;
+
+    let kind = reply.type_;
+    if kind != u32::from(AtomEnum::WINDOW) && kind != u32::from(AtomEnum::CARDINAL) {
         return Ok(None);
     }
+    if reply.value_len == 0 {
+        return Ok(None);
+    }
+
+    Ok(property_values(&reply))
+}
+
+/// Returns the names of the atoms stored in the given ATOM x property atom
+/// (E.g. WM_PROTOCOLS) on the given window
+pub fn get_atom_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let response = conn.get_property(false, window_id, atom, AtomEnum::ATOM, 0, u32::MAX);
+    let reply = response?.reply()?;
+
+    if reply.value_len == 0 {
+        return Ok(None);
+    }
+    let Some(atoms) = reply.value32() else {
+        return Ok(None);
+    };
+    let atoms: Vec<u32> = atoms.collect();
 
-    let values: Vec<u32> = value.value32().unwrap().collect();
-    Ok(Some(values))
+    Ok(Some(get_atom_names(&conn, atoms.as_slice())?))
+}
+
+/// Returns the values of the given property reply widened to 32 bits,
+/// regardless of whether the property was stored with an 8, 16 or 32 bit
+/// format. Returns None if the reply has an unknown format.
+pub fn property_values(reply: &GetPropertyReply) -> Option<Vec<u32>> {
+    match reply.format {
+        8 => reply.value8().map(|values| values.map(u32::from).collect()),
+        16 => reply
+            .value16()
+            .map(|values| values.map(u32::from).collect()),
+        32 => reply.value32().map(|values| values.collect()),
+        _ => None,
+    }
 }
 
 /// Sets the value(s) of the given x property on the given window.
@@ -99,6 +456,73 @@ where
     change_property(conn, window_id, key, values, PropMode::REPLACE)
 }
 
+/// Sets the value(s) of the given x property atom on the given window.
+pub fn set_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    values: Vec<u32>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    change_property_by_atom(conn, window_id, atom, values, PropMode::REPLACE)
+}
+
+/// Sets the given string x property on the given window
+pub fn set_string_property<F>(
+    conn: F,
+    window_id: u32,
+    key: &str,
+    value: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    set_string_property_by_atom(conn, window_id, atom, value)
+}
+
+/// Sets the given string x property atom on the given window
+pub fn set_string_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    value: &str,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    set_bytes_property_by_atom(
+        conn,
+        window_id,
+        atom,
+        AtomEnum::STRING.into(),
+        value.as_bytes(),
+    )
+}
+
+/// Sets the given 8-bit x property atom on the given window to the given bytes
+/// with the given type atom (E.g. STRING or UTF8_STRING)
+pub fn set_bytes_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    kind: u32,
+    value: &[u8],
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    use x11rb::wrapper::ConnectionExt;
+
+    // Request setting the property
+    let result = conn.change_property8(PropMode::REPLACE, window_id, atom, kind, value)?;
+    result.check()?;
+
+    Ok(())
+}
+
 /// Append the value(s) of the given x property on the given window.
 #[allow(dead_code)]
 pub fn append_property<F>(
@@ -138,19 +562,63 @@ pub fn change_property<F>(
 where
     F: Connection,
 {
-    use x11rb::wrapper::ConnectionExt;
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    change_property_by_atom(conn, window_id, atom, values, mode)
+}
 
-    let atom = intern_atom(&conn, false, key.as_bytes())?;
-    let atom = atom.reply()?;
+/// Change the value(s) of the given x property atom on the given window.
+pub fn change_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    values: Vec<u32>,
+    mode: PropMode,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    change_typed_property_by_atom(conn, window_id, atom, AtomEnum::CARDINAL, values, mode)
+}
 
-    // Request setting the property
-    let result = conn.change_property32(
-        mode,
+/// Sets the given window id(s) as the value of the given WINDOW typed x
+/// property on the given window.
+pub fn set_window_property<F>(
+    conn: F,
+    window_id: u32,
+    key: &str,
+    values: Vec<u32>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    change_typed_property_by_atom(
+        conn,
         window_id,
-        atom.atom,
-        AtomEnum::CARDINAL,
-        values.as_slice(),
-    )?;
+        atom,
+        AtomEnum::WINDOW,
+        values,
+        PropMode::REPLACE,
+    )
+}
+
+/// Change the value(s) of the given x property atom on the given window,
+/// writing them with the given type.
+fn change_typed_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+    kind: AtomEnum,
+    values: Vec<u32>,
+    mode: PropMode,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    use x11rb::wrapper::ConnectionExt;
+
+    // Request setting the property
+    let result = conn.change_property32(mode, window_id, atom, kind, values.as_slice())?;
     result.check()?;
 
     Ok(())
@@ -165,10 +633,20 @@ pub fn remove_property<F>(
 where
     F: Connection,
 {
-    let atom = intern_atom(&conn, false, key.as_bytes())?;
-    let atom = atom.reply()?;
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    remove_property_by_atom(conn, window_id, atom)
+}
 
-    let result = conn.delete_property(window_id, atom.atom)?;
+/// Remove the given x property atom from the given window.
+pub fn remove_property_by_atom<F>(
+    conn: F,
+    window_id: u32,
+    atom: u32,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let result = conn.delete_property(window_id, atom)?;
     result.check()?;
 
     Ok(())
@@ -184,24 +662,217 @@ where
     F: Connection,
 {
     let results = conn.list_properties(window_id)?.reply()?;
-    let mut properties: Vec<String> = Vec::new();
+    get_atom_names(conn, results.atoms.as_slice())
+}
+
+/// Returns all cardinal properties on the given window along with their values.
+/// Properties of other types are skipped.
+pub fn get_properties<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<HashMap<String, Vec<u32>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atoms = conn.list_properties(window_id)?.reply()?.atoms;
+    let names = get_atom_names(&conn, atoms.as_slice())?;
+    let requests: Vec<(u32, u32)> = atoms.iter().map(|atom| (window_id, *atom)).collect();
+    let values = get_atom_properties_batch(&conn, requests.as_slice())?;
+
+    let properties = names
+        .into_iter()
+        .zip(values)
+        .filter_map(|(name, value)| value.map(|value| (name, value)))
+        .collect();
+
+    Ok(properties)
+}
 
-    for atom in results.atoms {
-        let name = conn.get_atom_name(atom)?.reply()?.name;
-        let name = String::from_utf8(name)?;
-        properties.push(name);
+/// Returns the raw value of every property on the given window, keyed by atom,
+/// regardless of the property type.
+pub fn get_raw_properties<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<HashMap<u32, Vec<u8>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let atoms = conn.list_properties(window_id)?.reply()?.atoms;
+
+    // Request all properties before reading any of the replies
+    let mut cookies = Vec::with_capacity(atoms.len());
+    for atom in atoms.iter() {
+        cookies.push(conn.get_property(false, window_id, *atom, AtomEnum::ANY, 0, u32::MAX)?);
+    }
+
+    let mut properties: HashMap<u32, Vec<u8>> = HashMap::new();
+    for (atom, cookie) in atoms.into_iter().zip(cookies) {
+        properties.insert(atom, cookie.reply()?.value);
     }
 
     Ok(properties)
 }
 
-/// Uses XRes to determine the given Window's PID
+/// Returns the name of the given atom
+pub fn get_atom_name<F>(conn: F, atom: u32) -> Result<String, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let name = conn.get_atom_name(atom)?.reply()?.name;
+    Ok(String::from_utf8(name)?)
+}
+
+/// Returns all descendants of the given window in depth-first order. The tree
+/// is queried one level at a time and all requests of a level are sent before
+/// any reply is read, so this costs one round trip per level of the tree
+/// instead of one per window.
+pub fn get_all_windows<F>(conn: F, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut level = vec![window_id];
+    while !level.is_empty() {
+        let mut cookies = Vec::with_capacity(level.len());
+        for window in level.iter() {
+            cookies.push(conn.query_tree(*window)?);
+        }
+
+        let mut next = Vec::new();
+        for (window, cookie) in level.into_iter().zip(cookies) {
+            let tree = cookie.reply()?.children;
+            next.extend_from_slice(tree.as_slice());
+            children.insert(window, tree);
+        }
+        level = next;
+    }
+
+    Ok(flatten_window_tree(&children, window_id))
+}
+
+/// Returns the descendants of the given window in the given map of children
+/// in depth-first order
+fn flatten_window_tree(children: &HashMap<u32, Vec<u32>>, window_id: u32) -> Vec<u32> {
+    let mut windows = Vec::new();
+    let mut stack: Vec<u32> = Vec::new();
+    if let Some(root) = children.get(&window_id) {
+        stack.extend(root.iter().rev());
+    }
+    while let Some(window) = stack.pop() {
+        windows.push(window);
+        if let Some(next) = children.get(&window) {
+            stack.extend(next.iter().rev());
+        }
+    }
+
+    windows
+}
+
+/// Returns the names of all of the given atoms. All requests are sent before
+/// any reply is read.
+pub fn get_atom_names<F>(conn: F, atoms: &[u32]) -> Result<Vec<String>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let mut cookies = Vec::with_capacity(atoms.len());
+    for atom in atoms {
+        cookies.push(conn.get_atom_name(*atom)?);
+    }
+
+    let mut names: Vec<String> = Vec::with_capacity(cookies.len());
+    for cookie in cookies {
+        names.push(String::from_utf8(cookie.reply()?.name)?);
+    }
+
+    Ok(names)
+}
+
+/// Returns the position and size relative to the given root window of each of
+/// the given windows, or None for windows that no longer exist. All requests
+/// are sent before any reply is read.
+pub fn get_root_geometries<F>(
+    conn: F,
+    root_window_id: u32,
+    window_ids: &[u32],
+) -> Result<Vec<Option<WindowRect>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let mut cookies = Vec::with_capacity(window_ids.len());
+    for window_id in window_ids {
+        let geometry = conn.get_geometry(*window_id)?;
+        let position = conn.translate_coordinates(*window_id, root_window_id, 0, 0)?;
+        cookies.push((geometry, position));
+    }
+
+    let rects = cookies
+        .into_iter()
+        .map(|(geometry, position)| {
+            // Both requests fail if the window was destroyed in the meantime
+            let (Ok(geometry), Ok(position)) = (geometry.reply(), position.reply()) else {
+                return None;
+            };
+            Some(WindowRect {
+                x: position.dst_x,
+                y: position.dst_y,
+                width: geometry.width,
+                height: geometry.height,
+            })
+        })
+        .collect();
+
+    Ok(rects)
+}
+
+/// Sends the given synthetic input event to the given window using XTEST.
+/// Key events are delivered to the window with input focus, so the window is
+/// focused first. Returns an [ExtensionMissing](crate::extension::ExtensionMissing)
+/// error if the X server does not provide XTEST.
+pub fn send_input<F>(
+    conn: F,
+    root_window_id: u32,
+    window_id: u32,
+    event: InputEvent,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    use x11rb::protocol::xtest::ConnectionExt;
+    extension::require_extension(&conn, Extension::XTest)?;
+
+    if event.is_key() {
+        conn.set_input_focus(InputFocus::PARENT, window_id, CURRENT_TIME)?
+            .check()?;
+    }
+
+    // Motion is given relative to the window but XTEST expects root coordinates
+    let (x, y) = match event {
+        InputEvent::Motion { x, y } => {
+            let reply = conn
+                .translate_coordinates(window_id, root_window_id, x, y)?
+                .reply()?;
+            (reply.dst_x, reply.dst_y)
+        }
+        _ => (0, 0),
+    };
+
+    let (kind, detail) = (event.event_type(), event.detail());
+    conn.xtest_fake_input(kind, detail, CURRENT_TIME, root_window_id, x, y, 0)?
+        .check()?;
+
+    Ok(())
+}
+
+/// Uses XRes to determine the given Window's PID. Returns an
+/// [ExtensionMissing](crate::extension::ExtensionMissing) error if the X
+/// server does not provide XRes.
 #[allow(dead_code)]
 pub fn get_window_pids<F>(conn: F, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>>
 where
     F: Connection,
 {
     use x11rb::protocol::res::ConnectionExt;
+    extension::require_extension(&conn, Extension::XRes)?;
     let spec = ClientIdSpec {
         client: window_id,
         mask: ClientIdMask::LOCAL_CLIENT_PID,
@@ -229,6 +900,101 @@ where
     Ok(())
 }
 
+/// Returns the instance and class names from the WM_CLASS property of the given
+/// window
+pub fn get_window_class<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let response = conn.get_property(
+        false,
+        window_id,
+        AtomEnum::WM_CLASS,
+        AtomEnum::STRING,
+        0,
+        u32::MAX,
+    );
+    let value = response?.reply()?;
+
+    // Check to see if there was a value returned
+    if value.value_len == 0 {
+        return Ok(None);
+    }
+
+    // WM_CLASS consists of two consecutive null-terminated strings
+    let mut parts = value.value.split(|byte| *byte == 0);
+    let instance = String::from_utf8_lossy(parts.next().unwrap_or_default()).to_string();
+    let class = String::from_utf8_lossy(parts.next().unwrap_or_default()).to_string();
+    Ok(Some((instance, class)))
+}
+
+/// Returns the window the given window is a transient for (E.g. the parent of
+/// a dialog) from its WM_TRANSIENT_FOR property
+pub fn get_transient_for<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<Option<u32>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let response = conn.get_property(
+        false,
+        window_id,
+        AtomEnum::WM_TRANSIENT_FOR,
+        AtomEnum::WINDOW,
+        0,
+        1,
+    );
+    let value = response?.reply()?;
+
+    // A transient for the root or no window means there is no parent
+    let parent = value.value32().and_then(|mut values| values.next());
+    Ok(parent.filter(|parent| *parent != 0))
+}
+
+/// Returns the group leader of the given window from the window_group field of
+/// its WM_HINTS property
+pub fn get_window_group<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<Option<u32>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let hints = WmHints::get(&conn, window_id)?.reply()?;
+    Ok(hints.and_then(|hints| hints.window_group))
+}
+
+/// Returns true if the given window bypasses the window manager (E.g. tooltips
+/// and menus)
+pub fn is_override_redirect<F>(conn: F, window_id: u32) -> Result<bool, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let attributes = conn.get_window_attributes(window_id)?.reply()?;
+    Ok(attributes.override_redirect)
+}
+
+/// Returns the raw values of the _MOTIF_WM_HINTS property of the given window.
+/// The property has its own type, so it can't be read as a cardinal.
+pub fn get_motif_hints<F>(
+    conn: F,
+    window_id: u32,
+) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let key = GamescopeAtom::MotifWmHints.to_string();
+    let atom = intern_atom(&conn, false, key.as_bytes())?.reply()?.atom;
+    let response = conn.get_property(false, window_id, atom, AtomEnum::ANY, 0, 5);
+    let value = response?.reply()?;
+
+    Ok(property_values(&value))
+}
+
 // Returns the window name of the given window
 pub fn get_window_name<F>(
     conn: F,
@@ -239,3 +1005,55 @@ where
 {
     get_string_property(conn, window_id, "WM_NAME")
 }
+
+/// Returns the window names of all of the given windows
+pub fn get_window_names<F>(
+    conn: F,
+    window_ids: &[u32],
+) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>>
+where
+    F: Connection,
+{
+    let requests: Vec<(u32, &str)> = window_ids.iter().map(|id| (*id, "WM_NAME")).collect();
+    get_string_properties_batch(conn, requests.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_property_values() {
+        let reply = |format: u8, value: Vec<u8>| GetPropertyReply {
+            format,
+            sequence: 0,
+            length: 0,
+            type_: AtomEnum::CARDINAL.into(),
+            bytes_after: 0,
+            value_len: 0,
+            value,
+        };
+
+        assert_eq!(property_values(&reply(8, vec![1, 255])), Some(vec![1, 255]));
+        let value16 = [300u16, 2].iter().flat_map(|v| v.to_ne_bytes()).collect();
+        assert_eq!(property_values(&reply(16, value16)), Some(vec![300, 2]));
+        let value32 = 70000u32.to_ne_bytes().to_vec();
+        assert_eq!(property_values(&reply(32, value32)), Some(vec![70000]));
+        assert_eq!(property_values(&reply(0, Vec::new())), None);
+    }
+
+    #[test]
+    fn test_flatten_window_tree() {
+        let children = HashMap::from([
+            (1, vec![2, 5]),
+            (2, vec![3, 4]),
+            (3, vec![]),
+            (4, vec![]),
+            (5, vec![6]),
+            (6, vec![]),
+        ]);
+        assert_eq!(flatten_window_tree(&children, 1), vec![2, 3, 4, 5, 6]);
+        assert_eq!(flatten_window_tree(&children, 5), vec![6]);
+        assert!(flatten_window_tree(&children, 7).is_empty());
+    }
+}
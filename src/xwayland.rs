@@ -1,41 +1,206 @@
+use std::any::Any;
+use std::collections::HashMap;
 use std::fmt;
-use std::sync::mpsc::{self, Receiver};
+use std::marker::PhantomData;
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use std::thread::{self, JoinHandle};
-use std::{borrow::Borrow, sync::mpsc::Sender};
+use std::time::{Duration, Instant};
 
+use x11rb::connection::Connection;
 use x11rb::protocol::xproto::GetGeometryReply;
+use x11rb::protocol::xproto::{ConnectionExt, EventMask, Property};
 use x11rb::protocol::Event;
-use x11rb::rust_connection;
-use x11rb::{
-    connection::Connection,
-    protocol::xproto::{ChangeWindowAttributesAux, ConnectionExt, EventMask},
-    rust_connection::RustConnection,
-};
 
 use crate::{
-    atoms::GamescopeAtom,
-    x11::{self, get_window_name},
+    atom_cache::AtomCache,
+    atoms::{AtomHandle, AtomKey, AtomType, GamescopeAtom},
+    builder::XWaylandBuilder,
+    callback::{self, CallbackHandle},
+    channel::{self, BoundedReceiver, OverflowPolicy},
+    color::{self, NightMode},
+    connection::{
+        ConnectOptions, ConnectionEvent, ReconnectPolicy, SharedConnection, X11Connection,
+    },
+    control::GamescopeControl,
+    coordination::{self, ControllerEvent},
+    debounce::{self, Debounce},
+    display::{DisplayInfo, Edid},
+    event_loop::{EventLoop, RawEvent},
+    extension::{self, Capabilities, Extension},
+    health::{self, AppHealthEvent},
+    idle::{self, IdleEvent},
+    input::InputEvent,
+    journal::{Journal, JournalEntry},
+    latency::{self, FocusLatencyTracker, LatencyHistogram},
+    metadata::Metadata,
+    mode::{ModeControl, ModeControlLayout},
+    pool::ConnectionPool,
+    profiles::GamescopeSettings,
+    property::{PropertyChange, PropertyValue},
+    screenshot,
+    state::{FocusableWindow, SessionState, WindowState},
+    transaction::PropertyTransaction,
+    validation::{self, SuspiciousValue},
+    watchdog::{self, WatchdogEvent},
+    window::{Window, WindowIter},
+    window_tracker::{AppWindowEvent, WindowTracker},
+    worker, x11,
 };
 
+#[cfg(feature = "futures")]
+use crate::stream::{self, EventStream};
+
 /// Gamescope is hard-coded to look for STEAM_GAME=769 to determine if it is the
-/// overlay app.
+/// overlay app. Patched Gamescope builds may use a different id, which can be
+/// configured with [XWayland::set_overlay_app_id].
 pub const OVERLAY_APP_ID: u32 = 769;
 
-type WindowChangesCallback<T> = fn(
-    &rust_connection::RustConnection,
-    &Sender<T>,
-    Event,
-) -> Result<(), Box<dyn std::error::Error>>;
+/// How long [Primary::open_overlay] and [Primary::close_overlay] wait for
+/// Gamescope to report the focus change
+pub const OVERLAY_SWITCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How often the focused app is checked while waiting for the overlay to open
+/// or close
+const OVERLAY_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+type WindowChangesCallback<T> =
+    fn(&X11Connection, &Sender<T>, Event) -> Result<(), Box<dyn std::error::Error>>;
+
+type WindowLifecycleListener = (JoinHandle<()>, Receiver<(WindowLifecycleEvent, u32)>);
+
+/// Backend used by a property watcher to detect property changes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatchBackend {
+    /// Listen for PropertyNotify events from the X server
+    #[default]
+    Events,
+    /// Periodically read all properties on the window at the given interval
+    /// and emit the ones that changed since the last poll. This can be used on
+    /// X servers that do not reliably deliver PropertyNotify events.
+    Polling(Duration),
+}
 
 // Gamescope blur modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlurMode {
     Off,
     Cond,
     Always,
 }
 
+/// Scalers and filters understood by GAMESCOPE_SCALING_FILTER
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScalingFilter {
+    /// Bilinear filtering
+    #[default]
+    Linear = 0,
+    /// Nearest neighbor filtering
+    Nearest = 1,
+    /// Scale by whole multiples with nearest neighbor filtering
+    Integer = 2,
+    /// AMD FidelityFX Super Resolution
+    Fsr = 3,
+    /// NVIDIA Image Scaling
+    Nis = 4,
+    /// Pixel art filtering
+    Pixel = 5,
+}
+
+/// Sharpest FSR sharpness value
+pub const FSR_SHARPNESS_MAX: u32 = 0;
+/// Softest FSR sharpness value
+pub const FSR_SHARPNESS_MIN: u32 = 20;
+
+/// Variable refresh rate (adaptive sync) state of the display Gamescope is
+/// outputting to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VrrState {
+    /// True if the display supports VRR
+    pub capable: bool,
+    /// True if VRR was requested to be used when possible
+    pub enabled: bool,
+    /// True if the display is currently running with VRR
+    pub active: bool,
+}
+
+impl VrrState {
+    /// Properties the VRR state is read from
+    const ATOMS: [GamescopeAtom; 3] = [
+        GamescopeAtom::VRRCapable,
+        GamescopeAtom::VRREnabled,
+        GamescopeAtom::VRRFeedback,
+    ];
+
+    /// Reads the VRR state from the properties on the given root window
+    fn read<F>(conn: F, root_window_id: u32) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        F: x11rb::connection::Connection,
+    {
+        let keys = Self::ATOMS.map(|atom| atom.to_string());
+        let requests = keys.each_ref().map(|key| (root_window_id, key.as_str()));
+        let values = x11::get_properties_batch(conn, &requests)?;
+        let flag = |index: usize| {
+            values[index]
+                .as_ref()
+                .and_then(|value| value.first().copied())
+                .unwrap_or_default()
+                != 0
+        };
+
+        Ok(Self {
+            capable: flag(0),
+            enabled: flag(1),
+            active: flag(2),
+        })
+    }
+}
+
+/// HDR state of the display Gamescope is outputting to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HdrStatus {
+    /// True if the display supports HDR
+    pub supported: bool,
+    /// True if HDR output was requested
+    pub enabled: bool,
+    /// True if Gamescope is currently outputting HDR
+    pub active: bool,
+    /// Brightness in nits that SDR content is shown at while outputting HDR
+    pub sdr_content_nits: Option<u32>,
+    /// True if inverse tone mapping of SDR content to HDR is enabled
+    pub itm_enabled: bool,
+    /// Brightness in nits of SDR content used as input for inverse tone
+    /// mapping
+    pub itm_sdr_nits: Option<u32>,
+    /// Brightness in nits SDR content is inverse tone mapped to
+    pub itm_target_nits: Option<u32>,
+}
+
+/// Screenshot types understood by GAMESCOPECTRL_REQUEST_SCREENSHOT. Older
+/// Gamescope versions take a screenshot for any value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScreenshotType {
+    /// Only the base plane (E.g. the game without overlays)
+    BasePlaneOnly = 0,
+    /// All layers as they are shown on screen
+    #[default]
+    AllRealLayers = 1,
+    /// The final composited image, including any color management
+    FullComposition = 2,
+    /// The contents of the screen buffer as scanned out
+    ScreenBuffer = 3,
+}
+
 // Window lifecycle events
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WindowLifecycleEvent {
     Created,
     Destroyed,
@@ -63,31 +228,237 @@ impl TryFrom<&str> for WindowLifecycleEvent {
     }
 }
 
-/// [XWayland] is a handle to a single Gamescope XWayland instance.
+/// Connection state of an [XWayland] shared between all of its clones
 #[derive(Debug)]
-pub struct XWayland {
+struct Inner {
+    conn: Option<SharedConnection>,
+    events: Option<Arc<EventLoop>>,
+    pool: Option<Arc<ConnectionPool>>,
+    journal: Option<Arc<Journal>>,
+    focus_latency: Option<Arc<FocusLatencyTracker>>,
+    /// When the notification shown on each window with
+    /// [XWayland::show_notification] expires
+    notification_deadlines: HashMap<u32, Instant>,
+    /// Values attached by callers with [XWayland::set_metadata]
+    metadata: Metadata,
+    /// Receivers of suspicious property values when strict validation is
+    /// enabled
+    validators: Vec<Sender<SuspiciousValue>>,
+    options: ConnectOptions,
+    reconnect_policy: Option<ReconnectPolicy>,
+    overlay_app_id: u32,
+}
+
+/// Size of the border around the contents of a window, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameExtents {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+    /// True if the extents were set by the client itself (_GTK_FRAME_EXTENTS)
+    /// for client-side decorations and shadows, which are drawn inside the
+    /// window geometry. Otherwise they were set by a window manager
+    /// (_NET_FRAME_EXTENTS) and the frame is drawn outside of it.
+    pub client_side: bool,
+}
+
+/// Position and size of a window relative to the root window, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowRect {
+    /// Horizontal position of the window contents on the root window
+    pub x: i16,
+    /// Vertical position of the window contents on the root window
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Window decoration hints from the _MOTIF_WM_HINTS property
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MotifHints {
+    /// Which of the other fields are set
+    pub flags: u32,
+    /// Window manager functions allowed for the window
+    pub functions: u32,
+    /// Decorations the window manager should draw
+    pub decorations: u32,
+    /// Input mode of the window (E.g. modal)
+    pub input_mode: u32,
+    /// Status of a tear-off menu
+    pub status: u32,
+}
+
+impl MotifHints {
+    /// Flag set if the decorations field is valid
+    pub const FLAG_DECORATIONS: u32 = 1 << 1;
+
+    /// Returns whether the window wants to be decorated, or None if the
+    /// window does not say
+    pub fn has_decorations(&self) -> Option<bool> {
+        if self.flags & Self::FLAG_DECORATIONS == 0 {
+            return None;
+        }
+        Some(self.decorations != 0)
+    }
+}
+
+/// Functional type of a window from its _NET_WM_WINDOW_TYPE property
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display, strum_macros::EnumString)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowType {
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_NORMAL")]
+    Normal,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_DIALOG")]
+    Dialog,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_SPLASH")]
+    Splash,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_UTILITY")]
+    Utility,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_TOOLBAR")]
+    Toolbar,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_MENU")]
+    Menu,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_DROPDOWN_MENU")]
+    DropdownMenu,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_POPUP_MENU")]
+    PopupMenu,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_TOOLTIP")]
+    Tooltip,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_NOTIFICATION")]
+    Notification,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_COMBO")]
+    Combo,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_DND")]
+    Dnd,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_DESKTOP")]
+    Desktop,
+    #[strum(serialize = "_NET_WM_WINDOW_TYPE_DOCK")]
+    Dock,
+}
+
+impl WindowType {
+    /// Returns true if windows of this type can be the main window of an app
+    pub fn is_main(&self) -> bool {
+        *self == WindowType::Normal
+    }
+
+    /// Returns true if windows of this type can receive focus like an app.
+    /// Menus, tooltips, splash screens and similar windows only accompany
+    /// the main window of an app.
+    pub fn is_focusable(&self) -> bool {
+        matches!(self, WindowType::Normal | WindowType::Dialog)
+    }
+}
+
+/// Type-state marker for an [XWayland] that has not been connected yet. Only
+/// configuration methods and [XWayland::connect] are available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disconnected;
+
+/// Type-state marker for an [XWayland] that is connected to its display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connected;
+
+/// [XWayland] is a handle to a single Gamescope XWayland instance. A single
+/// connection is shared between the query API and all event listeners. The
+/// handle is cheap to clone and can be shared across threads; all clones share
+/// the same underlying connection.
+///
+/// A handle starts out as `XWayland<Disconnected>` and has to be turned into
+/// an `XWayland<Connected>` with [XWayland::connect] before the display can be
+/// queried, so calling a query on an unconnected handle is a compile error.
+#[derive(Debug, Clone)]
+pub struct XWayland<S = Connected> {
     name: String,
-    conn: Option<RustConnection>,
-    root_window_id: u32,
+    inner: Arc<RwLock<Inner>>,
+    state: PhantomData<S>,
 }
 
-impl XWayland {
+impl XWayland<Disconnected> {
     /// Create a new Gamescope XWayland instance with the given display name (e.g. ":0")
     pub fn new(name: String) -> Self {
+        Self::with_options(name, ConnectOptions::default())
+    }
+
+    /// Create a new Gamescope XWayland instance with the given display name
+    /// that connects using the given options
+    pub fn with_options(name: String, options: ConnectOptions) -> Self {
+        let inner = Inner {
+            options,
+            conn: None,
+            events: None,
+            pool: None,
+            journal: None,
+            focus_latency: None,
+            notification_deadlines: HashMap::new(),
+            metadata: Metadata::new(),
+            validators: Vec::new(),
+            reconnect_policy: None,
+            overlay_app_id: OVERLAY_APP_ID,
+        };
         Self {
             name,
-            conn: None,
-            root_window_id: 0,
+            inner: Arc::new(RwLock::new(inner)),
+            state: PhantomData,
+        }
+    }
+
+    /// Returns a builder to configure how to connect to the given display
+    /// (E.g. ":1")
+    pub fn builder(name: &str) -> XWaylandBuilder {
+        XWaylandBuilder::new(name)
+    }
+
+    /// Connect to the XWayland display and return the connected handle
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn connect(self) -> Result<XWayland<Connected>, Box<dyn std::error::Error>> {
+        // Connect to the display
+        let options = self.read_inner().options.clone();
+        let conn = SharedConnection::connect(self.name.as_str(), options.clone())?;
+        {
+            let mut inner = self.write_inner();
+            conn.set_policy(inner.reconnect_policy);
+            inner.events = Some(Arc::new(EventLoop::new(conn.clone())?));
+            inner.pool = options
+                .pool_size
+                .filter(|size| *size > 0)
+                .map(|size| Arc::new(ConnectionPool::new(conn.clone(), size)));
+            inner.conn = Some(conn);
         }
+
+        Ok(XWayland {
+            name: self.name,
+            inner: self.inner,
+            state: PhantomData,
+        })
     }
 }
 
-impl XWayland {
+impl<S> XWayland<S> {
     /// Returns the name of the XWayland instance (E.g. ":0")
     pub fn get_name(&self) -> String {
         self.name.clone()
     }
 
+    /// Returns the app id used to identify the overlay app. Defaults to
+    /// [OVERLAY_APP_ID].
+    pub fn get_overlay_app_id(&self) -> u32 {
+        self.read_inner().overlay_app_id
+    }
+
+    /// Sets the app id used to identify the overlay app, for Gamescope builds
+    /// that negotiate an overlay id other than [OVERLAY_APP_ID].
+    pub fn set_overlay_app_id(&self, app_id: u32) {
+        self.write_inner().overlay_app_id = app_id;
+    }
+
     /// Returns true if the XWayland connection exists
     pub fn is_connected(&self) -> bool {
         if let Ok(conn) = self.get_connection() {
@@ -97,59 +468,380 @@ impl XWayland {
         }
     }
 
+    /// Sets the options used to connect to the display (E.g. an explicit
+    /// Xauthority file for a display owned by another user). The options are
+    /// also used when the connection is automatically re-established.
+    pub fn set_connect_options(&self, options: ConnectOptions) {
+        self.write_inner().options = options;
+    }
+
+    /// Returns the options used to connect to the display
+    pub fn get_connect_options(&self) -> ConnectOptions {
+        self.read_inner().options.clone()
+    }
+
+    /// Enables or disables automatic reconnection. When a [ReconnectPolicy] is
+    /// set and the connection to the display is lost (E.g. because Gamescope
+    /// was restarted), the display is connected to again and all active
+    /// listeners are subscribed again. Reconnecting is disabled by default.
+    pub fn set_reconnect_policy(&self, policy: Option<ReconnectPolicy>) {
+        let mut inner = self.write_inner();
+        inner.reconnect_policy = policy;
+        if let Some(conn) = inner.conn.as_ref() {
+            conn.set_policy(policy);
+        }
+    }
+
+    /// Records every property change made through this instance in the given
+    /// [Journal], or stops recording when set to None
+    pub fn set_journal(&self, journal: Option<Arc<Journal>>) {
+        self.write_inner().journal = journal;
+    }
+
+    /// Returns the journal property changes are recorded in, if enabled
+    pub fn get_journal(&self) -> Option<Arc<Journal>> {
+        self.read_inner().journal.clone()
+    }
+
+    /// Stops measuring focus change latency. See
+    /// [XWayland::enable_focus_latency_tracking].
+    pub fn disable_focus_latency_tracking(&self) {
+        self.write_inner().focus_latency = None;
+    }
+
+    /// Returns the focus change latencies measured since tracking was enabled,
+    /// or None if tracking is disabled
+    pub fn get_focus_latency(&self) -> Option<LatencyHistogram> {
+        self.read_inner()
+            .focus_latency
+            .as_ref()
+            .map(|tracker| tracker.histogram())
+    }
+
+    /// Enables strict validation of Gamescope property values read through
+    /// this instance. Values that do not have the expected shape (E.g.
+    /// implausible window ids in GAMESCOPE_FOCUSABLE_APPS) are logged and sent
+    /// to the returned receiver. Can be called again for more receivers.
+    pub fn enable_strict_validation(&self) -> Receiver<SuspiciousValue> {
+        let (tx, rx) = mpsc::channel();
+        self.write_inner().validators.push(tx);
+        rx
+    }
+
+    /// Disables strict validation and hangs up all of its receivers
+    pub fn disable_strict_validation(&self) {
+        self.write_inner().validators.clear();
+    }
+
+    /// Returns true if strict validation is enabled
+    pub fn is_strict_validation_enabled(&self) -> bool {
+        !self.read_inner().validators.is_empty()
+    }
+
+    /// Validates the given property value if strict validation is enabled
+    fn check_value(&self, window_id: u32, property: GamescopeAtom, value: &PropertyValue) {
+        check_value(&self.inner, window_id, property, value);
+    }
+
+    /// Attaches the given value to this instance, replacing and returning any
+    /// value of the same type (E.g. a caller defined `HostedApp(620)`). The
+    /// value is shared between all clones of this instance.
+    pub fn set_metadata<T: Any + Send + Sync>(&self, value: T) -> Option<T> {
+        self.write_inner().metadata.insert(value)
+    }
+
+    /// Returns a copy of the attached value of the given type
+    pub fn get_metadata<T: Any + Send + Sync + Clone>(&self) -> Option<T> {
+        self.read_inner().metadata.get::<T>().cloned()
+    }
+
+    /// Calls the given function with the attached value of the given type and
+    /// returns its result, without copying the value
+    pub fn with_metadata<T, R>(&self, f: impl FnOnce(&T) -> R) -> Option<R>
+    where
+        T: Any + Send + Sync,
+    {
+        self.read_inner().metadata.get::<T>().map(f)
+    }
+
+    /// Returns true if a value of the given type is attached
+    pub fn has_metadata<T: Any + Send + Sync>(&self) -> bool {
+        self.read_inner().metadata.contains::<T>()
+    }
+
+    /// Removes and returns the attached value of the given type
+    pub fn remove_metadata<T: Any + Send + Sync>(&self) -> Option<T> {
+        self.write_inner().metadata.remove::<T>()
+    }
+
     /// Borrow the connection to the XWayland server. Will error if not yet
     /// connected.
-    fn get_connection(&self) -> Result<&RustConnection, Box<dyn std::error::Error>> {
-        if self.conn.is_none() {
-            return Err("No connection".into());
-        }
+    fn get_connection(&self) -> Result<Arc<X11Connection>, Box<dyn std::error::Error>> {
+        Ok(self.get_shared_connection()?.get())
+    }
+
+    /// Borrow the connection state shared with background threads. Will error
+    /// if not yet connected.
+    pub(crate) fn get_shared_connection(
+        &self,
+    ) -> Result<SharedConnection, Box<dyn std::error::Error>> {
+        self.read_inner()
+            .conn
+            .clone()
+            .ok_or_else(|| "No connection".into())
+    }
 
-        let borrow = self.conn.borrow();
-        let rust_connection = borrow.as_ref().unwrap();
-        Ok(rust_connection)
+    /// Returns the current connection together with the id of the given atom
+    /// on it. Atoms are interned once per connection and cached afterwards.
+    fn resolve_atom(
+        &self,
+        key: GamescopeAtom,
+    ) -> Result<(Arc<X11Connection>, u32), Box<dyn std::error::Error>> {
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let atom = atoms.resolve(conn.as_ref(), key)?;
+        Ok((conn, atom))
     }
 
-    /// Connect to the XWayland display
-    pub fn connect(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // Connect to the display
-        let (conn, screen_num) = x11rb::connect(Some(self.name.as_str()))?;
-        log::info!("Connected to: {}", screen_num);
-        let screen = &conn.setup().roots[screen_num];
+    /// Lock the shared connection state for reading
+    fn read_inner(&self) -> RwLockReadGuard<'_, Inner> {
+        self.inner
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 
-        self.root_window_id = screen.root;
-        self.conn = Some(conn);
+    /// Lock the shared connection state for writing
+    fn write_inner(&self) -> RwLockWriteGuard<'_, Inner> {
+        self.inner
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
 
-        Ok(())
+    /// Returns true if both handles share the same underlying connection
+    pub(crate) fn ptr_eq<T>(&self, other: &XWayland<T>) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl XWayland<Connected> {
+    /// Returns the root window id of the current connection, or 0 if not yet
+    /// connected.
+    fn root_window_id(&self) -> u32 {
+        self.read_inner()
+            .conn
+            .as_ref()
+            .map(|conn| conn.root_window_id())
+            .unwrap_or_default()
+    }
+
+    /// Returns the pool of extra connections used to spread large queries
+    /// (E.g. a session snapshot) over several connections, or None if no pool
+    /// was configured or not yet connected.
+    fn get_pool(&self) -> Option<Arc<ConnectionPool>> {
+        self.read_inner().pool.clone()
+    }
+
+    /// Borrow the event loop that dispatches events from the shared connection.
+    /// Will error if not yet connected.
+    fn get_event_loop(&self) -> Result<Arc<EventLoop>, Box<dyn std::error::Error>> {
+        self.read_inner()
+            .events
+            .clone()
+            .ok_or_else(|| "No connection".into())
+    }
+
+    /// Returns a receiver channel that can be used to receive changes to the
+    /// connection state, such as [ConnectionEvent::Reconnected] after the
+    /// connection was automatically re-established.
+    pub fn listen_for_connection_events(
+        &self,
+    ) -> Result<Receiver<ConnectionEvent>, Box<dyn std::error::Error>> {
+        self.get_event_loop()?.subscribe_status()
+    }
+
+    /// Returns the file descriptor of the connection to the X server, so it
+    /// can be registered with an external reactor (E.g. epoll or mio) and
+    /// events read with [XWayland::poll_for_event] when it becomes readable.
+    /// The descriptor changes when the connection is re-established.
+    pub fn as_raw_fd(&self) -> Result<RawFd, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        #[cfg(not(feature = "xcb"))]
+        let fd = conn.stream().as_raw_fd();
+        #[cfg(feature = "xcb")]
+        let fd = conn.as_raw_fd();
+        Ok(fd)
+    }
+
+    /// Selects the given event mask on the given window, replacing the mask
+    /// selected with this method before. The events listeners of this
+    /// instance selected on the window are kept. Used together with
+    /// [XWayland::poll_for_event] to receive events without any listener.
+    pub fn select_window_events(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.get_event_loop()?.select(window_id, event_mask)
+    }
+
+    /// Returns the next event received from the X server without waiting, or
+    /// None if there is none. This lets consumers read events from their own
+    /// reactor (see [XWayland::as_raw_fd]) instead of the background threads
+    /// of this crate. Will error once the background event thread of this
+    /// instance was started, since it reads the events itself. The thread is
+    /// started by any listener, including the ones helpers like
+    /// [XWayland::wait_for_xprop], [XWayland::wait_for_window_for_app_id] or
+    /// [XWayland::start_idle_monitor] use internally, and keeps running until
+    /// the connection is dropped.
+    pub fn poll_for_event(&self) -> Result<Option<Event>, Box<dyn std::error::Error>> {
+        if self.get_event_loop()?.is_running() {
+            return Err("Events are read by the listeners of this instance".into());
+        }
+        let conn = self.get_connection()?;
+        Ok(conn.poll_for_event()?)
+    }
+
+    /// Returns true if the X server provides the given extension. Extensions
+    /// are only queried the first time they are needed, so features that
+    /// depend on a missing extension fail with an
+    /// [ExtensionMissing](crate::extension::ExtensionMissing) error instead.
+    pub fn has_extension(&self, extension: Extension) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        extension::has_extension(conn.as_ref(), extension)
+    }
+
+    /// Returns which X11 extensions the X server provides and which fallback
+    /// paths are used because of missing ones
+    pub fn get_capabilities(&self) -> Result<Capabilities, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        extension::get_capabilities(conn.as_ref())
     }
 
     /// Tries to discover the process IDs that are associated with the given
-    /// window.
+    /// window using the XRes extension. If the X server does not provide
+    /// XRes, the '_NET_WM_PID' property of the window is used instead (see
+    /// [Capabilities::pid_lookup]).
     pub fn get_pids_for_window(
         &self,
         window_id: u32,
     ) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
         let conn = self.get_connection()?;
-        x11::get_window_pids(conn, window_id)
+        if extension::has_extension(conn.as_ref(), Extension::XRes)? {
+            return x11::get_window_pids(conn.as_ref(), window_id);
+        }
+
+        log::debug!(
+            "XRes is missing, using _NET_WM_PID for window {}",
+            window_id
+        );
+        Ok(self.get_window_pid(window_id)?.into_iter().collect())
     }
 
     /// Returns the window id(s) for the given process ID.
     pub fn get_windows_for_pid(&self, pid: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
         // Get all windows from the root window to search for the one with this
         // process ID.
-        let all_windows = self.get_all_windows(self.root_window_id)?;
+        let all_windows = self.get_all_windows(self.root_window_id())?;
+        let pids = match self.get_xprop_batch(all_windows.as_slice(), GamescopeAtom::NetWmPID) {
+            Ok(pids) => pids,
+            // A window may have been destroyed while reading the batch, so fall
+            // back to reading each window individually.
+            Err(_) => all_windows
+                .iter()
+                .map(|window_id| {
+                    self.get_xprop(*window_id, GamescopeAtom::NetWmPID)
+                        .unwrap_or_default()
+                })
+                .collect(),
+        };
         let window_ids = all_windows
             .into_iter()
-            .filter(|window_id| {
-                let window_pid = self
-                    .get_window_pid(*window_id)
-                    .unwrap_or_default()
+            .zip(pids)
+            .filter(|(_, window_pid)| {
+                let window_pid = window_pid
+                    .as_ref()
+                    .and_then(|values| values.first().copied())
                     .unwrap_or_default();
                 pid == window_pid
             })
+            .map(|(window_id, _)| window_id)
             .collect();
         Ok(window_ids)
     }
 
+    /// Waits until the process with the given PID has a window, for up to the
+    /// given timeout. Returns the main window of the process right away if it
+    /// has one (see [XWayland::get_main_window]), otherwise the first
+    /// focusable top-level window the process maps, so splash screens are
+    /// skipped. Windows are matched by their _NET_WM_PID property as they are
+    /// mapped, without polling.
+    pub fn wait_for_window_for_pid(
+        &self,
+        pid: u32,
+        timeout: Duration,
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + timeout;
+
+        // Listen before looking at the existing windows so no window is missed
+        let root = self.root_window_id();
+        let mask = EventMask::SUBSTRUCTURE_NOTIFY;
+        let (_, rx) = self.listen_for_events(root, mask, move |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::MapNotify(event) = event else {
+                return Ok(());
+            };
+            let atom = atoms.resolve(conn, GamescopeAtom::NetWmPID)?;
+            // The window may already be destroyed again
+            let value = x11::get_property_by_atom(conn, event.window, atom).unwrap_or_default();
+            if value.and_then(|value| value.first().copied()) == Some(pid) {
+                tx.send(event.window)?;
+            }
+
+            Ok(())
+        })?;
+        if let Some(window_id) = self.get_main_window(&self.get_windows_for_pid(pid)?)? {
+            return Ok(window_id);
+        }
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let window_id = rx
+                .recv_timeout(remaining)
+                .map_err(|_| format!("Timed out waiting for a window of process {}", pid))?;
+            // The window may already be destroyed again
+            let focusable = self
+                .get_window_type(window_id)
+                .is_ok_and(|window_type| window_type.is_focusable());
+            if focusable {
+                return Ok(window_id);
+            }
+        }
+    }
+
+    /// Waits until a top-level window with the given app id (STEAM_GAME)
+    /// exists, for up to the given timeout. Returns an existing window of the
+    /// app right away, otherwise the first window created with or assigned
+    /// the app id. See [WindowTracker].
+    pub fn wait_for_window_for_app_id(
+        &self,
+        app_id: u32,
+        timeout: Duration,
+    ) -> Result<u32, Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + timeout;
+        let (_, rx) = WindowTracker::new().start(self)?;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(remaining) {
+                Ok(AppWindowEvent::AppWindowAppeared {
+                    app_id: appeared,
+                    window_id,
+                }) if appeared == app_id => return Ok(window_id),
+                Ok(_) => continue,
+                Err(_) => {
+                    return Err(format!("Timed out waiting for a window of app {}", app_id).into())
+                }
+            }
+        }
+    }
+
     /// Gets the geometry of the window
     pub fn get_geometry_for_window(
         &self,
@@ -161,11 +853,53 @@ impl XWayland {
         Ok(geometry)
     }
 
+    /// Returns the position and size of each of the given windows relative to
+    /// the root window, or None for windows that no longer exist. The geometry
+    /// and root position of all windows are requested together in a single
+    /// round trip, so this is cheap enough to call every frame.
+    pub fn get_geometries(
+        &self,
+        window_ids: &[u32],
+    ) -> Result<Vec<Option<WindowRect>>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_root_geometries(conn.as_ref(), self.root_window_id(), window_ids)
+    }
+
+    /// Returns the frame extents of the given window from _NET_FRAME_EXTENTS,
+    /// or from _GTK_FRAME_EXTENTS for windows with client-side decorations.
+    /// Returns None if the window has neither property.
+    pub fn get_frame_extents(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<FrameExtents>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        let net = GamescopeAtom::NetFrameExtents.to_string();
+        let gtk = GamescopeAtom::GtkFrameExtents.to_string();
+        let requests = [(window_id, net.as_str()), (window_id, gtk.as_str())];
+        let values = x11::get_properties_batch(conn.as_ref(), &requests)?;
+
+        let extents = values
+            .into_iter()
+            .zip([false, true])
+            .find_map(|(value, client_side)| match value?.as_slice() {
+                [left, right, top, bottom, ..] => Some(FrameExtents {
+                    left: *left,
+                    right: *right,
+                    top: *top,
+                    bottom: *bottom,
+                    client_side,
+                }),
+                _ => None,
+            });
+
+        Ok(extents)
+    }
+
     /// Listen for property changes on the root window
     pub fn listen_for_property_changes(
         &self,
     ) -> Result<(JoinHandle<()>, Receiver<String>), Box<dyn std::error::Error>> {
-        self.listen_for_window_property_changes(self.root_window_id)
+        self.listen_for_window_property_changes(self.root_window_id())
     }
 
     /// Listen for property changes on the given window. Returns a
@@ -176,11 +910,267 @@ impl XWayland {
         &self,
         window_id: u32,
     ) -> Result<(JoinHandle<()>, Receiver<String>), Box<dyn std::error::Error>> {
-        self.listen_for_window_changes(window_id, EventMask::PROPERTY_CHANGE, |conn, tx, event| {
+        self.listen_for_window_property_changes_with(window_id, WatchBackend::Events)
+    }
+
+    /// Listen for property changes on the given window using the given
+    /// [WatchBackend]. Returns a join handle of the listening thread and a
+    /// receiver channel that can be used to receive property changes.
+    pub fn listen_for_window_property_changes_with(
+        &self,
+        window_id: u32,
+        backend: WatchBackend,
+    ) -> Result<(JoinHandle<()>, Receiver<String>), Box<dyn std::error::Error>> {
+        let interval = match backend {
+            WatchBackend::Events => return self.listen_for_property_notify(window_id),
+            WatchBackend::Polling(interval) => interval,
+        };
+
+        // Share the connection with the polling thread
+        let conn = self.get_shared_connection()?;
+        let mut last = x11::get_raw_properties(conn.get().as_ref(), window_id)?;
+
+        // Create a channel to send update messages through
+        let (tx, rx): (Sender<String>, Receiver<String>) = mpsc::channel();
+
+        // Spawn a thread to poll for property changes
+        let display = conn.name().to_string();
+        let options = conn.options().clone();
+        let child = worker::spawn(&display, "poller", &options, move || loop {
+            thread::sleep(interval);
+            let current = match x11::get_raw_properties(conn.get().as_ref(), window_id) {
+                Ok(current) => current,
+                // Keep polling while the connection may be re-established
+                Err(err) if conn.policy().is_some() => {
+                    log::debug!("Error polling window properties: {}", err);
+                    continue;
+                }
+                Err(err) => {
+                    log::error!("Error polling window properties: {}", err);
+                    break;
+                }
+            };
+
+            // Find all properties that were added, changed, or removed
+            let removed = last.keys().filter(|atom| !current.contains_key(atom));
+            let changed = current
+                .iter()
+                .filter(|(atom, value)| last.get(atom) != Some(value))
+                .map(|(atom, _)| atom);
+            let atoms: Vec<u32> = changed.chain(removed).copied().collect();
+
+            let (current_conn, cache) = conn.get_with_atoms();
+            for atom in atoms {
+                let property = match cache.name(current_conn.as_ref(), atom) {
+                    Ok(property) => property.to_string(),
+                    Err(err) => {
+                        log::error!("Error getting atom name: {}", err);
+                        continue;
+                    }
+                };
+                if tx.send(property).is_err() {
+                    return;
+                }
+            }
+            last = current;
+        });
+
+        Ok((child, rx))
+    }
+
+    /// Listen for changes to any of the given properties on the root window.
+    /// Events are matched by the atom ids resolved once for the connection, so
+    /// no atom names are looked up per event. Returns a join handle of the
+    /// listening thread and a receiver channel that receives the property that
+    /// changed or was removed.
+    pub fn listen_for_atom_changes(
+        &self,
+        keys: &[GamescopeAtom],
+    ) -> Result<(JoinHandle<()>, Receiver<GamescopeAtom>), Box<dyn std::error::Error>> {
+        let keys = keys.to_vec();
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, move |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            for key in keys.iter() {
+                if event.atom == atoms.resolve(conn, *key)? {
+                    tx.send(*key)?;
+                    break;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Listen for changes to any of the given properties on the root window
+    /// like [XWayland::listen_for_atom_changes], limiting how often changes
+    /// of each property are delivered according to the given [Debounce]
+    /// setting.
+    pub fn listen_for_atom_changes_with(
+        &self,
+        keys: &[GamescopeAtom],
+        debounce: Debounce,
+    ) -> Result<(JoinHandle<()>, Receiver<GamescopeAtom>), Box<dyn std::error::Error>> {
+        let listener = self.listen_for_atom_changes(keys)?;
+        Ok(self.debounce(listener, debounce, |atom| *atom))
+    }
+
+    /// Listen for property changes on the root window with decoded values.
+    /// Returns a join handle of the listening thread and a receiver channel
+    /// that can be used to receive property changes.
+    pub fn listen_for_property_values(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<PropertyChange>), Box<dyn std::error::Error>> {
+        self.listen_for_window_property_values(self.root_window_id())
+    }
+
+    /// Listen for property changes on the given window. The new value of each
+    /// changed property is read and decoded before it is sent, using the known
+    /// type of Gamescope properties (E.g. WINDOW for GAMESCOPE_FOCUSED_WINDOW)
+    /// and the type reported by the X server for all others.
+    pub fn listen_for_window_property_values(
+        &self,
+        window_id: u32,
+    ) -> Result<(JoinHandle<()>, Receiver<PropertyChange>), Box<dyn std::error::Error>> {
+        let mask = EventMask::PROPERTY_CHANGE;
+        let inner = Arc::downgrade(&self.inner);
+        self.listen_for_events(window_id, mask, move |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            let property = atoms.name(conn, event.atom)?;
+            let value = if event.state == Property::DELETE {
+                None
+            } else {
+                x11::get_decoded_property_by_atom(conn, event.window, event.atom, &property)?
+            };
+            let atom = GamescopeAtom::from_property_name(&property);
+            if let (Some(atom), Some(value), Some(inner)) = (atom, value.as_ref(), inner.upgrade())
+            {
+                check_value(&inner, event.window, atom, value);
+            }
+            tx.send(PropertyChange {
+                window_id: event.window,
+                property: property.to_string(),
+                value,
+            })?;
+
+            Ok(())
+        })
+    }
+
+    /// Listen for property changes on the given window with decoded values
+    /// like [XWayland::listen_for_window_property_values], limiting how often
+    /// changes of each property are delivered according to the given
+    /// [Debounce] setting.
+    pub fn listen_for_window_property_values_with(
+        &self,
+        window_id: u32,
+        debounce: Debounce,
+    ) -> Result<(JoinHandle<()>, Receiver<PropertyChange>), Box<dyn std::error::Error>> {
+        let listener = self.listen_for_window_property_values(window_id)?;
+        Ok(self.debounce(listener, debounce, |change| {
+            (change.window_id, change.property.clone())
+        }))
+    }
+
+    /// Moves the events of the given listener (E.g. from
+    /// [XWayland::listen_for_property_changes]) into a channel that holds at
+    /// most the given number of events, so a stalled consumer can't make the
+    /// queue grow without limit. Events that don't fit are handled according
+    /// to the given [OverflowPolicy] and counted in
+    /// [BoundedReceiver::dropped]. Returns a join handle of the forwarding
+    /// thread and the bounded receiver.
+    pub fn bounded<T>(
+        &self,
+        listener: (JoinHandle<()>, Receiver<T>),
+        capacity: usize,
+        overflow: OverflowPolicy,
+    ) -> (JoinHandle<()>, BoundedReceiver<T>)
+    where
+        T: Send + 'static,
+    {
+        let (tx, rx) = channel::bounded(capacity, overflow);
+        let events = listener.1;
+        let options = self.get_connect_options();
+        let child = worker::spawn(&self.name, "bounded", &options, move || {
+            for event in events {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (child, rx)
+    }
+
+    /// Turns the given listener (E.g. from
+    /// [XWayland::listen_for_atom_changes]) into an [EventStream] for async
+    /// consumers. The events are forwarded by a background thread that exits
+    /// when the stream is dropped.
+    #[cfg(feature = "futures")]
+    pub fn stream<T>(&self, listener: (JoinHandle<()>, Receiver<T>)) -> EventStream<T>
+    where
+        T: Send + 'static,
+    {
+        let options = self.get_connect_options();
+        stream::spawn_stream(&self.name, &options, listener.1)
+    }
+
+    /// Returns a stream of the property changes on the root window along with
+    /// their new values
+    #[cfg(feature = "futures")]
+    pub fn property_stream(
+        &self,
+    ) -> Result<EventStream<PropertyChange>, Box<dyn std::error::Error>> {
+        self.window_property_stream(self.root_window_id())
+    }
+
+    /// Returns a stream of the property changes on the given window along
+    /// with their new values
+    #[cfg(feature = "futures")]
+    pub fn window_property_stream(
+        &self,
+        window_id: u32,
+    ) -> Result<EventStream<PropertyChange>, Box<dyn std::error::Error>> {
+        let listener = self.listen_for_window_property_values(window_id)?;
+        Ok(self.stream(listener))
+    }
+
+    /// Limits the events of the given listener according to the given
+    /// [Debounce] setting, grouping them by the key returned by the given
+    /// function. The listener is returned as is if debouncing is off.
+    fn debounce<T, K, F>(
+        &self,
+        listener: (JoinHandle<()>, Receiver<T>),
+        debounce: Debounce,
+        key: F,
+    ) -> (JoinHandle<()>, Receiver<T>)
+    where
+        T: Send + 'static,
+        K: std::hash::Hash + Eq + Clone + Send + 'static,
+        F: Fn(&T) -> K + Send + 'static,
+    {
+        if debounce == Debounce::Off {
+            return listener;
+        }
+        let options = self.get_connect_options();
+        debounce::spawn_debouncer(&self.name, &options, listener.1, debounce, key)
+    }
+
+    /// Listen for PropertyNotify events on the given window
+    fn listen_for_property_notify(
+        &self,
+        window_id: u32,
+    ) -> Result<(JoinHandle<()>, Receiver<String>), Box<dyn std::error::Error>> {
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(window_id, mask, |conn, atoms, tx, event| {
             if let x11rb::protocol::Event::PropertyNotify(event) = event {
-                let atom = conn.get_atom_name(event.atom).unwrap().reply().unwrap();
-                let property = String::from_utf8(atom.name).unwrap();
-                tx.send(property).unwrap();
+                let property = atoms.name(conn, event.atom)?;
+                tx.send(property.to_string())?;
             }
 
             Ok(())
@@ -196,7 +1186,7 @@ impl XWayland {
         &self,
     ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
         #[allow(deprecated)]
-        self.listen_for_window_created_on_window(self.root_window_id)
+        self.listen_for_window_created_on_window(self.root_window_id())
     }
 
     /// Listen for window created event on the given window. Returns a
@@ -213,30 +1203,59 @@ impl XWayland {
     ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
         self.listen_for_window_changes(window_id, EventMask::SUBSTRUCTURE_NOTIFY, |_, tx, event| {
             if let x11rb::protocol::Event::CreateNotify(event) = event {
-                tx.send(event.window).unwrap();
+                tx.send(event.window)?;
             }
 
             Ok(())
         })
     }
 
-    /// Listen for window lifecycle events on the root window
-    pub fn listen_for_window_lifecycle(
+    /// Listen for Steam Remote Play streaming client windows being shown.
+    /// Returns a join handle of the listening thread and a receiver channel
+    /// that receives the window id of every streaming client window that is
+    /// mapped (See [XWayland::is_streaming_client]).
+    pub fn listen_for_streaming_client_windows(
         &self,
-    ) -> Result<(JoinHandle<()>, Receiver<(WindowLifecycleEvent, u32)>), Box<dyn std::error::Error>>
-    {
-        self.listen_for_window_lifecycle_on_window(self.root_window_id)
-    }
-
-    /// Listen for window lifecycle event on the given window. Returns a
-    /// join handle of the listening thread and a receiver channel that can be
+    ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::SUBSTRUCTURE_NOTIFY;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::MapNotify(event) = event else {
+                return Ok(());
+            };
+            let keys = [
+                GamescopeAtom::SteamStreamingClient,
+                GamescopeAtom::SteamStreamingClientVideo,
+            ];
+            for key in keys {
+                let atom = atoms.resolve(conn, key)?;
+                // The window may already be destroyed again
+                let value = x11::get_property_by_atom(conn, event.window, atom).unwrap_or_default();
+                if value.is_some() {
+                    tx.send(event.window)?;
+                    break;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Listen for window lifecycle events on the root window
+    pub fn listen_for_window_lifecycle(
+        &self,
+    ) -> Result<WindowLifecycleListener, Box<dyn std::error::Error>> {
+        self.listen_for_window_lifecycle_on_window(self.root_window_id())
+    }
+
+    /// Listen for window lifecycle event on the given window. Returns a
+    /// join handle of the listening thread and a receiver channel that can be
     /// used to receive property changes.
     /// https://stackoverflow.com/questions/60141048/get-notifications-when-active-x-window-changes-using-python-xlib
     pub fn listen_for_window_lifecycle_on_window(
         &self,
         window_id: u32,
-    ) -> Result<(JoinHandle<()>, Receiver<(WindowLifecycleEvent, u32)>), Box<dyn std::error::Error>>
-    {
+    ) -> Result<WindowLifecycleListener, Box<dyn std::error::Error>> {
         self.listen_for_window_changes(window_id, EventMask::SUBSTRUCTURE_NOTIFY, |_, tx, event| {
             let (lifecycle_event, window) = match event {
                 x11rb::protocol::Event::CreateNotify(event) => {
@@ -269,46 +1288,489 @@ impl XWayland {
     where
         T: std::marker::Send + 'static,
     {
-        // Create a new connection for the new thread
-        let (conn, _) = x11rb::connect(Some(self.name.as_str()))?;
+        self.listen_for_events(window_id, event_mask, move |conn, _, tx, event| {
+            callback(conn, tx, event)
+        })
+    }
 
-        // Set the event mask to start listening for events
-        let mut attrs = ChangeWindowAttributesAux::new();
-        attrs.event_mask = Some(event_mask);
-        let result = conn.change_window_attributes(window_id, &attrs)?;
-        result.check()?;
+    /// Listen for events on the given window like
+    /// [XWayland::listen_for_window_changes], passing the atom cache of the
+    /// connection to the callback so it can look up atoms without a round
+    /// trip to the X server.
+    fn listen_for_events<T, C>(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+        callback: C,
+    ) -> Result<(JoinHandle<()>, Receiver<T>), Box<dyn std::error::Error>>
+    where
+        T: std::marker::Send + 'static,
+        C: Fn(
+                &X11Connection,
+                &AtomCache,
+                &Sender<T>,
+                Event,
+            ) -> Result<(), Box<dyn std::error::Error>>
+            + Send
+            + 'static,
+    {
+        // Subscribe to events from the shared connection
+        let events = self.get_event_loop()?.subscribe(window_id, event_mask)?;
 
         // Create a channel to send update messages through
         let (tx, rx): (Sender<T>, Receiver<T>) = mpsc::channel();
 
-        // Spawn a thread to listen for events
-        let child = thread::spawn(move || {
-            // Loop and listen for events
-            loop {
-                let event = conn.wait_for_event();
-                if event.is_err() {
+        // Spawn a thread to process events for this listener. The thread exits
+        // when the event loop stops or the receiver is dropped, which releases
+        // its event mask on the window.
+        let options = self.get_connect_options();
+        let child = worker::spawn(&self.name, "listener", &options, move || {
+            for (conn, atoms, event) in events {
+                let Err(err) = callback(&conn, &atoms, &tx, event) else {
+                    continue;
+                };
+                if err.downcast_ref::<mpsc::SendError<T>>().is_some() {
                     break;
                 }
-
-                if let Err(err) = callback(&conn, &tx, event.unwrap()) {
-                    log::error!("Error processing window change event: {}", err);
-                }
+                log::error!("Error processing window change event: {}", err);
             }
         });
 
         Ok((child, rx))
     }
 
+    /// Select the given event mask on the given window and send its events
+    /// through the given sender, so a single receiver can follow the events
+    /// of several windows. See [EventLoop::subscribe_with].
+    pub(crate) fn subscribe_events(
+        &self,
+        window_id: u32,
+        event_mask: EventMask,
+        tx: Arc<Sender<RawEvent>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.get_event_loop()?
+            .subscribe_with(window_id, event_mask, tx)
+    }
+
+    /// Ends all subscriptions made with [XWayland::subscribe_events] using the
+    /// given sender. See [EventLoop::unsubscribe].
+    #[cfg_attr(not(any(feature = "dbus", feature = "ffi")), allow(dead_code))]
+    pub(crate) fn unsubscribe_events(
+        &self,
+        tx: &Arc<Sender<RawEvent>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.get_event_loop()?.unsubscribe(tx)
+    }
+
+    /// Start a watchdog that checks for STEAM_INPUT_FOCUS being left set on a
+    /// window whose owning process has died (E.g. a crashed overlay) at the
+    /// given interval and clears it. Returns a join handle of the watchdog
+    /// thread and a receiver channel that can be used to receive
+    /// [WatchdogEvent]s whenever a stale input focus is cleared. Requires the
+    /// XRes extension to find the owning processes; without it nothing is
+    /// cleared.
+    pub fn start_input_focus_watchdog(
+        &self,
+        interval: Duration,
+    ) -> Result<(JoinHandle<()>, Receiver<WatchdogEvent>), Box<dyn std::error::Error>> {
+        let conn = self.get_shared_connection()?;
+        Ok(watchdog::spawn_input_focus_watchdog(conn, interval))
+    }
+
+    /// Start monitoring apps for crashes by combining window lifecycle events
+    /// with checks at the given interval whether the process owning each app
+    /// window is still alive. Returns a join handle of the monitor thread and a
+    /// receiver channel that can be used to receive [AppHealthEvent]s whenever
+    /// an app process dies while its windows remain, or all of its windows are
+    /// destroyed while the process keeps running. Requires the XRes extension
+    /// to find the owning processes; without it no app is monitored.
+    pub fn listen_for_app_crashes(
+        &self,
+        interval: Duration,
+    ) -> Result<(JoinHandle<()>, Receiver<AppHealthEvent>), Box<dyn std::error::Error>> {
+        let conn = self.get_shared_connection()?;
+        health::spawn_app_health_monitor(self, conn, interval)
+    }
+
+    /// Returns the process ID of the client that currently controls this
+    /// instance (E.g. drives the baselayer and input focus properties), or
+    /// None if no running process has taken control. Control is advisory and
+    /// only respected by cooperating clients.
+    pub fn get_controller(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        coordination::get_controller(conn.as_ref(), self.root_window_id())
+    }
+
+    /// Returns true if the current process controls this instance
+    pub fn has_control(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        Ok(self.get_controller()? == Some(std::process::id()))
+    }
+
+    /// Listen for changes to the Gamescope input counter, which is increased
+    /// whenever the user provides input. Must be called on the primary
+    /// instance. Returns a join handle of the listening thread and a receiver
+    /// channel that receives the new counter value.
+    pub fn listen_for_input_counter_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            let key = GamescopeAtom::InputCounter;
+            if event.atom != atoms.resolve(conn, key)? || event.state == Property::DELETE {
+                return Ok(());
+            }
+            let value = x11::get_property_by_atom(conn, event.window, event.atom)?;
+            if let Some(counter) = value.and_then(|value| value.first().copied()) {
+                tx.send(counter)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Listen for changes to the input counter like
+    /// [XWayland::listen_for_input_counter_changes], limiting how often
+    /// changes are delivered according to the given [Debounce] setting
+    pub fn listen_for_input_counter_changes_with(
+        &self,
+        debounce: Debounce,
+    ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
+        let listener = self.listen_for_input_counter_changes()?;
+        Ok(self.debounce(listener, debounce, |_| ()))
+    }
+
+    /// Starts measuring the time between this instance writing a property
+    /// that changes focus (E.g. GAMESCOPECTRL_BASELAYER_APPID) and Gamescope
+    /// reporting the new focus through GAMESCOPE_FOCUSED_*. Must be called on
+    /// the primary instance. Returns a join handle of the listening thread and
+    /// a receiver channel that receives every measured latency. All samples
+    /// are also collected in a histogram (See
+    /// [XWayland::get_focus_latency]). Enabling tracking again resets the
+    /// histogram.
+    pub fn enable_focus_latency_tracking(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<Duration>), Box<dyn std::error::Error>> {
+        let tracker = Arc::new(FocusLatencyTracker::default());
+        let weak = Arc::downgrade(&tracker);
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        let listener = self.listen_for_events(root, mask, move |conn, atoms, tx, event| {
+            let now = Instant::now();
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            // Tracking was disabled or enabled again with a new tracker
+            let Some(tracker) = weak.upgrade() else {
+                return Ok(());
+            };
+            if !atoms.matches(conn, event.atom, &latency::FOCUS_FEEDBACK_ATOMS)? {
+                return Ok(());
+            }
+            if let Some(latency) = tracker.on_feedback(now) {
+                tx.send(latency)?;
+            }
+
+            Ok(())
+        })?;
+        self.write_inner().focus_latency = Some(tracker);
+
+        Ok(listener)
+    }
+
+    /// Start monitoring user activity using the Gamescope input counter. Must
+    /// be called on the primary instance. Returns a join handle of the monitor
+    /// thread and a receiver channel that receives [IdleEvent::UserIdle] when
+    /// no input was received for the given timeout and
+    /// [IdleEvent::UserActive] when input is received again.
+    pub fn start_idle_monitor(
+        &self,
+        timeout: Duration,
+    ) -> Result<(JoinHandle<()>, Receiver<IdleEvent>), Box<dyn std::error::Error>> {
+        let conn = self.get_shared_connection()?;
+        let (_, counter_rx) = self.listen_for_input_counter_changes()?;
+        Ok(idle::spawn_idle_monitor(
+            conn.name(),
+            conn.options(),
+            counter_rx,
+            timeout,
+        ))
+    }
+
+    /// Registers a callback that is called with the old and new focused app id
+    /// (GAMESCOPE_FOCUSED_APP) whenever the focused app changes. The callback
+    /// runs on an internal event thread until it is unregistered with the
+    /// returned handle.
+    pub fn on_focus_changed<F>(
+        &self,
+        callback: F,
+    ) -> Result<CallbackHandle, Box<dyn std::error::Error>>
+    where
+        F: FnMut(Option<u32>, Option<u32>) + Send + 'static,
+    {
+        self.on_change(
+            GamescopeAtom::FocusedApp,
+            |xwayland| xwayland.get_focused_app(),
+            callback,
+        )
+    }
+
+    /// Registers a callback that is called with the old and new FPS limit
+    /// whenever it changes. See [XWayland::on_focus_changed].
+    pub fn on_fps_limit_changed<F>(
+        &self,
+        callback: F,
+    ) -> Result<CallbackHandle, Box<dyn std::error::Error>>
+    where
+        F: FnMut(Option<u32>, Option<u32>) + Send + 'static,
+    {
+        self.on_change(
+            GamescopeAtom::FPSLimit,
+            |xwayland| xwayland.get_fps_limit(),
+            callback,
+        )
+    }
+
+    /// Registers a callback that is called with the old and new baselayer app
+    /// id (GAMESCOPECTRL_BASELAYER_APPID) whenever it changes. See
+    /// [XWayland::on_focus_changed].
+    pub fn on_baselayer_changed<F>(
+        &self,
+        callback: F,
+    ) -> Result<CallbackHandle, Box<dyn std::error::Error>>
+    where
+        F: FnMut(Option<u32>, Option<u32>) + Send + 'static,
+    {
+        self.on_change(
+            GamescopeAtom::BaselayerAppId,
+            |xwayland| xwayland.get_baselayer_app_id(),
+            callback,
+        )
+    }
+
+    /// Registers a callback that is called with the old and new value read
+    /// with the given function whenever the given root window property changes
+    fn on_change<T, R, F>(
+        &self,
+        key: GamescopeAtom,
+        read: R,
+        callback: F,
+    ) -> Result<CallbackHandle, Box<dyn std::error::Error>>
+    where
+        T: PartialEq + Clone + Send + 'static,
+        R: Fn(&XWayland) -> Result<T, Box<dyn std::error::Error>> + Send + 'static,
+        F: FnMut(T, T) + Send + 'static,
+    {
+        let (_, rx) = self.listen_for_atom_changes(&[key])?;
+        let initial = read(self)?;
+        let xwayland = self.clone();
+        let options = self.get_connect_options();
+        Ok(callback::spawn_change_callback(
+            &self.get_name(),
+            "callback",
+            &options,
+            rx,
+            initial,
+            move || read(&xwayland),
+            callback,
+        ))
+    }
+
+    /// Tries to take control of this instance for the current process. Returns
+    /// false if another running process already has control, in which case
+    /// the caller should defer to it and can wait for it to let go using
+    /// [XWayland::listen_for_controller_changes].
+    pub fn try_acquire_control(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        coordination::try_acquire(conn.as_ref(), self.root_window_id(), std::process::id())
+    }
+
+    /// Gives up control of this instance if the current process has it
+    pub fn release_control(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        coordination::release(conn.as_ref(), self.root_window_id(), std::process::id())?;
+        Ok(())
+    }
+
+    /// Listen for changes to the VRR state of the display. Returns a join
+    /// handle of the listening thread and a receiver channel that receives
+    /// the new [VrrState] whenever one of the VRR properties changes.
+    pub fn listen_for_vrr_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<VrrState>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            if !atoms.matches(conn, event.atom, &VrrState::ATOMS)? {
+                return Ok(());
+            }
+            tx.send(VrrState::read(conn, event.window)?)?;
+
+            Ok(())
+        })
+    }
+
+    /// Returns true if Gamescope currently shows the cursor on this instance
+    pub fn is_cursor_visible(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let visible = self.get_one_xprop(root, GamescopeAtom::CursorVisibleFeedback)?;
+        Ok(visible.unwrap_or_default() != 0)
+    }
+
+    /// Listen for changes to the visibility of the cursor on this instance.
+    /// Returns a join handle of the listening thread and a receiver channel
+    /// that receives true when the cursor is shown and false when it is
+    /// hidden.
+    pub fn listen_for_cursor_visibility_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<bool>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            let key = GamescopeAtom::CursorVisibleFeedback;
+            if event.atom != atoms.resolve(conn, key)? {
+                return Ok(());
+            }
+            let visible = if event.state == Property::DELETE {
+                false
+            } else {
+                let value = x11::get_property_by_atom(conn, event.window, event.atom)?;
+                value
+                    .and_then(|value| value.first().copied())
+                    .unwrap_or_default()
+                    != 0
+            };
+            tx.send(visible)?;
+
+            Ok(())
+        })
+    }
+
+    /// Listen for Gamescope moving keyboard focus between XWayland instances.
+    /// Must be called on the primary instance. Returns a join handle of the
+    /// listening thread and a receiver channel that receives the name of the
+    /// display that gained keyboard focus (E.g. ":1").
+    pub fn listen_for_keyboard_focus_display_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<String>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            let key = GamescopeAtom::KeyboardFocusDisplay;
+            if event.atom != atoms.resolve(conn, key)? || event.state == Property::DELETE {
+                return Ok(());
+            }
+            let value =
+                x11::get_typed_property_by_atom(conn, event.window, event.atom, AtomType::String)?;
+            let display = value
+                .as_ref()
+                .and_then(|value| value.as_str())
+                .map(str::trim);
+            if let Some(display) = display.filter(|display| !display.is_empty()) {
+                tx.send(display.to_string())?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Listen for changes to the refresh rate the display is running at.
+    /// Returns a join handle of the listening thread and a receiver channel
+    /// that receives the new refresh rate in Hz whenever it changes.
+    pub fn listen_for_refresh_rate_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<u32>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            let key = GamescopeAtom::DisplayRefreshRateFeedback;
+            if event.atom != atoms.resolve(conn, key)? || event.state == Property::DELETE {
+                return Ok(());
+            }
+            let value = x11::get_property_by_atom(conn, event.window, event.atom)?;
+            if let Some(refresh_rate) = value.and_then(|value| value.first().copied()) {
+                tx.send(refresh_rate)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Listen for changes to the controlling client of this instance. Returns
+    /// a join handle of the listening thread and a receiver channel that can
+    /// be used to receive [ControllerEvent]s.
+    pub fn listen_for_controller_changes(
+        &self,
+    ) -> Result<(JoinHandle<()>, Receiver<ControllerEvent>), Box<dyn std::error::Error>> {
+        let root = self.root_window_id();
+        let mask = EventMask::PROPERTY_CHANGE;
+        self.listen_for_events(root, mask, |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            if atoms.name(conn, event.atom)?.as_ref() != coordination::CONTROLLER_PROPERTY {
+                return Ok(());
+            }
+            let pid = if event.state == Property::DELETE {
+                None
+            } else {
+                coordination::get_controller(conn, event.window)?
+            };
+            let event = match pid {
+                Some(pid) => ControllerEvent::Acquired { pid },
+                None => ControllerEvent::Released,
+            };
+            tx.send(event)?;
+
+            Ok(())
+        })
+    }
+
     /// Returns true if this instance is the primary Gamescope xwayland instance
     pub fn is_primary_instance(&self) -> Result<bool, Box<dyn std::error::Error>> {
-        let root_id = self.root_window_id;
+        let root_id = self.root_window_id();
         self.has_xprop(root_id, GamescopeAtom::KeyboardFocusDisplay)
     }
 
     /// Returns the root window ID of the xwayland instance
     pub fn get_root_window_id(&self) -> Result<u32, Box<dyn std::error::Error>> {
         let _ = self.get_connection()?;
-        Ok(self.root_window_id)
+        Ok(self.root_window_id())
+    }
+
+    /// Returns the window names of all of the given windows. The names are
+    /// requested together in a single round trip.
+    pub fn get_window_names(
+        &self,
+        window_ids: &[u32],
+    ) -> Result<Vec<Option<String>>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_window_names(conn.as_ref(), window_ids)
+    }
+
+    /// Returns a [Window] handle for the given window id on this instance
+    pub fn window(&self, window_id: u32) -> Window<'_> {
+        Window::new(self, window_id)
+    }
+
+    /// Returns a [Window] handle for the root window of this instance
+    pub fn root_window(&self) -> Result<Window<'_>, Box<dyn std::error::Error>> {
+        Ok(Window::new(self, self.get_root_window_id()?))
     }
 
     /// Returns the window name of the given window
@@ -317,7 +1779,255 @@ impl XWayland {
         window_id: u32,
     ) -> Result<Option<String>, Box<dyn std::error::Error>> {
         let conn = self.get_connection()?;
-        x11::get_window_name(conn, window_id)
+        x11::get_window_name(conn.as_ref(), window_id)
+    }
+
+    /// Returns the instance and class names from the WM_CLASS property of the
+    /// given window
+    pub fn get_window_class(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_window_class(conn.as_ref(), window_id)
+    }
+
+    /// Returns true if the given window is override-redirect. These are windows
+    /// like tooltips, menus and splash screens that bypass the window manager
+    /// and should never be treated as focusable apps.
+    pub fn is_override_redirect(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::is_override_redirect(conn.as_ref(), window_id)
+    }
+
+    /// Returns the _MOTIF_WM_HINTS decoration hints of the given window, if
+    /// it has any
+    pub fn get_motif_hints(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<MotifHints>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        let Some(values) = x11::get_motif_hints(conn.as_ref(), window_id)? else {
+            return Ok(None);
+        };
+        let value = |index: usize| values.get(index).copied().unwrap_or_default();
+
+        Ok(Some(MotifHints {
+            flags: value(0),
+            functions: value(1),
+            decorations: value(2),
+            input_mode: value(3),
+            status: value(4),
+        }))
+    }
+
+    /// Returns the window the given window is a transient for (E.g. the game
+    /// window a launcher splash dialog belongs to)
+    pub fn get_transient_for(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_transient_for(conn.as_ref(), window_id)
+    }
+
+    /// Returns the type of the given window from its _NET_WM_WINDOW_TYPE
+    /// property. The first type understood is used, since the property lists
+    /// them in order of preference. Windows without a known type are dialogs
+    /// if they are a transient for another window and normal windows
+    /// otherwise.
+    pub fn get_window_type(
+        &self,
+        window_id: u32,
+    ) -> Result<WindowType, Box<dyn std::error::Error>> {
+        let key = GamescopeAtom::NetWmWindowType.to_string();
+        let names = self.get_atom_xprop(window_id, key.as_str())?;
+        let known = names
+            .unwrap_or_default()
+            .iter()
+            .find_map(|name| name.parse().ok());
+        if let Some(window_type) = known {
+            return Ok(window_type);
+        }
+
+        match self.get_transient_for(window_id)? {
+            Some(_) => Ok(WindowType::Dialog),
+            None => Ok(WindowType::Normal),
+        }
+    }
+
+    /// Returns the most likely main window of an app out of the given windows:
+    /// the first normal window that is not a transient for another window,
+    /// otherwise the first focusable one. Windows that no longer exist are
+    /// skipped.
+    pub fn get_main_window(
+        &self,
+        window_ids: &[u32],
+    ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let mut focusable = None;
+        for window_id in window_ids {
+            let Ok(window_type) = self.get_window_type(*window_id) else {
+                continue;
+            };
+            if window_type.is_main() && self.get_transient_for(*window_id)?.is_none() {
+                return Ok(Some(*window_id));
+            }
+            if window_type.is_focusable() && focusable.is_none() {
+                focusable = Some(*window_id);
+            }
+        }
+
+        Ok(focusable)
+    }
+
+    /// Returns the leader of the window group the given window belongs to
+    pub fn get_window_group(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_window_group(conn.as_ref(), window_id)
+    }
+
+    /// Returns true if the given window belongs to the Steam client UI (Big
+    /// Picture), which Steam marks with the STEAM_BIGPICTURE property, as
+    /// opposed to a game
+    pub fn is_steam_ui_window(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
+        self.has_xprop(window_id, GamescopeAtom::Steam)
+    }
+
+    /// Returns all top-level windows on this instance that belong to the Steam
+    /// client UI. The properties of all windows are requested together in a
+    /// single round trip.
+    pub fn find_steam_ui_windows(&self) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let windows = self.get_window_children(self.get_root_window_id()?)?;
+        let values = self.get_xprop_batch(&windows, GamescopeAtom::Steam)?;
+        let steam_windows = windows
+            .into_iter()
+            .zip(values)
+            .filter_map(|(window_id, value)| value.map(|_| window_id))
+            .collect();
+
+        Ok(steam_windows)
+    }
+
+    /// Returns true if the given window belongs to a Steam Remote Play
+    /// streaming client, which Steam marks with STEAM_STREAMING_CLIENT or
+    /// STEAM_STREAMING_CLIENT_VIDEO for the window showing the stream
+    pub fn is_streaming_client(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
+        let values = self.get_xprops(
+            window_id,
+            &[
+                GamescopeAtom::SteamStreamingClient,
+                GamescopeAtom::SteamStreamingClientVideo,
+            ],
+        )?;
+        Ok(values.iter().any(Option::is_some))
+    }
+
+    /// Returns all top-level windows on this instance that belong to a Steam
+    /// Remote Play streaming client. The properties of all windows are
+    /// requested together in a single round trip per property.
+    pub fn find_streaming_client_windows(&self) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let windows = self.get_window_children(self.get_root_window_id()?)?;
+        let clients = self.get_xprop_batch(&windows, GamescopeAtom::SteamStreamingClient)?;
+        let videos = self.get_xprop_batch(&windows, GamescopeAtom::SteamStreamingClientVideo)?;
+        let streaming_windows = windows
+            .into_iter()
+            .zip(clients.into_iter().zip(videos))
+            .filter(|(_, (client, video))| client.is_some() || video.is_some())
+            .map(|(window_id, _)| window_id)
+            .collect();
+
+        Ok(streaming_windows)
+    }
+
+    /// Shows the given window as a notification (STEAM_NOTIFICATION=1) for the
+    /// given duration. Returns a join handle of the timer thread that hides it
+    /// again. Showing the same window again before the notification expired
+    /// extends it to the new duration.
+    pub fn show_notification(
+        &self,
+        window_id: u32,
+        duration: Duration,
+    ) -> Result<JoinHandle<()>, Box<dyn std::error::Error>> {
+        self.set_notification(window_id, 1)?;
+        let deadline = Instant::now() + duration;
+        self.write_inner()
+            .notification_deadlines
+            .insert(window_id, deadline);
+
+        let xwayland = self.clone();
+        let options = self.get_connect_options();
+        let handle = worker::spawn(&self.name, "notification", &options, move || {
+            thread::sleep(duration);
+            {
+                // Leave the notification to the timer of a newer one
+                let mut inner = xwayland.write_inner();
+                if inner.notification_deadlines.get(&window_id) != Some(&deadline) {
+                    return;
+                }
+                inner.notification_deadlines.remove(&window_id);
+            }
+            if let Err(err) = xwayland.set_notification(window_id, 0) {
+                log::debug!("Unable to hide notification {}: {}", window_id, err);
+            }
+        });
+
+        Ok(handle)
+    }
+
+    /// Returns all top-level windows on this instance that are currently
+    /// shown as a notification. The properties of all windows are requested
+    /// together in a single round trip.
+    pub fn find_notification_windows(&self) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let windows = self.get_window_children(self.get_root_window_id()?)?;
+        let values = self.get_xprop_batch(&windows, GamescopeAtom::SteamNotification)?;
+        let notifications = windows
+            .into_iter()
+            .zip(values)
+            .filter(|(_, value)| {
+                value
+                    .as_ref()
+                    .and_then(|value| value.first())
+                    .is_some_and(|value| *value != 0)
+            })
+            .map(|(window_id, _)| window_id)
+            .collect();
+
+        Ok(notifications)
+    }
+
+    /// Sends the given synthetic input event to the given window using the
+    /// XTEST extension. Key events move the X input focus to the window.
+    pub fn send_input(
+        &self,
+        window_id: u32,
+        event: InputEvent,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::send_input(conn.as_ref(), self.get_root_window_id()?, window_id, event)
+    }
+
+    /// Returns the top-level windows on this instance with the given app id
+    /// (STEAM_GAME). The properties of all windows are requested together in a
+    /// single round trip.
+    pub fn find_app_windows(&self, app_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+        let windows = self.get_window_children(self.get_root_window_id()?)?;
+        let values = self.get_xprop_batch(&windows, GamescopeAtom::SteamGame)?;
+        let app_windows = windows
+            .into_iter()
+            .zip(values)
+            .filter(|(_, value)| {
+                value
+                    .as_ref()
+                    .and_then(|value| value.first())
+                    .is_some_and(|value| *value == app_id)
+            })
+            .map(|(window_id, _)| window_id)
+            .collect();
+
+        Ok(app_windows)
     }
 
     /// Returns the window ids of the children of the given window
@@ -330,20 +2040,18 @@ impl XWayland {
         Ok(results.children)
     }
 
-    /// Recursively returns all child windows of the given window id
+    /// Recursively returns all child windows of the given window id in
+    /// depth-first order
     pub fn get_all_windows(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
-        let children = self.get_window_children(window_id)?;
-        if children.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        let mut leaves: Vec<u32> = Vec::new();
-        for child in children {
-            leaves.push(child);
-            leaves.append(&mut self.get_all_windows(child)?);
-        }
+        let conn = self.get_connection()?;
+        x11::get_all_windows(conn.as_ref(), window_id)
+    }
 
-        Ok(leaves)
+    /// Returns a lazy breadth-first iterator over all descendants of the given
+    /// window. Unlike [XWayland::get_all_windows], the tree is only queried as
+    /// the iterator advances.
+    pub fn iter_windows(&self, window_id: u32) -> WindowIter<'_> {
+        WindowIter::new(self, window_id)
     }
 
     /// Returns the true if the given property exists on the given window
@@ -352,57 +2060,599 @@ impl XWayland {
         window_id: u32,
         key: GamescopeAtom,
     ) -> Result<bool, Box<dyn std::error::Error>> {
-        let conn = self.get_connection()?;
-        x11::has_property(conn, window_id, key.to_string().as_str())
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::has_property_by_atom(conn.as_ref(), window_id, atom)
     }
 
-    /// Returns the value(s) of the given property on the given window
+    /// Returns the value(s) of the given property on the given window. Window
+    /// properties (E.g. GAMESCOPE_FOCUSED_WINDOW) are read like
+    /// [XWayland::get_window_xprop].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
     pub fn get_xprop(
         &self,
         window_id: u32,
         key: GamescopeAtom,
     ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        let (conn, atom) = self.resolve_atom(key)?;
+        let value = match key.atom_type() {
+            AtomType::Window => x11::get_window_property_by_atom(conn.as_ref(), window_id, atom)?,
+            _ => x11::get_property_by_atom(conn.as_ref(), window_id, atom)?,
+        };
+        if let Some(values) = value.as_ref() {
+            let decoded = match key.atom_type() {
+                AtomType::Window => Some(PropertyValue::Window(values.clone())),
+                AtomType::Cardinal => Some(PropertyValue::Cardinal(values.clone())),
+                AtomType::String => None,
+            };
+            if let Some(decoded) = decoded {
+                self.check_value(window_id, key, &decoded);
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the value of the given property on the given window. Known
+    /// Gamescope properties are decoded according to their
+    /// [crate::atoms::AtomType] and all others according to the type reported
+    /// by the X server.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn get_decoded_xprop(
+        &self,
+        window_id: u32,
+        key: &str,
+    ) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>> {
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let known = GamescopeAtom::from_property_name(key);
+        let atom = match known {
+            Some(known) => atoms.resolve(conn.as_ref(), known)?,
+            None => atoms.id(conn.as_ref(), key)?,
+        };
+        let value = x11::get_decoded_property_by_atom(conn.as_ref(), window_id, atom, key)?;
+        if let (Some(atom), Some(value)) = (known, value.as_ref()) {
+            self.check_value(window_id, atom, value);
+        }
+
+        Ok(value)
+    }
+
+    /// Waits until the decoded value of the given property on the given window
+    /// passes the given predicate, for up to the given timeout. The predicate
+    /// is called with None while the property does not exist. Returns the
+    /// value that passed. Property changes are received as events, so callers
+    /// can wait for E.g. the focused app to change without polling.
+    pub fn wait_for_xprop<P>(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        predicate: P,
+        timeout: Duration,
+    ) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>>
+    where
+        P: Fn(Option<&PropertyValue>) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+
+        // Listen before reading the current value so no change is missed
+        let mask = EventMask::PROPERTY_CHANGE;
+        let (_, rx) = self.listen_for_events(window_id, mask, move |conn, atoms, tx, event| {
+            let x11rb::protocol::Event::PropertyNotify(event) = event else {
+                return Ok(());
+            };
+            if event.atom != atoms.resolve(conn, key)? {
+                return Ok(());
+            }
+            let value = if event.state == Property::DELETE {
+                None
+            } else {
+                x11::get_typed_property_by_atom(conn, event.window, event.atom, key.atom_type())?
+            };
+            tx.send(value)?;
+
+            Ok(())
+        })?;
+
+        let mut value = self.get_decoded_xprop(window_id, key.to_string().as_str())?;
+        loop {
+            if predicate(value.as_ref()) {
+                return Ok(value);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            value = rx
+                .recv_timeout(remaining)
+                .map_err(|_| format!("Timed out waiting for {} to change", key))?;
+        }
+    }
+
+    /// Returns the value of the given property on the given window, decoded
+    /// based on the property type reported by the X server
+    pub fn get_any_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>> {
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::get_any_property_by_atom(conn.as_ref(), window_id, atom)
+    }
+
+    /// Returns the value(s) of the given property on each of the given windows.
+    /// All properties are requested together in a single round trip.
+    pub fn get_xprop_batch(
+        &self,
+        window_ids: &[u32],
+        key: GamescopeAtom,
+    ) -> Result<Vec<Option<Vec<u32>>>, Box<dyn std::error::Error>> {
+        let (conn, atom) = self.resolve_atom(key)?;
+        let requests: Vec<(u32, u32)> = window_ids.iter().map(|id| (*id, atom)).collect();
+        x11::get_atom_properties_batch(conn.as_ref(), requests.as_slice())
+    }
+
+    /// Returns the value(s) of each of the given properties on the given
+    /// window. All properties are requested together in a single round trip.
+    pub fn get_xprops(
+        &self,
+        window_id: u32,
+        keys: &[GamescopeAtom],
+    ) -> Result<Vec<Option<Vec<u32>>>, Box<dyn std::error::Error>> {
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let mut requests: Vec<(u32, u32)> = Vec::with_capacity(keys.len());
+        for key in keys {
+            requests.push((window_id, atoms.resolve(conn.as_ref(), *key)?));
+        }
+        x11::get_atom_properties_batch(conn.as_ref(), requests.as_slice())
+    }
+
+    /// Returns the window ids stored in the given property on the given
+    /// window. Properties like GAMESCOPE_FOCUSED_WINDOW may be stored with the
+    /// WINDOW or CARDINAL type and both are read.
+    pub fn get_window_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::get_window_property_by_atom(conn.as_ref(), window_id, atom)
+    }
+
+    /// Returns the names of the atoms stored in the given ATOM property (E.g.
+    /// "WM_PROTOCOLS") on the given window
+    pub fn get_atom_xprop(
+        &self,
+        window_id: u32,
+        key: &str,
+    ) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let atom = atoms.id(conn.as_ref(), key)?;
+        x11::get_atom_property_by_atom(conn.as_ref(), window_id, atom)
+    }
+
+    /// Returns the first value of the given property on the given window
+    pub fn get_one_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let value = self
+            .get_xprop(window_id, key)?
+            .unwrap_or_default()
+            .drain(..)
+            .next();
+        Ok(value)
+    }
+
+    /// Sets the given x window property value(s) on the given window
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn set_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.journal_change(
+            window_id,
+            &key.to_string(),
+            Some(PropertyValue::Cardinal(values.clone())),
+        )?;
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::set_property_by_atom(conn.as_ref(), window_id, atom, values)?;
+        self.track_focus_request(key);
+
+        Ok(())
+    }
+
+    /// Removes the given x window property from the given window
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn remove_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.journal_change(window_id, &key.to_string(), None)?;
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::remove_property_by_atom(conn.as_ref(), window_id, atom)?;
+        self.track_focus_request(key);
+
+        Ok(())
+    }
+
+    /// Returns true if the given property exists on the given window. Unlike
+    /// [XWayland::has_xprop], the property may be any atom (E.g.
+    /// "GAMESCOPE_NEW_FEATURE").
+    pub fn has_key_xprop(
+        &self,
+        window_id: u32,
+        key: impl Into<AtomKey>,
+    ) -> Result<bool, Box<dyn std::error::Error>> {
+        match key.into() {
+            AtomKey::Known(key) => self.has_xprop(window_id, key),
+            AtomKey::Custom(name) => {
+                let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+                let atom = atoms.id(conn.as_ref(), &name)?;
+                x11::has_property_by_atom(conn.as_ref(), window_id, atom)
+            }
+        }
+    }
+
+    /// Returns the value(s) of the given property on the given window like
+    /// [XWayland::get_xprop] for any atom
+    pub fn get_key_xprop(
+        &self,
+        window_id: u32,
+        key: impl Into<AtomKey>,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        match key.into() {
+            AtomKey::Known(key) => self.get_xprop(window_id, key),
+            AtomKey::Custom(name) => {
+                let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+                let atom = atoms.id(conn.as_ref(), &name)?;
+                x11::get_property_by_atom(conn.as_ref(), window_id, atom)
+            }
+        }
+    }
+
+    /// Sets the given property value(s) on the given window like
+    /// [XWayland::set_xprop] for any atom
+    pub fn set_key_xprop(
+        &self,
+        window_id: u32,
+        key: impl Into<AtomKey>,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match key.into() {
+            AtomKey::Known(key) => self.set_xprop(window_id, key, values),
+            AtomKey::Custom(name) => {
+                let new = Some(PropertyValue::Cardinal(values.clone()));
+                self.journal_change(window_id, &name, new)?;
+                let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+                let atom = atoms.id(conn.as_ref(), &name)?;
+                x11::set_property_by_atom(conn.as_ref(), window_id, atom, values)
+            }
+        }
+    }
+
+    /// Removes the given property from the given window like
+    /// [XWayland::remove_xprop] for any atom
+    pub fn remove_key_xprop(
+        &self,
+        window_id: u32,
+        key: impl Into<AtomKey>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match key.into() {
+            AtomKey::Known(key) => self.remove_xprop(window_id, key),
+            AtomKey::Custom(name) => {
+                self.journal_change(window_id, &name, None)?;
+                let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+                let atom = atoms.id(conn.as_ref(), &name)?;
+                x11::remove_property_by_atom(conn.as_ref(), window_id, atom)
+            }
+        }
+    }
+
+    /// Returns a [PropertyTransaction] to change several properties at once
+    /// with a single flush. E.g. to apply a bundle of settings:
+    /// `xwayland.transaction().set(root, GamescopeAtom::FPSLimit, vec![30]).commit()`
+    pub fn transaction(&self) -> PropertyTransaction<'_> {
+        PropertyTransaction::new(self)
+    }
+
+    /// Interns the atom with the given name, so properties outside of
+    /// [GamescopeAtom] that are used often can be read and written without
+    /// looking up the atom on every call. The atom is only interned on the X
+    /// server the first time it is requested on this connection.
+    pub fn intern(&self, name: &str) -> Result<AtomHandle, Box<dyn std::error::Error>> {
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let id = atoms.id(conn.as_ref(), name)?;
+        Ok(AtomHandle::new(id, name.into()))
+    }
+
+    /// Returns the value(s) of the given interned property on the given window
+    pub fn get_xprop_by_handle(
+        &self,
+        window_id: u32,
+        atom: &AtomHandle,
+    ) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_property_by_atom(conn.as_ref(), window_id, atom.id())
+    }
+
+    /// Returns the value of the given interned property on the given window,
+    /// decoded like [XWayland::get_decoded_xprop]
+    pub fn get_decoded_xprop_by_handle(
+        &self,
+        window_id: u32,
+        atom: &AtomHandle,
+    ) -> Result<Option<PropertyValue>, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::get_decoded_property_by_atom(conn.as_ref(), window_id, atom.id(), atom.name())
+    }
+
+    /// Sets the given interned property value(s) on the given window
+    pub fn set_xprop_by_handle(
+        &self,
+        window_id: u32,
+        atom: &AtomHandle,
+        values: Vec<u32>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.journal_change(
+            window_id,
+            atom.name(),
+            Some(PropertyValue::Cardinal(values.clone())),
+        )?;
+        let conn = self.get_connection()?;
+        x11::set_property_by_atom(conn.as_ref(), window_id, atom.id(), values)
+    }
+
+    /// Sets the given interned string property on the given window
+    pub fn set_string_xprop_by_handle(
+        &self,
+        window_id: u32,
+        atom: &AtomHandle,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let new = Some(PropertyValue::String(value.into()));
+        self.journal_change(window_id, atom.name(), new)?;
         let conn = self.get_connection()?;
-        x11::get_property(conn, window_id, key.to_string().as_str())
+        x11::set_string_property_by_atom(conn.as_ref(), window_id, atom.id(), value)
+    }
+
+    /// Removes the given interned property from the given window
+    pub fn remove_xprop_by_handle(
+        &self,
+        window_id: u32,
+        atom: &AtomHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.journal_change(window_id, atom.name(), None)?;
+        let conn = self.get_connection()?;
+        x11::remove_property_by_atom(conn.as_ref(), window_id, atom.id())
+    }
+
+    /// Remembers when a property that makes Gamescope change focus was written,
+    /// if focus latency tracking is enabled
+    pub(crate) fn track_focus_request(&self, key: GamescopeAtom) {
+        if !latency::FOCUS_REQUEST_ATOMS.contains(&key) {
+            return;
+        }
+        if let Some(tracker) = self.read_inner().focus_latency.as_ref() {
+            tracker.on_request(Instant::now());
+        }
+    }
+
+    /// Records the change of the given property to the given value in the
+    /// journal, if one is enabled
+    pub(crate) fn journal_change(
+        &self,
+        window_id: u32,
+        property: &str,
+        new: Option<PropertyValue>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(journal) = self.get_journal() else {
+            return Ok(());
+        };
+        let conn = self.get_connection()?;
+        let old = x11::get_decoded_property(conn.as_ref(), window_id, property)?;
+        journal.record(&JournalEntry {
+            display: self.get_name(),
+            window_id,
+            property: property.to_string(),
+            old,
+            new,
+        })
+    }
+
+    /// Sets the given property back to the given value, or removes it if the
+    /// value is None, without recording the change in the journal
+    pub(crate) fn restore_property(
+        &self,
+        window_id: u32,
+        property: &str,
+        value: Option<&PropertyValue>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        match value {
+            None => x11::remove_property(conn.as_ref(), window_id, property),
+            Some(PropertyValue::String(value)) => {
+                x11::set_string_property(conn.as_ref(), window_id, property, value)
+            }
+            Some(PropertyValue::Window(values)) => {
+                x11::set_window_property(conn.as_ref(), window_id, property, values.clone())
+            }
+            Some(value) => {
+                let values = value.as_u32s().ok_or("Unsupported journal value")?;
+                x11::set_property(conn.as_ref(), window_id, property, values.to_vec())
+            }
+        }
+    }
+
+    /// Returns true if the given atom is known to the X server. Gamescope
+    /// interns the atoms of all features it supports on startup, so this can
+    /// be used to detect if the running version supports a feature.
+    pub fn has_atom(&self, key: GamescopeAtom) -> Result<bool, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        x11::atom_exists(conn.as_ref(), key.to_string().as_str())
+    }
+
+    /// Returns the given string x window property of the given window
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn get_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let (conn, atom) = self.resolve_atom(key)?;
+        let value =
+            x11::get_typed_property_by_atom(conn.as_ref(), window_id, atom, AtomType::String)?;
+        Ok(value.and_then(|value| value.as_str().map(String::from)))
+    }
+
+    /// Sets the given string x window property on the given window
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self), fields(display = %self.name), err(Display))
+    )]
+    pub fn set_string_xprop(
+        &self,
+        window_id: u32,
+        key: GamescopeAtom,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let property = key.to_string();
+        self.journal_change(
+            window_id,
+            &property,
+            Some(PropertyValue::String(value.into())),
+        )?;
+        let (conn, atom) = self.resolve_atom(key)?;
+        x11::set_string_property_by_atom(conn.as_ref(), window_id, atom, value)
+    }
+
+    /// Sets the given string property (E.g. "WM_NAME") on the given window with
+    /// the given type, which is usually "STRING" for Latin-1 text or
+    /// "UTF8_STRING"
+    pub fn set_string_property(
+        &self,
+        window_id: u32,
+        key: &str,
+        kind: &str,
+        value: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let new = PropertyValue::String(value.into());
+        self.write_bytes_property(window_id, key, kind, value.as_bytes(), new)
+    }
+
+    /// Sets the given 8-bit property on the given window to the given bytes
+    /// with the given type (E.g. "STRING"). Properties that hold several
+    /// strings like WM_CLASS end each of them with a NUL byte.
+    pub fn set_bytes_property(
+        &self,
+        window_id: u32,
+        key: &str,
+        kind: &str,
+        value: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let new = PropertyValue::Bytes(value.to_vec());
+        self.write_bytes_property(window_id, key, kind, value, new)
+    }
+
+    /// Sets the WM_NAME of the given window
+    pub fn set_window_name(
+        &self,
+        window_id: u32,
+        name: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_string_property(window_id, "WM_NAME", "STRING", name)
+    }
+
+    /// Sets the instance and class names in the WM_CLASS property of the given
+    /// window
+    pub fn set_window_class(
+        &self,
+        window_id: u32,
+        instance: &str,
+        class: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let value = format!("{}\0{}\0", instance, class);
+        self.set_bytes_property(window_id, "WM_CLASS", "STRING", value.as_bytes())
+    }
+
+    /// Records the given change in the journal and writes the given bytes
+    fn write_bytes_property(
+        &self,
+        window_id: u32,
+        key: &str,
+        kind: &str,
+        value: &[u8],
+        new: PropertyValue,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.journal_change(window_id, key, Some(new))?;
+        let (conn, atoms) = self.get_shared_connection()?.get_with_atoms();
+        let atom = atoms.id(conn.as_ref(), key)?;
+        let kind = atoms.id(conn.as_ref(), kind)?;
+        x11::set_bytes_property_by_atom(conn.as_ref(), window_id, atom, kind, value)
     }
 
-    /// Returns the first value of the given property on the given window
-    pub fn get_one_xprop(
+    /// Returns the refresh rate in Hz the given app window asked Gamescope to
+    /// run the display at
+    pub fn get_requested_refresh_rate(
         &self,
         window_id: u32,
-        key: GamescopeAtom,
     ) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        let value = self
-            .get_xprop(window_id, key)?
-            .unwrap_or_default()
-            .drain(..)
-            .next();
-        Ok(value)
+        self.get_one_xprop(window_id, GamescopeAtom::RequestedRefreshRate)
     }
 
-    /// Sets the given x window property value(s) on the given window
-    pub fn set_xprop(
+    /// Asks Gamescope to run the display at the given refresh rate in Hz while
+    /// the given app window is focused
+    pub fn set_requested_refresh_rate(
         &self,
         window_id: u32,
-        key: GamescopeAtom,
-        values: Vec<u32>,
+        refresh_rate: u32,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let conn = self.get_connection()?;
-        x11::set_property(conn, window_id, key.to_string().as_str(), values)?;
-
-        Ok(())
+        self.set_xprop(
+            window_id,
+            GamescopeAtom::RequestedRefreshRate,
+            vec![refresh_rate],
+        )
     }
 
-    /// Removes the given x window property from the given window
-    pub fn remove_xprop(
+    /// Removes the requested refresh rate from the given app window
+    pub fn remove_requested_refresh_rate(
         &self,
         window_id: u32,
-        key: GamescopeAtom,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let conn = self.get_connection()?;
-        x11::remove_property(conn, window_id, key.to_string().as_str())?;
+        self.remove_xprop(window_id, GamescopeAtom::RequestedRefreshRate)
+    }
 
-        Ok(())
+    /// Returns the path to the EDID file the given app window asked Gamescope
+    /// to use
+    pub fn get_display_edid_path(
+        &self,
+        window_id: u32,
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        self.get_string_xprop(window_id, GamescopeAtom::DisplayEdidPath)
+    }
+
+    /// Asks Gamescope to use the EDID at the given path for the display while
+    /// the given app window is focused
+    pub fn set_display_edid_path(
+        &self,
+        window_id: u32,
+        path: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_string_xprop(window_id, GamescopeAtom::DisplayEdidPath, path)
     }
 
     /// Returns the process ID of the given window from the '_NET_WM_PID' atom
@@ -431,10 +2681,143 @@ impl XWayland {
     pub fn has_app_id(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
         self.has_xprop(window_id, GamescopeAtom::SteamGame)
     }
+
+    /// Captures the current root window properties, the server id and the
+    /// properties of all top-level and focusable windows. Two snapshots can be
+    /// compared using [crate::state::diff_states]. With the serde feature, the
+    /// snapshot can be serialized (E.g. to JSON for bug reports). If
+    /// [ConnectOptions::pool_size] is set, the windows are captured in
+    /// parallel on the pooled connections.
+    pub fn snapshot(&self) -> Result<SessionState, Box<dyn std::error::Error>> {
+        let conn = self.get_connection()?;
+        let root = self.root_window_id();
+        let root_properties = x11::get_properties(conn.as_ref(), root)?;
+        let root_string_properties = capture_string_properties(conn.as_ref(), root)?;
+        let server_id = root_properties
+            .get(&GamescopeAtom::XWaylandServerId.to_string())
+            .and_then(|values| values.first().copied());
+        let focusable_windows = root_properties
+            .get(&GamescopeAtom::FocusableWindows.to_string())
+            .map(|values| FocusableWindow::from_values(values))
+            .unwrap_or_default();
+        let mut window_ids = self.get_window_children(root)?;
+        for focusable in focusable_windows.iter() {
+            if !window_ids.contains(&focusable.window_id) {
+                window_ids.push(focusable.window_id);
+            }
+        }
+        let state = SessionState {
+            name: self.get_name(),
+            server_id,
+            root_properties,
+            root_string_properties,
+            focusable_windows,
+            windows: HashMap::new(),
+        };
+
+        // Without a connection pool, capture all windows on the shared
+        // connection. Otherwise split them between the pooled connections.
+        let Some(pool) = self.get_pool() else {
+            return Ok(SessionState {
+                windows: capture_windows(conn.as_ref(), &window_ids)?,
+                ..state
+            });
+        };
+
+        let options = self.get_connect_options();
+        let chunk_size = window_ids.len().div_ceil(pool.size()).max(1);
+        let windows = thread::scope(|scope| {
+            let handles: Vec<_> = window_ids
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let pool = pool.as_ref();
+                    worker::spawn_scoped(scope, &self.name, "snapshot", &options, move || {
+                        let conn = pool.get();
+                        capture_windows(&conn, chunk).map_err(|err| err.to_string())
+                    })
+                })
+                .collect();
+
+            let mut windows: HashMap<u32, WindowState> = HashMap::new();
+            for handle in handles {
+                let captured = handle
+                    .join()
+                    .map_err(|_| "Snapshot thread panicked".to_string())??;
+                windows.extend(captured);
+            }
+            Ok::<_, String>(windows)
+        })?;
+
+        Ok(SessionState { windows, ..state })
+    }
+
+    /// Returns the index Gamescope assigned to this XWayland server
+    /// (GAMESCOPE_XWAYLAND_SERVER_ID), where 0 is the primary server
+    pub fn get_server_id(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(self.root_window_id(), GamescopeAtom::XWaylandServerId)
+    }
+}
+
+/// Captures the name and properties of each of the given windows. Windows
+/// that were destroyed while capturing are left out.
+fn capture_windows(
+    conn: &X11Connection,
+    window_ids: &[u32],
+) -> Result<HashMap<u32, WindowState>, Box<dyn std::error::Error>> {
+    let mut windows: HashMap<u32, WindowState> = HashMap::new();
+    for window_id in window_ids {
+        match capture_window(conn, *window_id) {
+            Ok(window) => {
+                windows.insert(*window_id, window);
+            }
+            Err(err) if x11::is_bad_window(err.as_ref()) => {
+                log::debug!("Skipping destroyed window {} in snapshot", window_id);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(windows)
+}
+
+/// Captures the name and properties of the given window
+fn capture_window(
+    conn: &X11Connection,
+    window_id: u32,
+) -> Result<WindowState, Box<dyn std::error::Error>> {
+    let properties = x11::get_properties(conn, window_id)?;
+    let first = |atom: GamescopeAtom| {
+        properties
+            .get(&atom.to_string())
+            .and_then(|values| values.first().copied())
+    };
+
+    Ok(WindowState {
+        name: x11::get_window_name(conn, window_id)?,
+        app_id: first(GamescopeAtom::SteamGame),
+        pid: first(GamescopeAtom::NetWmPID),
+        properties,
+    })
+}
+
+/// Captures the string properties of the given window
+fn capture_string_properties(
+    conn: &X11Connection,
+    window_id: u32,
+) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let mut properties: HashMap<String, String> = HashMap::new();
+    for name in x11::list_properties(conn, window_id)? {
+        let value = x11::get_decoded_property(conn, window_id, name.as_str())?;
+        if let Some(PropertyValue::String(value)) = value {
+            properties.insert(name, value);
+        }
+    }
+
+    Ok(properties)
 }
 
 /// A Primary [XWayland] has extra window properties available for controlling
-/// Gamescope.
+/// Gamescope. It is implemented for every [GamescopeControl].
 pub trait Primary {
     /// Return a list of focusable apps
     fn get_focusable_apps(&self) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>;
@@ -462,6 +2845,16 @@ pub trait Primary {
     fn get_overlay(&self, window_id: u32) -> Result<Option<u32>, Box<dyn std::error::Error>>;
     /// Set the given window as the overlay window
     fn set_overlay(&self, window_id: u32, value: u32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Opens the given overlay window: marks it as the overlay app, shows it
+    /// as the overlay and gives it input focus, then waits up to
+    /// [OVERLAY_SWITCH_TIMEOUT] for Gamescope to focus the overlay app. If any
+    /// step fails or the overlay is not focused in time, the overlay is hidden
+    /// again and an error is returned.
+    fn open_overlay(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Closes the given overlay window by taking away its input focus and
+    /// hiding it, then waits up to [OVERLAY_SWITCH_TIMEOUT] for Gamescope to
+    /// focus another app
+    fn close_overlay(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>>;
     /// Set the given window as a notification. This should be set to "1" when some
     /// UI wants to be shown but not intercept input.
     fn set_notification(
@@ -485,8 +2878,12 @@ pub trait Primary {
     fn get_blur_mode(&self) -> Result<Option<BlurMode>, Box<dyn std::error::Error>>;
     /// Sets the Gamescope blur radius when blur is active
     fn set_blur_radius(&self, radius: u32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Gets the Gamescope blur radius
+    fn get_blur_radius(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
     /// Configures Gamescope to allow tearing or not
     fn set_allow_tearing(&self, allow: bool) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns true if tearing is allowed
+    fn get_allow_tearing(&self) -> Result<bool, Box<dyn std::error::Error>>;
     /// Returns the currently set manual app focus
     fn get_baselayer_app_id(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
     /// Focuses the app with the given app id
@@ -499,13 +2896,157 @@ pub trait Primary {
     fn set_baselayer_window(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>>;
     /// Removes the baselayer property to un-focus windows
     fn remove_baselayer_window(&self) -> Result<(), Box<dyn std::error::Error>>;
-    /// Request a screenshot from Gamescope
-    fn request_screenshot(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Request a screenshot of the given type from Gamescope
+    fn request_screenshot(
+        &self,
+        screenshot_type: ScreenshotType,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Request a debug screenshot from Gamescope, which also dumps the state
+    /// of every layer
+    fn request_debug_screenshot(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Requests a screenshot and waits up to the given timeout for Gamescope
+    /// to write it to [crate::screenshot::SCREENSHOT_DIR]. Returns the path of
+    /// the new screenshot.
+    fn take_screenshot(&self, timeout: Duration) -> Result<PathBuf, Box<dyn std::error::Error>>;
+    /// Requests a screenshot of the given type and waits like
+    /// [Primary::take_screenshot]
+    fn take_screenshot_with_type(
+        &self,
+        screenshot_type: ScreenshotType,
+        timeout: Duration,
+    ) -> Result<PathBuf, Box<dyn std::error::Error>>;
+    /// Sets whether Gamescope forces all windows to be fullscreen instead of
+    /// letting games open small floating windows
+    fn set_force_windows_fullscreen(&self, force: bool) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns true if Gamescope forces all windows to be fullscreen
+    fn get_force_windows_fullscreen(&self) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Sets the scaler and filter used to upscale games to the display
+    fn set_scaling_filter(&self, filter: ScalingFilter) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the scaler and filter used to upscale games to the display
+    fn get_scaling_filter(&self) -> Result<Option<ScalingFilter>, Box<dyn std::error::Error>>;
+    /// Sets the FSR sharpness from [FSR_SHARPNESS_MAX] (0) to
+    /// [FSR_SHARPNESS_MIN] (20)
+    fn set_fsr_sharpness(&self, sharpness: u32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the FSR sharpness
+    fn get_fsr_sharpness(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
+    /// Returns true if Gamescope is currently upscaling with FSR
+    fn is_fsr_active(&self) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Returns the last mode requested through GAMESCOPE_XWAYLAND_MODE_CONTROL
+    fn get_mode_control(&self) -> Result<Option<ModeControl>, Box<dyn std::error::Error>>;
+    /// Returns the layout of the GAMESCOPE_XWAYLAND_MODE_CONTROL payload the
+    /// running Gamescope uses. The layout is detected from the current
+    /// payload, and [ModeControlLayout::Current] is assumed if none is set.
+    fn get_mode_control_layout(&self) -> Result<ModeControlLayout, Box<dyn std::error::Error>>;
+    /// Requests the given mode for an XWayland server, encoded in the layout
+    /// the running Gamescope uses
+    fn set_mode_control(&self, mode: ModeControl) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns true if Gamescope is outputting to an external display (E.g.
+    /// when the device is docked to a TV)
+    fn is_external_display(&self) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Returns information about the display Gamescope is outputting to,
+    /// including its identity read from the EDID file Gamescope wrote
+    fn get_display_info(&self) -> Result<DisplayInfo, Box<dyn std::error::Error>>;
+    /// Returns the refresh rate in Hz the display is currently running at
+    fn get_display_refresh_rate(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
+    /// Returns the refresh rates in Hz Gamescope may dynamically switch the
+    /// display between
+    fn get_dynamic_refresh_rates(&self) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>>;
+    /// Sets the refresh rates in Hz Gamescope may dynamically switch the
+    /// display between. Dynamic refresh is disabled if the list is empty.
+    fn set_dynamic_refresh_rates(&self, rates: &[u32]) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the VRR state of the display
+    fn get_vrr_state(&self) -> Result<VrrState, Box<dyn std::error::Error>>;
+    /// Enables or disables VRR if the display supports it
+    fn set_vrr_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the HDR state of the display
+    fn get_hdr_status(&self) -> Result<HdrStatus, Box<dyn std::error::Error>>;
+    /// Enables or disables HDR output if the display supports it
+    fn set_hdr_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>>;
+    /// Sets the brightness in nits that SDR content is shown at while
+    /// outputting HDR
+    fn set_sdr_content_brightness(&self, nits: u32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Enables or disables inverse tone mapping of SDR content to HDR
+    fn set_hdr_itm_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>>;
+    /// Sets the brightness in nits of SDR content used as input for inverse
+    /// tone mapping, and the brightness in nits it is mapped to
+    fn set_hdr_itm_nits(
+        &self,
+        sdr_nits: u32,
+        target_nits: u32,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the per-channel (red, green, blue) color gain
+    fn get_color_gain(&self) -> Result<Option<[f32; 3]>, Box<dyn std::error::Error>>;
+    /// Sets the per-channel (red, green, blue) color gain
+    fn set_color_gain(&self, gain: [f32; 3]) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the per-channel (red, green, blue) gain applied in linear space
+    fn get_color_linear_gain(&self) -> Result<Option<[f32; 3]>, Box<dyn std::error::Error>>;
+    /// Sets the per-channel (red, green, blue) gain applied in linear space
+    fn set_color_linear_gain(&self, gain: [f32; 3]) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns how much of the linear gain is blended in, from 0.0 to 1.0
+    fn get_color_linear_gain_blend(&self) -> Result<Option<f32>, Box<dyn std::error::Error>>;
+    /// Sets how much of the linear gain is blended in, from 0.0 to 1.0
+    fn set_color_linear_gain_blend(&self, blend: f32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the 3x3 color matrix in row-major order
+    fn get_color_matrix(&self) -> Result<Option<[f32; 9]>, Box<dyn std::error::Error>>;
+    /// Sets the 3x3 color matrix in row-major order (E.g. to adjust
+    /// saturation)
+    fn set_color_matrix(&self, matrix: [f32; 9]) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns how far SDR content is stretched to the gamut of the display,
+    /// from 0.0 to 1.0
+    fn get_sdr_gamut_wideness(&self) -> Result<Option<f32>, Box<dyn std::error::Error>>;
+    /// Sets how far SDR content is stretched to the gamut of the display, from
+    /// 0.0 to 1.0. This increases the vibrance of colors on wide gamut
+    /// displays.
+    fn set_sdr_gamut_wideness(&self, wideness: f32) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the night mode settings
+    fn get_night_mode(&self) -> Result<Option<NightMode>, Box<dyn std::error::Error>>;
+    /// Sets the night mode settings
+    fn set_night_mode(&self, night_mode: NightMode) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the path of the 3D LUT file overriding the color pipeline
+    fn get_color_3dlut(&self) -> Result<Option<String>, Box<dyn std::error::Error>>;
+    /// Overrides the color pipeline with the 3D LUT (.cube) file at the given
+    /// path, or removes the override if None
+    fn set_color_3dlut(&self, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the path of the shaper LUT file overriding the color pipeline
+    fn get_color_shaper_lut(&self) -> Result<Option<String>, Box<dyn std::error::Error>>;
+    /// Overrides the color pipeline with the shaper (1D) LUT file at the given
+    /// path, or removes the override if None
+    fn set_color_shaper_lut(&self, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the index of the XWayland server that currently has focus,
+    /// where 0 is the primary server.
+    fn get_focus_display(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
+    /// Returns the name of the XWayland display that currently has keyboard
+    /// focus (E.g. ":1")
+    fn get_keyboard_focus_display(&self) -> Result<Option<String>, Box<dyn std::error::Error>>;
+    /// Returns the Gamescope input counter, which is increased whenever the
+    /// user provides input
+    fn get_input_counter(&self) -> Result<Option<u32>, Box<dyn std::error::Error>>;
+    /// Returns true if the running Gamescope has a built-in overview/app
+    /// switcher that can be controlled
+    fn supports_overview(&self) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Returns true if the overview is currently shown
+    fn is_overview_active(&self) -> Result<bool, Box<dyn std::error::Error>>;
+    /// Shows the built-in overview/app switcher. Errors if the running
+    /// Gamescope does not support it.
+    fn enter_overview(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Hides the built-in overview/app switcher. Errors if the running
+    /// Gamescope does not support it.
+    fn exit_overview(&self) -> Result<(), Box<dyn std::error::Error>>;
+    /// Applies every setting that is set in the given settings (E.g. a
+    /// per-game profile)
+    fn apply_settings(
+        &self,
+        settings: &GamescopeSettings,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+    /// Returns the current value of every setting in [GamescopeSettings], so
+    /// it can be restored with [Primary::apply_settings] when a game exits.
+    /// Settings that are not set are restored by removing their property.
+    fn capture_settings(&self) -> Result<GamescopeSettings, Box<dyn std::error::Error>>;
 }
 
-impl Primary for XWayland {
+impl<T: GamescopeControl + ?Sized> Primary for T {
     fn get_focusable_apps(&self) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
-        self.get_xprop(self.root_window_id, GamescopeAtom::FocusableApps)
+        self.get_xprop(self.get_root_window_id()?, GamescopeAtom::FocusableApps)
     }
 
     fn is_focusable_app(&self, window_id: u32) -> Result<bool, Box<dyn std::error::Error>> {
@@ -518,37 +3059,38 @@ impl Primary for XWayland {
     }
 
     fn get_focusable_windows(&self) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
-        self.get_xprop(self.root_window_id, GamescopeAtom::FocusableWindows)
+        self.get_xprop(self.get_root_window_id()?, GamescopeAtom::FocusableWindows)
     }
 
     fn get_focusable_window_names(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
-        let conn = self.get_connection()?;
         let focusable_windows = self.get_focusable_windows()?.unwrap_or_default();
-        let mut window_names: Vec<String> = Vec::new();
-        for window in focusable_windows {
-            let window_name = get_window_name(conn, window)?;
-            if let Some(window_name) = window_name {
-                window_names.push(window_name);
-            }
-        }
+        let window_names = self
+            .get_window_names(focusable_windows.as_slice())?
+            .into_iter()
+            .flatten()
+            .collect();
 
         Ok(window_names)
     }
 
     fn get_focused_window(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::FocusedWindow)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FocusedWindow)
     }
 
     fn get_focused_app(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::FocusedApp)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FocusedApp)
     }
 
     fn get_focused_app_gfx(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::FocusedAppGFX)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FocusedAppGFX)
     }
 
     fn set_main_app(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
-        self.set_xprop(window_id, GamescopeAtom::SteamGame, vec![OVERLAY_APP_ID])
+        self.set_xprop(
+            window_id,
+            GamescopeAtom::SteamGame,
+            vec![self.get_overlay_app_id()],
+        )
     }
 
     fn set_input_focus(
@@ -560,7 +3102,7 @@ impl Primary for XWayland {
     }
 
     fn is_overlay_focused(&self) -> Result<bool, Box<dyn std::error::Error>> {
-        Ok(self.get_focused_app()?.unwrap_or_default() == OVERLAY_APP_ID)
+        Ok(self.get_focused_app()?.unwrap_or_default() == self.get_overlay_app_id())
     }
 
     fn get_overlay(&self, window_id: u32) -> Result<Option<u32>, Box<dyn std::error::Error>> {
@@ -571,6 +3113,31 @@ impl Primary for XWayland {
         self.set_xprop(window_id, GamescopeAtom::SteamOverlay, vec![value])
     }
 
+    fn open_overlay(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let result = self
+            .set_main_app(window_id)
+            .and_then(|_| self.set_overlay(window_id, 1))
+            .and_then(|_| self.set_input_focus(window_id, 1))
+            .and_then(|_| wait_for_overlay_focus(self, true));
+        if result.is_err() {
+            // Don't leave a half open overlay intercepting input
+            if let Err(err) = self
+                .set_input_focus(window_id, 0)
+                .and_then(|_| self.set_overlay(window_id, 0))
+            {
+                log::warn!("Unable to hide overlay window {}: {}", window_id, err);
+            }
+        }
+
+        result
+    }
+
+    fn close_overlay(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_input_focus(window_id, 0)?;
+        self.set_overlay(window_id, 0)?;
+        wait_for_overlay_focus(self, false)
+    }
+
     fn set_notification(
         &self,
         window_id: u32,
@@ -588,11 +3155,15 @@ impl Primary for XWayland {
     }
 
     fn set_fps_limit(&self, fps: u32) -> Result<(), Box<dyn std::error::Error>> {
-        self.set_xprop(self.root_window_id, GamescopeAtom::FPSLimit, vec![fps])
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::FPSLimit,
+            vec![fps],
+        )
     }
 
     fn get_fps_limit(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::FPSLimit)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FPSLimit)
     }
 
     fn set_blur_mode(&self, mode: BlurMode) -> Result<(), Box<dyn std::error::Error>> {
@@ -601,11 +3172,15 @@ impl Primary for XWayland {
             BlurMode::Cond => 1,
             BlurMode::Always => 2,
         };
-        self.set_xprop(self.root_window_id, GamescopeAtom::FPSLimit, vec![mode])
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::BlurMode,
+            vec![mode],
+        )
     }
 
     fn get_blur_mode(&self) -> Result<Option<BlurMode>, Box<dyn std::error::Error>> {
-        let mode = self.get_one_xprop(self.root_window_id, GamescopeAtom::BlurMode)?;
+        let mode = self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::BlurMode)?;
         if mode.is_none() {
             return Ok(None);
         }
@@ -619,55 +3194,552 @@ impl Primary for XWayland {
     }
 
     fn set_blur_radius(&self, radius: u32) -> Result<(), Box<dyn std::error::Error>> {
-        self.set_xprop(self.root_window_id, GamescopeAtom::BlurRadius, vec![radius])
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::BlurRadius,
+            vec![radius],
+        )
+    }
+
+    fn get_blur_radius(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::BlurRadius)
     }
 
     fn set_allow_tearing(&self, allow: bool) -> Result<(), Box<dyn std::error::Error>> {
         let value = if allow { 1 } else { 0 };
         self.set_xprop(
-            self.root_window_id,
+            self.get_root_window_id()?,
             GamescopeAtom::AllowTearing,
             vec![value],
         )
     }
 
+    fn get_allow_tearing(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let allow = self.get_one_xprop(root, GamescopeAtom::AllowTearing)?;
+        Ok(allow.unwrap_or_default() != 0)
+    }
+
     fn get_baselayer_app_id(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::BaselayerAppId)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::BaselayerAppId)
     }
 
     fn set_baselayer_app_id(&self, app_id: u32) -> Result<(), Box<dyn std::error::Error>> {
         self.set_xprop(
-            self.root_window_id,
+            self.get_root_window_id()?,
             GamescopeAtom::BaselayerAppId,
             vec![app_id],
         )
     }
 
     fn remove_baselayer_app_id(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.remove_xprop(self.root_window_id, GamescopeAtom::BaselayerAppId)
+        self.remove_xprop(self.get_root_window_id()?, GamescopeAtom::BaselayerAppId)
     }
 
     fn get_baselayer_window(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
-        self.get_one_xprop(self.root_window_id, GamescopeAtom::BaselayerWindow)
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::BaselayerWindow)
     }
 
     fn set_baselayer_window(&self, window_id: u32) -> Result<(), Box<dyn std::error::Error>> {
         self.set_xprop(
-            self.root_window_id,
+            self.get_root_window_id()?,
             GamescopeAtom::BaselayerWindow,
             vec![window_id],
         )
     }
 
     fn remove_baselayer_window(&self) -> Result<(), Box<dyn std::error::Error>> {
-        self.remove_xprop(self.root_window_id, GamescopeAtom::BaselayerWindow)
+        self.remove_xprop(self.get_root_window_id()?, GamescopeAtom::BaselayerWindow)
     }
 
-    fn request_screenshot(&self) -> Result<(), Box<dyn std::error::Error>> {
+    fn request_screenshot(
+        &self,
+        screenshot_type: ScreenshotType,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         self.set_xprop(
-            self.root_window_id,
+            self.get_root_window_id()?,
             GamescopeAtom::RequestScreenshot,
+            vec![screenshot_type as u32],
+        )
+    }
+
+    fn request_debug_screenshot(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::DebugRequestScreenshot,
             vec![1],
         )
     }
+
+    fn take_screenshot(&self, timeout: Duration) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        self.take_screenshot_with_type(ScreenshotType::default(), timeout)
+    }
+
+    fn take_screenshot_with_type(
+        &self,
+        screenshot_type: ScreenshotType,
+        timeout: Duration,
+    ) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let dir = Path::new(screenshot::SCREENSHOT_DIR);
+        let existing = screenshot::list_screenshots(dir);
+        self.request_screenshot(screenshot_type)?;
+        screenshot::wait_for_screenshot(dir, &existing, timeout)
+    }
+
+    fn set_force_windows_fullscreen(&self, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(
+            root,
+            GamescopeAtom::ForceWindowsFullscreen,
+            vec![force as u32],
+        )
+    }
+
+    fn get_force_windows_fullscreen(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let force = self.get_one_xprop(root, GamescopeAtom::ForceWindowsFullscreen)?;
+        Ok(force.unwrap_or_default() != 0)
+    }
+
+    fn set_scaling_filter(&self, filter: ScalingFilter) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::ScalingFilter, vec![filter as u32])
+    }
+
+    fn get_scaling_filter(&self) -> Result<Option<ScalingFilter>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let Some(filter) = self.get_one_xprop(root, GamescopeAtom::ScalingFilter)? else {
+            return Ok(None);
+        };
+
+        match filter {
+            0 => Ok(Some(ScalingFilter::Linear)),
+            1 => Ok(Some(ScalingFilter::Nearest)),
+            2 => Ok(Some(ScalingFilter::Integer)),
+            3 => Ok(Some(ScalingFilter::Fsr)),
+            4 => Ok(Some(ScalingFilter::Nis)),
+            5 => Ok(Some(ScalingFilter::Pixel)),
+            _ => Ok(None),
+        }
+    }
+
+    fn set_fsr_sharpness(&self, sharpness: u32) -> Result<(), Box<dyn std::error::Error>> {
+        if sharpness > FSR_SHARPNESS_MIN {
+            return Err(format!(
+                "FSR sharpness must be between {} and {}",
+                FSR_SHARPNESS_MAX, FSR_SHARPNESS_MIN
+            )
+            .into());
+        }
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::FSRSharpness, vec![sharpness])
+    }
+
+    fn get_fsr_sharpness(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FSRSharpness)
+    }
+
+    fn is_fsr_active(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let feedback = self.get_one_xprop(root, GamescopeAtom::FSRFeedback)?;
+        Ok(feedback.unwrap_or_default() != 0)
+    }
+
+    fn get_mode_control(&self) -> Result<Option<ModeControl>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let values = self.get_xprop(root, GamescopeAtom::XWaylandModeControl)?;
+        Ok(values.and_then(|values| ModeControl::decode(&values)))
+    }
+
+    fn get_mode_control_layout(&self) -> Result<ModeControlLayout, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let values = self.get_xprop(root, GamescopeAtom::XWaylandModeControl)?;
+        let layout = values.and_then(|values| ModeControlLayout::from_value_count(values.len()));
+        Ok(layout.unwrap_or_default())
+    }
+
+    fn set_mode_control(&self, mode: ModeControl) -> Result<(), Box<dyn std::error::Error>> {
+        let layout = self.get_mode_control_layout()?;
+        let root = self.get_root_window_id()?;
+        self.set_xprop(
+            root,
+            GamescopeAtom::XWaylandModeControl,
+            mode.encode(layout),
+        )
+    }
+
+    fn is_external_display(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let external = self.get_one_xprop(root, GamescopeAtom::DisplayIsExternal)?;
+        Ok(external.unwrap_or_default() != 0)
+    }
+
+    fn get_display_info(&self) -> Result<DisplayInfo, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let edid_path = self.get_string_xprop(root, GamescopeAtom::DisplayEdidPath)?;
+        let edid = edid_path.as_ref().and_then(|path| {
+            Edid::read(Path::new(path))
+                .map_err(|err| log::debug!("Unable to read EDID: {}", err))
+                .ok()
+        });
+
+        Ok(DisplayInfo {
+            is_external: self.is_external_display()?,
+            edid_path,
+            edid,
+        })
+    }
+
+    fn get_display_refresh_rate(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.get_one_xprop(root, GamescopeAtom::DisplayRefreshRateFeedback)
+    }
+
+    fn get_dynamic_refresh_rates(&self) -> Result<Option<Vec<u32>>, Box<dyn std::error::Error>> {
+        self.get_xprop(self.get_root_window_id()?, GamescopeAtom::DynamicRefresh)
+    }
+
+    fn set_dynamic_refresh_rates(&self, rates: &[u32]) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        if rates.is_empty() {
+            return self.remove_xprop(root, GamescopeAtom::DynamicRefresh);
+        }
+        self.set_xprop(root, GamescopeAtom::DynamicRefresh, rates.to_vec())
+    }
+
+    fn get_vrr_state(&self) -> Result<VrrState, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let flag = |key: GamescopeAtom| -> Result<bool, Box<dyn std::error::Error>> {
+            Ok(self.get_one_xprop(root, key)?.unwrap_or_default() != 0)
+        };
+
+        Ok(VrrState {
+            capable: flag(GamescopeAtom::VRRCapable)?,
+            enabled: flag(GamescopeAtom::VRREnabled)?,
+            active: flag(GamescopeAtom::VRRFeedback)?,
+        })
+    }
+
+    fn set_vrr_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::VRREnabled, vec![enabled as u32])
+    }
+
+    fn get_hdr_status(&self) -> Result<HdrStatus, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let flag = |key: GamescopeAtom| -> Result<bool, Box<dyn std::error::Error>> {
+            Ok(self.get_one_xprop(root, key)?.unwrap_or_default() != 0)
+        };
+
+        Ok(HdrStatus {
+            supported: flag(GamescopeAtom::DisplaySupportsHDR)?,
+            enabled: flag(GamescopeAtom::DisplayHDREnabled)?,
+            active: flag(GamescopeAtom::HDROutputFeedback)?,
+            sdr_content_nits: self.get_one_xprop(root, GamescopeAtom::SDROnHDRContentBrightness)?,
+            itm_enabled: flag(GamescopeAtom::HDRItmEnable)?,
+            itm_sdr_nits: self.get_one_xprop(root, GamescopeAtom::HDRItmSDRNits)?,
+            itm_target_nits: self.get_one_xprop(root, GamescopeAtom::HDRItmTargetNits)?,
+        })
+    }
+
+    fn set_hdr_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::DisplayHDREnabled, vec![enabled as u32])
+    }
+
+    fn set_sdr_content_brightness(&self, nits: u32) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::SDROnHDRContentBrightness, vec![nits])
+    }
+
+    fn set_hdr_itm_enabled(&self, enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::HDRItmEnable, vec![enabled as u32])
+    }
+
+    fn set_hdr_itm_nits(
+        &self,
+        sdr_nits: u32,
+        target_nits: u32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.set_xprop(root, GamescopeAtom::HDRItmSDRNits, vec![sdr_nits])?;
+        self.set_xprop(root, GamescopeAtom::HDRItmTargetNits, vec![target_nits])
+    }
+
+    fn get_color_gain(&self) -> Result<Option<[f32; 3]>, Box<dyn std::error::Error>> {
+        let values = self.get_xprop(self.get_root_window_id()?, GamescopeAtom::ColorGain)?;
+        Ok(values.and_then(|values| color::decode_floats(&values)))
+    }
+
+    fn set_color_gain(&self, gain: [f32; 3]) -> Result<(), Box<dyn std::error::Error>> {
+        let values = color::encode_floats(&gain);
+        self.set_xprop(self.get_root_window_id()?, GamescopeAtom::ColorGain, values)
+    }
+
+    fn get_color_linear_gain(&self) -> Result<Option<[f32; 3]>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let values = self.get_xprop(root, GamescopeAtom::ColorLinearGain)?;
+        Ok(values.and_then(|values| color::decode_floats(&values)))
+    }
+
+    fn set_color_linear_gain(&self, gain: [f32; 3]) -> Result<(), Box<dyn std::error::Error>> {
+        let values = color::encode_floats(&gain);
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::ColorLinearGain,
+            values,
+        )
+    }
+
+    fn get_color_linear_gain_blend(&self) -> Result<Option<f32>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let value = self.get_one_xprop(root, GamescopeAtom::ColorLinearGainBlend)?;
+        Ok(value.map(color::decode_float))
+    }
+
+    fn set_color_linear_gain_blend(&self, blend: f32) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::ColorLinearGainBlend,
+            vec![color::encode_float(blend)],
+        )
+    }
+
+    fn get_color_matrix(&self) -> Result<Option<[f32; 9]>, Box<dyn std::error::Error>> {
+        let values = self.get_xprop(self.get_root_window_id()?, GamescopeAtom::ColorMatrix)?;
+        Ok(values.and_then(|values| color::decode_floats(&values)))
+    }
+
+    fn set_color_matrix(&self, matrix: [f32; 9]) -> Result<(), Box<dyn std::error::Error>> {
+        let values = color::encode_floats(&matrix);
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::ColorMatrix,
+            values,
+        )
+    }
+
+    fn get_sdr_gamut_wideness(&self) -> Result<Option<f32>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let value = self.get_one_xprop(root, GamescopeAtom::ColorSDRGamutWideness)?;
+        Ok(value.map(color::decode_float))
+    }
+
+    fn set_sdr_gamut_wideness(&self, wideness: f32) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::ColorSDRGamutWideness,
+            vec![color::encode_float(wideness)],
+        )
+    }
+
+    fn get_night_mode(&self) -> Result<Option<NightMode>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let values = self.get_xprop(root, GamescopeAtom::ColorNightMode)?;
+        Ok(values.and_then(|values| NightMode::decode(&values)))
+    }
+
+    fn set_night_mode(&self, night_mode: NightMode) -> Result<(), Box<dyn std::error::Error>> {
+        self.set_xprop(
+            self.get_root_window_id()?,
+            GamescopeAtom::ColorNightMode,
+            night_mode.encode(),
+        )
+    }
+
+    fn get_color_3dlut(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.get_string_xprop(root, GamescopeAtom::Color3DLutOverride)
+    }
+
+    fn set_color_3dlut(&self, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        match path {
+            Some(path) => self.set_string_xprop(root, GamescopeAtom::Color3DLutOverride, path),
+            None => self.remove_xprop(root, GamescopeAtom::Color3DLutOverride),
+        }
+    }
+
+    fn get_color_shaper_lut(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        self.get_string_xprop(root, GamescopeAtom::ColorShaperLutOverride)
+    }
+
+    fn set_color_shaper_lut(&self, path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        match path {
+            Some(path) => self.set_string_xprop(root, GamescopeAtom::ColorShaperLutOverride, path),
+            None => self.remove_xprop(root, GamescopeAtom::ColorShaperLutOverride),
+        }
+    }
+
+    fn get_focus_display(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::FocusDisplay)
+    }
+
+    fn get_keyboard_focus_display(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let root = self.get_root_window_id()?;
+        let display = self.get_string_xprop(root, GamescopeAtom::KeyboardFocusDisplay)?;
+        Ok(display
+            .map(|display| display.trim().to_string())
+            .filter(|display| !display.is_empty()))
+    }
+
+    fn get_input_counter(&self) -> Result<Option<u32>, Box<dyn std::error::Error>> {
+        self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::InputCounter)
+    }
+
+    fn supports_overview(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        self.has_atom(GamescopeAtom::Overview)
+    }
+
+    fn is_overview_active(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        // Reading the property would intern the atom and break detection
+        if !self.supports_overview()? {
+            return Ok(false);
+        }
+        let value = self.get_one_xprop(self.get_root_window_id()?, GamescopeAtom::Overview)?;
+        Ok(value.unwrap_or_default() != 0)
+    }
+
+    fn enter_overview(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.supports_overview()? {
+            return Err("Overview is not supported by this Gamescope".into());
+        }
+        self.set_xprop(self.get_root_window_id()?, GamescopeAtom::Overview, vec![1])
+    }
+
+    fn exit_overview(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.supports_overview()? {
+            return Err("Overview is not supported by this Gamescope".into());
+        }
+        self.set_xprop(self.get_root_window_id()?, GamescopeAtom::Overview, vec![0])
+    }
+
+    fn apply_settings(
+        &self,
+        settings: &GamescopeSettings,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        settings.apply(self)
+    }
+
+    fn capture_settings(&self) -> Result<GamescopeSettings, Box<dyn std::error::Error>> {
+        GamescopeSettings::all().capture(self)
+    }
+}
+
+/// Waits up to [OVERLAY_SWITCH_TIMEOUT] until Gamescope reports the overlay
+/// app as focused or, if focused is false, another app
+fn wait_for_overlay_focus<C>(primary: &C, focused: bool) -> Result<(), Box<dyn std::error::Error>>
+where
+    C: Primary + ?Sized,
+{
+    let deadline = Instant::now() + OVERLAY_SWITCH_TIMEOUT;
+    loop {
+        if primary.is_overlay_focused()? == focused {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            let action = if focused { "open" } else { "close" };
+            return Err(format!("Timed out waiting for the overlay to {}", action).into());
+        }
+        thread::sleep(OVERLAY_POLL_INTERVAL);
+    }
+}
+
+/// Validates the given property value of the instance with the given state if
+/// strict validation is enabled
+fn check_value(
+    inner: &RwLock<Inner>,
+    window_id: u32,
+    property: GamescopeAtom,
+    value: &PropertyValue,
+) {
+    let enabled = inner
+        .read()
+        .map(|inner| !inner.validators.is_empty())
+        .unwrap_or_default();
+    if !enabled {
+        return;
+    }
+    let Some(reason) = validation::validate(property, value) else {
+        return;
+    };
+    log::warn!(
+        "Suspicious value of {} on window {}: {}",
+        property,
+        window_id,
+        reason
+    );
+
+    let suspicious = SuspiciousValue {
+        window_id,
+        property,
+        value: value.clone(),
+        reason,
+    };
+    inner
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .validators
+        .retain(|tx| tx.send(suspicious.clone()).is_ok());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xwayland_clones_share_state() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<XWayland>();
+
+        let xwayland = XWayland::new(":0".into());
+        let clone = xwayland.clone();
+        clone.set_overlay_app_id(1234);
+        assert_eq!(xwayland.get_overlay_app_id(), 1234);
+        assert!(xwayland.ptr_eq(&clone));
+
+        clone.set_metadata(620u32);
+        assert_eq!(xwayland.get_metadata::<u32>(), Some(620));
+    }
+
+    #[test]
+    fn test_window_type_classification() {
+        let splash: WindowType = "_NET_WM_WINDOW_TYPE_SPLASH".parse().unwrap();
+        assert_eq!(splash, WindowType::Splash);
+        assert!(!splash.is_focusable());
+        assert!(WindowType::Dialog.is_focusable());
+        assert!(!WindowType::Dialog.is_main());
+        assert!(WindowType::Normal.is_main());
+        assert!("_KDE_NET_WM_WINDOW_TYPE_OVERRIDE"
+            .parse::<WindowType>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_open_overlay_waits_for_focus() {
+        use crate::fake::{FakeXWayland, FAKE_ROOT_WINDOW_ID};
+
+        let primary = FakeXWayland::new_primary(":0");
+        let window_id = primary.spawn_app(OVERLAY_APP_ID, "Steam").unwrap();
+
+        // Gamescope never focuses the overlay, so it is hidden again
+        assert!(primary.open_overlay(window_id).is_err());
+        assert_eq!(primary.get_overlay(window_id).unwrap(), Some(0));
+
+        let gamescope = primary.clone();
+        let focus = thread::spawn(move || {
+            thread::sleep(OVERLAY_POLL_INTERVAL * 2);
+            let key = GamescopeAtom::FocusedApp;
+            gamescope
+                .simulate_property(FAKE_ROOT_WINDOW_ID, key, vec![OVERLAY_APP_ID])
+                .unwrap();
+        });
+        primary.open_overlay(window_id).unwrap();
+        focus.join().unwrap();
+        assert_eq!(primary.get_overlay(window_id).unwrap(), Some(1));
+        assert!(primary.is_overlay_focused().unwrap());
+    }
 }